@@ -0,0 +1,38 @@
+// Blocky terrain shader: repeats each face's atlas tile across greedy-merged quads
+// UV_0 counts tile repeats across the quad, UV_1 is the tile's corner in the atlas
+
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    forward_io::{VertexOutput, FragmentOutput},
+}
+
+struct AtlasTilingUniforms {
+    tile_extent: vec2<f32>,
+    alpha_cutoff: f32,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> tiling: AtlasTilingUniforms;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var atlas_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var atlas_sampler: sampler;
+
+fn sample_atlas(repeat_uv: vec2<f32>, tile_origin: vec2<f32>) -> vec4<f32> {
+    let atlas_uv = tile_origin + fract(repeat_uv) * tiling.tile_extent;
+    // Gradients of the unwrapped UVs, so the mip level does not jump where the tile repeats
+    let ddx = dpdx(repeat_uv) * tiling.tile_extent;
+    let ddy = dpdy(repeat_uv) * tiling.tile_extent;
+    return textureSampleGrad(atlas_texture, atlas_sampler, atlas_uv, ddx, ddy);
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    // Vertex colors (AO) and the base color come from the standard material
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color *= sample_atlas(in.uv, in.uv_b);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
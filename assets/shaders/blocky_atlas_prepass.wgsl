@@ -0,0 +1,51 @@
+// Prepass and shadows for blocky terrain: cuts out transparent texels of the repeated atlas tile
+
+#import bevy_pbr::prepass_io::VertexOutput
+#ifdef PREPASS_FRAGMENT
+#import bevy_pbr::prepass_io::FragmentOutput
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+#import bevy_pbr::pbr_prepass_functions::calculate_motion_vector
+#endif
+
+struct AtlasTilingUniforms {
+    tile_extent: vec2<f32>,
+    alpha_cutoff: f32,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> tiling: AtlasTilingUniforms;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var atlas_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var atlas_sampler: sampler;
+
+fn discard_transparent(in: VertexOutput) {
+    let atlas_uv = in.uv_b + fract(in.uv) * tiling.tile_extent;
+    let ddx = dpdx(in.uv) * tiling.tile_extent;
+    let ddy = dpdy(in.uv) * tiling.tile_extent;
+    if textureSampleGrad(atlas_texture, atlas_sampler, atlas_uv, ddx, ddy).a < tiling.alpha_cutoff {
+        discard;
+    }
+}
+
+#ifdef PREPASS_FRAGMENT
+@fragment
+fn fragment(in: VertexOutput) -> FragmentOutput {
+    discard_transparent(in);
+
+    var out: FragmentOutput;
+#ifdef NORMAL_PREPASS
+    out.normal = vec4(in.world_normal * 0.5 + vec3(0.5), 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    out.motion_vector = calculate_motion_vector(in.world_position, in.previous_world_position);
+#endif
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.frag_depth = in.unclipped_depth;
+#endif
+    return out;
+}
+#else
+@fragment
+fn fragment(in: VertexOutput) {
+    discard_transparent(in);
+}
+#endif
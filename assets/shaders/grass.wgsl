@@ -54,6 +54,8 @@ struct Vertex {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
+    // Biome tint of the blade
+    @location(5) tint: vec4<f32>,
 };
 
 struct VertexOutput {
@@ -89,8 +91,8 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     out.clip_position = mesh_position_local_to_clip(model, local_pos);
     out.uv = vertex.uv;
     
-    // Gradient color from base to tip
-    out.color = mix(material.tip_color, material.base_color, vertex.uv.y);
+    // Gradient color from base to tip, tinted by the biome
+    out.color = mix(material.tip_color, material.base_color, vertex.uv.y) * vertex.tint;
 
     return out;
 }
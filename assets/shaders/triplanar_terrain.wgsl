@@ -9,6 +9,8 @@ struct TriplanarUniforms {
     blend_sharpness: f32,
     normal_intensity: f32,
     parallax_scale: f32,
+    atlas_tile_extent: f32,
+    atlas_columns: u32,
 };
 
 @group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> uniforms: TriplanarUniforms;
@@ -30,6 +32,9 @@ struct TriplanarUniforms {
 @group(#{MATERIAL_BIND_GROUP}) @binding(8) var dirt_albedo: texture_2d<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(9) var dirt_normal: texture_2d<f32>;
 
+// Block atlas, ore tiles are drawn over the materials
+@group(#{MATERIAL_BIND_GROUP}) @binding(10) var atlas_texture: texture_2d<f32>;
+
 fn compute_uv(world_coord: vec2<f32>) -> vec2<f32> {
     return fract(world_coord / uniforms.tex_scale);
 }
@@ -132,6 +137,27 @@ fn sample_normal_tp(uv_yz: vec2<f32>, uv_xz: vec2<f32>, uv_xy: vec2<f32>, w: vec
     return normalize(n0 * w.x + n1 * w.y + n2 * w.z);
 }
 
+// Sky and block light baked into UV_0 by the mesher, 0 dark to 1 full. Each level is 80%
+// as bright as the next; block light is warm. Matches lighting::light_color.
+fn voxel_light(levels: vec2<f32>) -> vec3<f32> {
+    let sky = pow(0.8, (1.0 - levels.x) * 15.0);
+    let block = pow(0.8, (1.0 - levels.y) * 15.0);
+    return max(vec3(sky), block * vec3(1.0, 0.85, 0.6));
+}
+
+// Triplanar sample of one atlas tile, repeating once per voxel. dx and dy are the screen
+// space derivatives of world_pos, so the mip level does not jump where the tile repeats.
+fn sample_atlas_tp(world_pos: vec3<f32>, dx: vec3<f32>, dy: vec3<f32>, w: vec3<f32>, tile: u32) -> vec4<f32> {
+    let columns = f32(uniforms.atlas_columns);
+    let padding = (1.0 / columns - uniforms.atlas_tile_extent) * 0.5;
+    let origin = vec2(f32(tile % uniforms.atlas_columns), f32(tile / uniforms.atlas_columns)) / columns + padding;
+    let e = uniforms.atlas_tile_extent;
+    let x = textureSampleGrad(atlas_texture, tex_sampler, origin + fract(world_pos.yz) * e, dx.yz * e, dy.yz * e);
+    let y = textureSampleGrad(atlas_texture, tex_sampler, origin + fract(world_pos.xz) * e, dx.xz * e, dy.xz * e);
+    let z = textureSampleGrad(atlas_texture, tex_sampler, origin + fract(world_pos.xy) * e, dx.xy * e, dy.xy * e);
+    return x * w.x + y * w.y + z * w.z;
+}
+
 fn get_base_material(atlas_idx: i32) -> i32 {
     if (atlas_idx == 0) { return 0; }
     if (atlas_idx == 2 || atlas_idx == 3) { return 1; }
@@ -189,6 +215,16 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
         final_normal += sample_normal_tp(uv_yz, uv_xz, uv_xy, weights, world_normal, 3, view_dir) * w.w;
     }
     
+#ifdef VERTEX_UVS_B
+    // Ores: UV_1 holds the overlay weight and the atlas tile, drawn over the blend
+    let pos_dx = dpdx(world_pos);
+    let pos_dy = dpdy(world_pos);
+    if (in.uv_b.x > 0.001) {
+        let ore = sample_atlas_tp(world_pos, pos_dx, pos_dy, weights, u32(in.uv_b.y + 0.5));
+        albedo = mix(albedo, ore, smoothstep(0.0, 0.6, in.uv_b.x));
+    }
+#endif
+
     albedo = albedo * uniforms.base_color;
     let blended_n = normalize(final_normal);
 
@@ -199,5 +235,5 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     let ndoth = max(dot(blended_n, half_dir), 0.0);
     
     let lit = albedo.rgb * (0.35 + ndotl * 0.65) + vec3(pow(ndoth, 32.0) * 0.15);
-    return vec4(lit, albedo.a);
+    return vec4(lit * voxel_light(in.uv), albedo.a);
 }
@@ -0,0 +1,47 @@
+// Voxel water shader: ripples the surface normal, scrolling along the flow direction
+// UV_0 is the world position in the plane of the face, UV_1 the flow in that plane
+
+#import bevy_pbr::{
+    mesh_view_bindings::globals,
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+    forward_io::{VertexOutput, FragmentOutput},
+}
+
+struct WaterFlowUniforms {
+    flow_speed: f32,
+    ripple_scale: f32,
+    ripple_strength: f32,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> water: WaterFlowUniforms;
+
+// Slope of two crossing wave trains at a point of the face plane
+fn ripple_slope(p: vec2<f32>, time: f32) -> vec2<f32> {
+    let a = p * water.ripple_scale;
+    let b = vec2<f32>(a.x * 0.8 - a.y * 0.6, a.x * 0.6 + a.y * 0.8) * 1.7;
+    return vec2<f32>(cos(a.x + time), cos(a.y - time * 0.7))
+        + vec2<f32>(cos(b.x - time * 1.3), cos(b.y + time * 0.9)) * 0.5;
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+    // Moving the sample point against the flow makes the pattern travel with the water
+    let flow = in.uv_b;
+    let p = in.uv - flow * globals.time * water.flow_speed;
+    let slope = ripple_slope(p, globals.time) * water.ripple_strength;
+
+    // Tilt the normal within the face plane, built from the face's tangent directions
+    let n = pbr_input.N;
+    let helper = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(n.y) > 0.9);
+    let t = normalize(cross(helper, n));
+    let b = cross(n, t);
+    pbr_input.N = normalize(n - t * slope.x - b * slope.y);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
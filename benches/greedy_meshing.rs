@@ -0,0 +1,47 @@
+use bevy::math::IVec3;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use voxel_builder::voxel::generation::{NoiseGenerator, WorldGenerator};
+use voxel_builder::voxel::meshing::{generate_chunk_mesh, generate_chunk_mesh_greedy, ChunkMeshResult, ChunkSnapshot};
+use voxel_builder::voxel::world::{VoxelWorld, WorldSeed};
+
+/// Snapshots of a column of generated chunks with all their neighbors loaded
+fn terrain_snapshots() -> Vec<ChunkSnapshot> {
+    let size = IVec3::new(3, 4, 3);
+    let generator = WorldGenerator::new(NoiseGenerator::new(WorldSeed(42)));
+    let mut world = VoxelWorld::new(size);
+    for x in 0..size.x {
+        for y in 0..size.y {
+            for z in 0..size.z {
+                world.insert_chunk(generator.generate(IVec3::new(x, y, z)));
+            }
+        }
+    }
+
+    (0..size.y)
+        .filter_map(|y| ChunkSnapshot::capture(&world, IVec3::new(1, y, 1)))
+        .collect()
+}
+
+fn mesh_all(snapshots: &[ChunkSnapshot], mesher: fn(&ChunkSnapshot) -> ChunkMeshResult) -> usize {
+    snapshots
+        .iter()
+        .map(|snapshot| mesher(snapshot).solid.positions.len())
+        .sum()
+}
+
+fn greedy_meshing(c: &mut Criterion) {
+    let snapshots = terrain_snapshots();
+    println!(
+        "solid vertices: per face {}, greedy {}",
+        mesh_all(&snapshots, generate_chunk_mesh),
+        mesh_all(&snapshots, generate_chunk_mesh_greedy)
+    );
+
+    let mut group = c.benchmark_group("blocky_meshing");
+    group.bench_function("per_face", |b| b.iter(|| mesh_all(black_box(&snapshots), generate_chunk_mesh)));
+    group.bench_function("greedy", |b| b.iter(|| mesh_all(black_box(&snapshots), generate_chunk_mesh_greedy)));
+    group.finish();
+}
+
+criterion_group!(benches, greedy_meshing);
+criterion_main!(benches);
@@ -6,10 +6,11 @@ use bevy::pbr::{DistanceFog, FogFalloff};
 use bevy::post_process::bloom::Bloom;
 use bevy::window::{CursorGrabMode, CursorOptions};
 use bevy_water::ImageReformat;
+use serde::{Serialize, Deserialize};
 use crate::voxel::world::VoxelWorld;
 use crate::voxel::types::Voxel;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum CameraMode {
     Fly,
     Walk,
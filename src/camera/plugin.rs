@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, CursorOptions};
 use crate::camera::controller::{spawn_camera, player_camera_system};
+use crate::voxel::generation::tasks::spawn_area_ready;
 
 pub struct CameraPlugin;
 
@@ -8,7 +9,8 @@ impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_systems(Startup, (spawn_camera, lock_cursor_on_start))
-            .add_systems(Update, player_camera_system);
+            // Hold the player in place until the terrain below it has been generated
+            .add_systems(Update, player_camera_system.run_if(spawn_area_ready));
     }
 }
 
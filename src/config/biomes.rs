@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::path::Path;
+use crate::config::loader::{load_config, ConfigError};
+
+/// Default location of the biome configuration file
+pub const BIOMES_CONFIG_PATH: &str = "assets/config/biomes.yaml";
+
+/// Typed contents of `biomes.yaml`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BiomesConfigFile {
+    #[serde(default)]
+    pub climate: ClimateSection,
+    #[serde(default = "default_biomes")]
+    pub biomes: Vec<BiomeDef>,
+}
+
+/// The shipped biome config, built in as the fallback
+const BUILTIN_BIOMES: &str = include_str!("../../assets/config/biomes.yaml");
+
+impl Default for BiomesConfigFile {
+    fn default() -> Self {
+        serde_yaml::from_str(BUILTIN_BIOMES).expect("shipped biomes.yaml parses")
+    }
+}
+
+/// Temperature and humidity noise maps that biomes are picked from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ClimateSection {
+    /// Frequency of the climate noise, smaller values give larger biomes
+    #[serde(default = "default_climate_scale")]
+    pub scale: f32,
+    /// Climate distance over which neighboring biomes blend into each other
+    #[serde(default = "default_blend")]
+    pub blend: f32,
+}
+
+impl Default for ClimateSection {
+    fn default() -> Self {
+        Self { scale: default_climate_scale(), blend: default_blend() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BiomeDef {
+    pub id: String,
+    /// Climate the biome is found in, both 0..=1. Each column takes the biome closest to
+    /// its temperature and humidity.
+    pub temperature: f32,
+    pub humidity: f32,
+    /// Voxels added to the terrain height
+    #[serde(default)]
+    pub height_offset: f32,
+    /// Multiplier for the height of hills
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+    /// Surface and subsurface layers, top first
+    pub layers: Vec<BiomeLayer>,
+    /// Layers used instead near the water level, the normal layers if empty
+    #[serde(default)]
+    pub shore_layers: Vec<BiomeLayer>,
+    /// Voxel type below the layers
+    #[serde(default = "default_base")]
+    pub base: String,
+    /// Tint of the grass blades, multiplied with the grass material colors
+    #[serde(default = "default_grass_color")]
+    pub grass_color: [f32; 3],
+    /// Creatures that spawn here
+    #[serde(default)]
+    pub fauna: Vec<FaunaSpawn>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BiomeLayer {
+    /// Voxel type id from voxel_types.yaml
+    pub voxel: String,
+    pub thickness: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FaunaSpawn {
+    /// wolf or rabbit
+    pub creature: String,
+    /// Chance of a spawn on each column the spawner tries, 0..=1
+    pub chance: f32,
+}
+
+fn default_climate_scale() -> f32 {
+    0.004
+}
+
+fn default_blend() -> f32 {
+    0.1
+}
+
+fn default_roughness() -> f32 {
+    1.0
+}
+
+fn default_base() -> String {
+    "rock".to_string()
+}
+
+fn default_grass_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+/// Built-in biomes, used for fields and files left out
+fn default_biomes() -> Vec<BiomeDef> {
+    BiomesConfigFile::default().biomes
+}
+
+impl BiomesConfigFile {
+    /// Check that the values can actually be used to generate terrain. Voxel names are
+    /// checked against the registry when the biome map is built.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.climate.scale <= 0.0 || self.climate.blend <= 0.0 {
+            return Err(ConfigError::InvalidClimate { scale: self.climate.scale, blend: self.climate.blend });
+        }
+        if self.biomes.is_empty() {
+            return Err(ConfigError::NoBiomes);
+        }
+        let mut ids = HashSet::new();
+        for biome in &self.biomes {
+            if !ids.insert(biome.id.as_str()) {
+                return Err(ConfigError::DuplicateBiomeId(biome.id.clone()));
+            }
+            if let Some(spawn) = biome.fauna.iter().find(|spawn| !(0.0..=1.0).contains(&spawn.chance)) {
+                return Err(ConfigError::InvalidSpawnChance {
+                    id: biome.id.clone(),
+                    creature: spawn.creature.clone(),
+                    chance: spawn.chance,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Load and validate the biome config, falling back to the built-in biomes if the file is missing
+pub fn load_biomes_config<P: AsRef<Path>>(path: P) -> Result<BiomesConfigFile, ConfigError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        info!("No biome config at {}, using built-in biomes", path.display());
+        return Ok(BiomesConfigFile::default());
+    }
+
+    let config: BiomesConfigFile = load_config(path)?;
+    config.validate()?;
+    Ok(config)
+}
@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::config::loader::ConfigError;
+use crate::config::world::{load_world_config, FluidSection, InteractionSection, LodSection, WorldConfigFile, WORLD_CONFIG_PATH};
+use crate::voxel::meshing::MeshMode;
+use crate::voxel::registry::{VoxelRegistry, VOXEL_TYPES_CONFIG_PATH};
+use crate::voxel::types::VoxelType;
+
+/// How often the watched files are checked for changes
+const POLL_INTERVAL_SECS: f32 = 1.0;
+
+/// Voxel types whose properties changed between two registries
+#[derive(Clone, Debug, Default)]
+pub struct VoxelTypesDiff {
+    pub added: Vec<VoxelType>,
+    /// Atlas, splat, light or solid/transparent/liquid changes (meshes must be rebuilt)
+    pub visual_changed: Vec<VoxelType>,
+    /// Hardness or tool changes (picked up on the next break attempt)
+    pub gameplay_changed: Vec<VoxelType>,
+}
+
+impl VoxelTypesDiff {
+    /// Compare two registries by type name. Loaded chunks store numeric ids, so a reload
+    /// that removes a type or gives one a different id is refused.
+    pub fn between(old: &VoxelRegistry, new: &VoxelRegistry) -> Result<Self, ConfigError> {
+        for (voxel, info) in old.iter() {
+            match new.id(&info.name) {
+                None => return Err(ConfigError::VoxelTypeRemoved(info.name.clone())),
+                Some(moved) if moved != voxel => {
+                    return Err(ConfigError::VoxelTypeRenumbered { id: info.name.clone(), from: voxel.0, to: moved.0 });
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut diff = Self::default();
+        for (voxel, info) in new.iter() {
+            let Some(previous) = old.id(&info.name).map(|id| old.get(id)) else {
+                diff.added.push(voxel);
+                continue;
+            };
+
+            if previous.solid != info.solid
+                || previous.transparent != info.transparent
+                || previous.liquid != info.liquid
+                || previous.atlas_index != info.atlas_index
+                || previous.atlas_top != info.atlas_top
+                || previous.atlas_bottom != info.atlas_bottom
+                || previous.atlas_side != info.atlas_side
+                || previous.splat_material != info.splat_material
+                || previous.light_emission != info.light_emission
+            {
+                diff.visual_changed.push(voxel);
+            }
+            if previous.hardness != info.hardness || previous.tool_required != info.tool_required {
+                diff.gameplay_changed.push(voxel);
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// New types can only appear in chunks after an edit, so only visual changes need a remesh
+    pub fn requires_remesh(&self) -> bool {
+        !self.visual_changed.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.visual_changed.is_empty() && self.gameplay_changed.is_empty()
+    }
+}
+
+/// Settings that changed in world.yaml (None = unchanged)
+#[derive(Clone, Debug, Default)]
+pub struct WorldConfigDiff {
+    /// World size, chunk size, seed, slot, generator and streaming only apply on restart
+    pub requires_restart: bool,
+    pub meshing_enabled: Option<bool>,
+    pub greedy: Option<bool>,
+    pub mesh_mode: Option<MeshMode>,
+    pub upload_budget: Option<usize>,
+    pub lod: Option<LodSection>,
+    pub fluids: Option<FluidSection>,
+    pub interaction: Option<InteractionSection>,
+    pub wireframe: Option<bool>,
+    pub chunk_borders: Option<bool>,
+}
+
+impl WorldConfigDiff {
+    pub fn between(old: &WorldConfigFile, new: &WorldConfigFile) -> Self {
+        fn changed<T: PartialEq + Copy>(old: T, new: T) -> Option<T> {
+            (old != new).then_some(new)
+        }
+
+        Self {
+            requires_restart: old.world.size_chunks != new.world.size_chunks
+                || old.world.chunk_size != new.world.chunk_size
+                || old.world.seed != new.world.seed
+                || old.world.slot != new.world.slot
+                || old.generator != new.generator
+                || old.streaming != new.streaming,
+            meshing_enabled: changed(old.meshing.enabled, new.meshing.enabled),
+            greedy: changed(old.meshing.greedy, new.meshing.greedy),
+            mesh_mode: changed(old.meshing.mode, new.meshing.mode),
+            upload_budget: changed(old.meshing.upload_budget, new.meshing.upload_budget),
+            lod: changed(old.meshing.lod, new.meshing.lod),
+            fluids: changed(old.fluids, new.fluids),
+            interaction: changed(old.interaction, new.interaction),
+            wireframe: changed(old.debug.wireframe, new.debug.wireframe),
+            chunk_borders: changed(old.debug.chunk_borders, new.debug.chunk_borders),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.requires_restart
+            && self.meshing_enabled.is_none()
+            && self.greedy.is_none()
+            && self.mesh_mode.is_none()
+            && self.upload_budget.is_none()
+            && self.lod.is_none()
+            && self.fluids.is_none()
+            && self.interaction.is_none()
+            && self.wireframe.is_none()
+            && self.chunk_borders.is_none()
+    }
+}
+
+/// Sent after a watched config file was edited and parsed successfully
+#[derive(Message, Clone, Debug)]
+pub enum ConfigReloaded {
+    VoxelTypes {
+        registry: VoxelRegistry,
+        diff: VoxelTypesDiff,
+    },
+    World {
+        config: WorldConfigFile,
+        diff: WorldConfigDiff,
+    },
+}
+
+/// Reload failures by file path, shown in the debug overlay until the file parses again
+#[derive(Resource, Default, Debug)]
+pub struct ConfigReloadStatus {
+    pub errors: BTreeMap<String, String>,
+}
+
+/// A polled file along with the last successfully parsed contents
+struct WatchedFile<T> {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    current: T,
+}
+
+impl<T> WatchedFile<T> {
+    fn new(path: &str, current: T) -> Self {
+        let path = PathBuf::from(path);
+        Self {
+            modified: modified_time(&path),
+            path,
+            current,
+        }
+    }
+
+    /// Returns true once per modification of the file on disk
+    fn poll_changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        // A deleted file keeps the last good config
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Watches world.yaml and voxel_types.yaml for edits while the game runs
+#[derive(Resource)]
+pub struct ConfigWatcher {
+    timer: Timer,
+    world: WatchedFile<WorldConfigFile>,
+    voxel_types: WatchedFile<VoxelRegistry>,
+}
+
+impl ConfigWatcher {
+    /// Start watching the default config paths, diffing against the configs already in use
+    pub fn new(world: WorldConfigFile, registry: VoxelRegistry) -> Self {
+        Self {
+            timer: Timer::from_seconds(POLL_INTERVAL_SECS, TimerMode::Repeating),
+            world: WatchedFile::new(WORLD_CONFIG_PATH, world),
+            voxel_types: WatchedFile::new(VOXEL_TYPES_CONFIG_PATH, registry),
+        }
+    }
+}
+
+/// Poll the watched files and re-parse the ones that changed
+pub fn poll_config_files(
+    time: Res<Time>,
+    mut watcher: ResMut<ConfigWatcher>,
+    mut status: ResMut<ConfigReloadStatus>,
+    mut reloaded: MessageWriter<ConfigReloaded>,
+) {
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if watcher.world.poll_changed() {
+        let path = watcher.world.path.display().to_string();
+        match load_world_config(&watcher.world.path) {
+            Ok(config) => {
+                status.errors.remove(&path);
+                let diff = WorldConfigDiff::between(&watcher.world.current, &config);
+                if !diff.is_empty() {
+                    info!("Reloaded {}: {:?}", path, diff);
+                    reloaded.write(ConfigReloaded::World { config: config.clone(), diff });
+                }
+                watcher.world.current = config;
+            }
+            Err(e) => {
+                warn!("Failed to reload {}: {}", path, e);
+                status.errors.insert(path, e.to_string());
+            }
+        }
+    }
+
+    if watcher.voxel_types.poll_changed() {
+        let path = watcher.voxel_types.path.display().to_string();
+        match VoxelRegistry::load(&watcher.voxel_types.path) {
+            Ok(registry) => match VoxelTypesDiff::between(&watcher.voxel_types.current, &registry) {
+                Ok(diff) => {
+                    status.errors.remove(&path);
+                    if !diff.is_empty() {
+                        info!("Reloaded {}: {:?}", path, diff);
+                        reloaded.write(ConfigReloaded::VoxelTypes { registry: registry.clone(), diff });
+                    }
+                    watcher.voxel_types.current = registry;
+                }
+                // The running world keeps the old registry until the ids line up again
+                Err(e) => {
+                    warn!("Refusing to reload {}: {}", path, e);
+                    status.errors.insert(path, e.to_string());
+                }
+            },
+            Err(e) => {
+                warn!("Failed to reload {}: {}", path, e);
+                status.errors.insert(path, e.to_string());
+            }
+        }
+    }
+}
+
+/// Polls YAML configs and emits `ConfigReloaded` (needs a `ConfigWatcher` resource)
+pub struct ConfigReloadPlugin;
+
+impl Plugin for ConfigReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_message::<ConfigReloaded>()
+            .init_resource::<ConfigReloadStatus>()
+            .add_systems(Update, poll_config_files.run_if(resource_exists::<ConfigWatcher>));
+    }
+}
@@ -1,3 +1,4 @@
+use bevy::math::IVec3;
 use serde::de::DeserializeOwned;
 use std::fs::File;
 use std::io::BufReader;
@@ -10,6 +11,56 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("YAML parse error: {0}")]
     Yaml(#[from] serde_yaml::Error),
+    #[error("World size must be positive in every axis, got {0}")]
+    InvalidWorldSize(IVec3),
+    #[error("Chunk size must be positive, got {0}")]
+    InvalidChunkSize(i32),
+    #[error("Chunk size {found} does not match compiled CHUNK_SIZE {expected}")]
+    ChunkSizeMismatch { found: i32, expected: i32 },
+    #[error("Streaming radius must be positive, got {0}")]
+    InvalidStreamingRadius(i32),
+    #[error("Unload margin must not be negative, got {0}")]
+    InvalidUnloadMargin(i32),
+    #[error("LOD distances must be positive and increasing with a non-negative hysteresis, got {0:?}")]
+    InvalidLodDistances([f32; 3]),
+    #[error("Fluid tick must be positive, got {0}")]
+    InvalidFluidTick(f32),
+    #[error("Voxel type '{0}' is defined more than once")]
+    DuplicateVoxelId(String),
+    #[error("Voxel type '{id}' uses splat material {slot}, only 0-3 exist")]
+    InvalidSplatMaterial { id: String, slot: u8 },
+    #[error("Voxel type '{id}' emits light level {level}, at most 15 is supported")]
+    InvalidLightEmission { id: String, level: u8 },
+    #[error("Too many voxel types, at most {0} are supported")]
+    TooManyVoxelTypes(usize),
+    #[error("Voxel type '{0}' was removed, loaded chunks still use it")]
+    VoxelTypeRemoved(String),
+    #[error("Voxel type '{id}' moved from id {from} to {to}, new types must be appended at the end")]
+    VoxelTypeRenumbered { id: String, from: u8, to: u8 },
+    #[error("Climate scale and blend must be positive, got {scale} and {blend}")]
+    InvalidClimate { scale: f32, blend: f32 },
+    #[error("At least one biome must be defined")]
+    NoBiomes,
+    #[error("Biome '{0}' is defined more than once")]
+    DuplicateBiomeId(String),
+    #[error("Biome '{id}' spawns {creature} with chance {chance}, it must be within 0-1")]
+    InvalidSpawnChance { id: String, creature: String, chance: f32 },
+    #[error("Deposit of '{ore}' has vein size {size}, it must be within 1-16")]
+    InvalidVeinSize { ore: String, size: u32 },
+    #[error("Deposit of '{ore}' has {frequency} veins per chunk, it must not be negative")]
+    InvalidVeinFrequency { ore: String, frequency: f32 },
+    #[error("Deposit of '{ore}' has depth range {min_y}..={max_y}, min_y must not be above max_y")]
+    InvalidDepthRange { ore: String, min_y: i32, max_y: i32 },
+    #[error("Structure template '{0}' is defined more than once")]
+    DuplicateTemplateId(String),
+    #[error("Structure template '{id}' reaches {reach} voxels from its anchor, at most 16 is supported")]
+    TemplateTooWide { id: String, reach: i32 },
+    #[error("Structure template '{id}' has a shape with chance {chance}, it must be within 0-1")]
+    InvalidShapeChance { id: String, chance: f32 },
+    #[error("Structure placement uses template '{0}' which is not defined")]
+    UnknownTemplate(String),
+    #[error("Structure placement of '{template}' has chance {chance}, it must be within 0-1")]
+    InvalidPlacementChance { template: String, chance: f32 },
 }
 
 pub fn load_config<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T, ConfigError> {
@@ -1 +1,6 @@
+pub mod biomes;
+pub mod hot_reload;
 pub mod loader;
+pub mod ores;
+pub mod structures;
+pub mod world;
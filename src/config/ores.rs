@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+use crate::config::loader::{load_config, ConfigError};
+
+/// Default location of the ore deposit configuration file
+pub const ORES_CONFIG_PATH: &str = "assets/config/ores.yaml";
+
+/// Most voxels in one vein. Veins grow from a start voxel, so this also bounds how far they
+/// reach into the chunks around the one they start in.
+pub const MAX_VEIN_SIZE: u32 = 16;
+
+/// Typed contents of `ores.yaml`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OresConfigFile {
+    #[serde(default = "default_deposits")]
+    pub deposits: Vec<DepositDef>,
+}
+
+/// The shipped ore config, built in as the fallback
+const BUILTIN_ORES: &str = include_str!("../../assets/config/ores.yaml");
+
+impl Default for OresConfigFile {
+    fn default() -> Self {
+        serde_yaml::from_str(BUILTIN_ORES).expect("shipped ores.yaml parses")
+    }
+}
+
+/// Veins of one ore. An ore may have several deposits, e.g. with other depths per biome.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DepositDef {
+    /// Voxel type id from voxel_types.yaml
+    pub ore: String,
+    /// Voxels per vein, 1 to `MAX_VEIN_SIZE`
+    pub vein_size: u32,
+    /// Average number of veins starting in each chunk within the depth range
+    pub veins_per_chunk: f32,
+    /// Heights ore is placed between, inclusive
+    pub min_y: i32,
+    pub max_y: i32,
+    /// Voxel types the ore replaces, other voxels in the way of a vein are left alone
+    #[serde(default = "default_hosts")]
+    pub hosts: Vec<String>,
+    /// Biome ids from biomes.yaml veins may start in, every biome if empty
+    #[serde(default)]
+    pub biomes: Vec<String>,
+}
+
+fn default_hosts() -> Vec<String> {
+    vec!["rock".to_string()]
+}
+
+/// Built-in deposits, used for fields and files left out
+fn default_deposits() -> Vec<DepositDef> {
+    OresConfigFile::default().deposits
+}
+
+impl OresConfigFile {
+    /// Check that the values can actually be used to place veins. Ore, host and biome names
+    /// are checked when the deposit generator is built.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for deposit in &self.deposits {
+            if !(1..=MAX_VEIN_SIZE).contains(&deposit.vein_size) {
+                return Err(ConfigError::InvalidVeinSize { ore: deposit.ore.clone(), size: deposit.vein_size });
+            }
+            if !(0.0..).contains(&deposit.veins_per_chunk) {
+                return Err(ConfigError::InvalidVeinFrequency {
+                    ore: deposit.ore.clone(),
+                    frequency: deposit.veins_per_chunk,
+                });
+            }
+            if deposit.min_y > deposit.max_y {
+                return Err(ConfigError::InvalidDepthRange {
+                    ore: deposit.ore.clone(),
+                    min_y: deposit.min_y,
+                    max_y: deposit.max_y,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Load and validate the ore config, falling back to the built-in deposits if the file is missing
+pub fn load_ores_config<P: AsRef<Path>>(path: P) -> Result<OresConfigFile, ConfigError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        info!("No ore config at {}, using built-in deposits", path.display());
+        return Ok(OresConfigFile::default());
+    }
+
+    let config: OresConfigFile = load_config(path)?;
+    config.validate()?;
+    Ok(config)
+}
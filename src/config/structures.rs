@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::path::Path;
+use crate::config::loader::{load_config, ConfigError};
+
+/// Default location of the structure configuration file
+pub const STRUCTURES_CONFIG_PATH: &str = "assets/config/structures.yaml";
+
+/// Farthest a template may reach from its anchor along x and z. Structures spill into the
+/// chunks around the one they stand in, so this keeps them within the neighboring chunks.
+pub const MAX_STRUCTURE_REACH: i32 = 16;
+
+/// Typed contents of `structures.yaml`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StructuresConfigFile {
+    #[serde(default = "default_templates")]
+    pub templates: Vec<TemplateDef>,
+    #[serde(default = "default_placements")]
+    pub placements: Vec<PlacementDef>,
+}
+
+/// The shipped structure config, built in as the fallback
+const BUILTIN_STRUCTURES: &str = include_str!("../../assets/config/structures.yaml");
+
+impl Default for StructuresConfigFile {
+    fn default() -> Self {
+        serde_yaml::from_str(BUILTIN_STRUCTURES).expect("shipped structures.yaml parses")
+    }
+}
+
+/// A structure built from shapes, relative to its anchor: the air voxel on top of the ground
+/// it stands on. Structures are turned a random number of quarter turns around the anchor.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TemplateDef {
+    pub id: String,
+    /// Placed in order, so later shapes can fill or clear parts of earlier ones
+    pub shapes: Vec<ShapeDef>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShapeDef {
+    /// Voxel type id from voxel_types.yaml
+    pub voxel: String,
+    #[serde(flatten)]
+    pub form: ShapeForm,
+    /// Whether the shape replaces solid voxels, otherwise it only fills air
+    #[serde(default)]
+    pub replace: bool,
+    /// Chance of each voxel of the shape being placed, below 1 for crumbling or sparse shapes
+    #[serde(default = "default_chance")]
+    pub chance: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum ShapeForm {
+    /// Every voxel from min to max, inclusive
+    Box { min: [i32; 3], max: [i32; 3] },
+    /// Voxels whose centers are within the radii around the center
+    Ellipsoid { center: [f32; 3], radius: [f32; 3] },
+}
+
+impl ShapeForm {
+    /// Smallest and largest voxel offset the shape can cover
+    pub fn extent(&self) -> (IVec3, IVec3) {
+        match *self {
+            ShapeForm::Box { min, max } => (IVec3::from(min), IVec3::from(max)),
+            ShapeForm::Ellipsoid { center, radius } => {
+                let (center, radius) = (Vec3::from(center), Vec3::from(radius).abs());
+                ((center - radius).ceil().as_ivec3(), (center + radius).floor().as_ivec3())
+            }
+        }
+    }
+}
+
+/// Where a template is placed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlacementDef {
+    /// Template id from this file
+    pub template: String,
+    /// Chance of the template standing on each column, 0..=1
+    pub chance: f32,
+    /// Biome ids from biomes.yaml the template is placed in, every biome if empty
+    #[serde(default)]
+    pub biomes: Vec<String>,
+}
+
+fn default_chance() -> f32 {
+    1.0
+}
+
+/// Built-in templates, used for fields and files left out
+fn default_templates() -> Vec<TemplateDef> {
+    StructuresConfigFile::default().templates
+}
+
+/// Built-in placements, used for fields and files left out
+fn default_placements() -> Vec<PlacementDef> {
+    StructuresConfigFile::default().placements
+}
+
+impl StructuresConfigFile {
+    /// Check that the values can actually be used to place structures. Voxel and biome names
+    /// are checked when the structure generator is built.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut ids = HashSet::new();
+        for template in &self.templates {
+            if !ids.insert(template.id.as_str()) {
+                return Err(ConfigError::DuplicateTemplateId(template.id.clone()));
+            }
+            for shape in &template.shapes {
+                let (min, max) = shape.form.extent();
+                let reach = min.xz().abs().max(max.xz().abs()).max_element();
+                if reach > MAX_STRUCTURE_REACH {
+                    return Err(ConfigError::TemplateTooWide { id: template.id.clone(), reach });
+                }
+                if !(0.0..=1.0).contains(&shape.chance) {
+                    return Err(ConfigError::InvalidShapeChance { id: template.id.clone(), chance: shape.chance });
+                }
+            }
+        }
+        for placement in &self.placements {
+            if !ids.contains(placement.template.as_str()) {
+                return Err(ConfigError::UnknownTemplate(placement.template.clone()));
+            }
+            if !(0.0..=1.0).contains(&placement.chance) {
+                return Err(ConfigError::InvalidPlacementChance {
+                    template: placement.template.clone(),
+                    chance: placement.chance,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Load and validate the structure config, falling back to the built-in structures if the file is missing
+pub fn load_structures_config<P: AsRef<Path>>(path: P) -> Result<StructuresConfigFile, ConfigError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        info!("No structure config at {}, using built-in structures", path.display());
+        return Ok(StructuresConfigFile::default());
+    }
+
+    let config: StructuresConfigFile = load_config(path)?;
+    config.validate()?;
+    Ok(config)
+}
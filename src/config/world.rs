@@ -0,0 +1,341 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+use crate::config::loader::{load_config, ConfigError};
+use crate::constants::{CHUNK_SIZE_I32, DEFAULT_WORLD_CHUNKS_X, DEFAULT_WORLD_CHUNKS_Y, DEFAULT_WORLD_CHUNKS_Z};
+use crate::voxel::meshing::MeshMode;
+
+/// Default location of the world configuration file
+pub const WORLD_CONFIG_PATH: &str = "assets/config/world.yaml";
+
+/// Typed contents of `world.yaml`
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct WorldConfigFile {
+    #[serde(default)]
+    pub world: WorldSection,
+    #[serde(default)]
+    pub generator: GeneratorSection,
+    #[serde(default)]
+    pub streaming: StreamingSection,
+    #[serde(default)]
+    pub meshing: MeshingSection,
+    #[serde(default)]
+    pub fluids: FluidSection,
+    #[serde(default)]
+    pub interaction: InteractionSection,
+    #[serde(default)]
+    pub debug: DebugSection,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WorldSection {
+    /// World size in chunks (x, y, z)
+    pub size_chunks: [i32; 3],
+    /// Voxels per chunk edge, must match `CHUNK_SIZE`
+    pub chunk_size: i32,
+    /// Seed for new worlds, random if unset. Saved worlds keep their own seed.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Named world under `saves/` to load or create, `--world` overrides
+    #[serde(default)]
+    pub slot: Option<String>,
+}
+
+impl Default for WorldSection {
+    fn default() -> Self {
+        Self {
+            size_chunks: [DEFAULT_WORLD_CHUNKS_X, DEFAULT_WORLD_CHUNKS_Y, DEFAULT_WORLD_CHUNKS_Z],
+            chunk_size: CHUNK_SIZE_I32,
+            seed: None,
+            slot: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GeneratorSection {
+    /// noise, flat, void, superflat or the name of a registered custom generator
+    #[serde(rename = "type", default = "default_generator")]
+    pub kind: String,
+    /// Top of the ground for the flat generator
+    #[serde(default = "default_flat_height")]
+    pub flat_height: i32,
+    /// Layers of the superflat generator, bottom first
+    #[serde(default = "default_superflat_layers")]
+    pub superflat_layers: Vec<SuperflatLayer>,
+    /// Free-form settings for custom generators
+    #[serde(default)]
+    pub options: serde_yaml::Value,
+}
+
+impl Default for GeneratorSection {
+    fn default() -> Self {
+        Self {
+            kind: default_generator(),
+            flat_height: default_flat_height(),
+            superflat_layers: default_superflat_layers(),
+            options: serde_yaml::Value::Null,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SuperflatLayer {
+    /// Voxel type id from voxel_types.yaml
+    pub voxel: String,
+    pub thickness: u32,
+}
+
+/// Load chunks around the player instead of generating a fixed-size world
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StreamingSection {
+    /// Unlimited horizontally; `size_chunks` x/z are ignored and y is the world height
+    #[serde(default)]
+    pub enabled: bool,
+    /// Chunks kept loaded around the player horizontally
+    #[serde(default = "default_horizontal_radius")]
+    pub horizontal_radius: i32,
+    /// Chunk layers kept loaded above and below the player
+    #[serde(default = "default_vertical_radius")]
+    pub vertical_radius: i32,
+    /// Extra chunks a chunk must be beyond the radius before it is unloaded, so walking
+    /// back and forth over a chunk border does not reload the same chunks
+    #[serde(default = "default_unload_margin")]
+    pub unload_margin: i32,
+}
+
+impl Default for StreamingSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            horizontal_radius: default_horizontal_radius(),
+            vertical_radius: default_vertical_radius(),
+            unload_margin: default_unload_margin(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MeshingSection {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub greedy: bool,
+    /// Blocky or smooth (surface nets) meshing
+    #[serde(default = "default_mesh_mode")]
+    pub mode: MeshMode,
+    /// Finished chunk meshes uploaded per frame, the rest wait for the next frames
+    #[serde(default = "default_upload_budget")]
+    pub upload_budget: usize,
+    #[serde(default)]
+    pub lod: LodSection,
+}
+
+/// Mesh distant chunks from a downsampled voxel grid
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LodSection {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Distance in chunks from the camera at which chunks switch to 2x, 4x and 8x cells
+    #[serde(default = "default_lod_distances")]
+    pub distances: [f32; 3],
+    /// Chunks a distance has to pass a threshold by before the level changes, so chunks
+    /// on a threshold do not flip back and forth while the camera moves
+    #[serde(default = "default_lod_hysteresis")]
+    pub hysteresis: f32,
+}
+
+impl Default for LodSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            distances: default_lod_distances(),
+            hysteresis: default_lod_hysteresis(),
+        }
+    }
+}
+
+impl LodSection {
+    /// Level of detail for a chunk `distance` chunks away, an index into `LOD_SCALES`
+    pub fn level_for(&self, distance: f32) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        self.distances.iter().filter(|&&threshold| distance >= threshold).count()
+    }
+
+    /// Level of detail for a chunk `distance` chunks away that is meshed at level `current`.
+    /// The level only changes once the distance passes a threshold by the hysteresis.
+    pub fn next_level(&self, current: usize, distance: f32) -> usize {
+        let coarser = self.level_for(distance - self.hysteresis);
+        let finer = self.level_for(distance + self.hysteresis);
+        if coarser > current {
+            coarser
+        } else if finer < current {
+            finer
+        } else {
+            current
+        }
+    }
+}
+
+impl Default for MeshingSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            greedy: true,
+            mode: default_mesh_mode(),
+            upload_budget: default_upload_budget(),
+            lod: LodSection::default(),
+        }
+    }
+}
+
+/// Flowing water simulation
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FluidSection {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Seconds between simulation steps; water spreads one voxel per step
+    #[serde(default = "default_fluid_tick_seconds")]
+    pub tick_seconds: f32,
+    /// Scheduled water updates processed per step, the rest wait for the next steps
+    #[serde(default = "default_fluid_updates_per_tick")]
+    pub max_updates_per_tick: usize,
+}
+
+impl Default for FluidSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tick_seconds: default_fluid_tick_seconds(),
+            max_updates_per_tick: default_fluid_updates_per_tick(),
+        }
+    }
+}
+
+/// How the player edits blocks
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct InteractionSection {
+    /// Hold to break blocks, longer for harder blocks and without the right tool. Blocks
+    /// break on click otherwise.
+    #[serde(default)]
+    pub timed_breaking: bool,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DebugSection {
+    #[serde(default)]
+    pub wireframe: bool,
+    #[serde(default)]
+    pub chunk_borders: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_generator() -> String {
+    "noise".to_string()
+}
+
+fn default_flat_height() -> i32 {
+    12
+}
+
+fn default_superflat_layers() -> Vec<SuperflatLayer> {
+    [("bedrock", 1), ("rock", 2), ("subsoil", 3), ("topsoil", 1)]
+        .into_iter()
+        .map(|(voxel, thickness)| SuperflatLayer { voxel: voxel.to_string(), thickness })
+        .collect()
+}
+
+fn default_mesh_mode() -> MeshMode {
+    MeshMode::SurfaceNets
+}
+
+fn default_upload_budget() -> usize {
+    8
+}
+
+fn default_lod_distances() -> [f32; 3] {
+    [6.0, 12.0, 20.0]
+}
+
+fn default_lod_hysteresis() -> f32 {
+    0.5
+}
+
+fn default_fluid_tick_seconds() -> f32 {
+    0.25
+}
+
+fn default_fluid_updates_per_tick() -> usize {
+    1024
+}
+
+fn default_horizontal_radius() -> i32 {
+    10
+}
+
+fn default_vertical_radius() -> i32 {
+    4
+}
+
+fn default_unload_margin() -> i32 {
+    2
+}
+
+impl WorldConfigFile {
+    /// World size in chunks as a vector
+    pub fn size_chunks(&self) -> IVec3 {
+        IVec3::from_array(self.world.size_chunks)
+    }
+
+    /// Check that the values can actually be used to build a world
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let size = self.size_chunks();
+        if size.x <= 0 || size.y <= 0 || size.z <= 0 {
+            return Err(ConfigError::InvalidWorldSize(size));
+        }
+        if self.world.chunk_size <= 0 {
+            return Err(ConfigError::InvalidChunkSize(self.world.chunk_size));
+        }
+        if self.world.chunk_size != CHUNK_SIZE_I32 {
+            return Err(ConfigError::ChunkSizeMismatch {
+                found: self.world.chunk_size,
+                expected: CHUNK_SIZE_I32,
+            });
+        }
+        if self.streaming.horizontal_radius <= 0 {
+            return Err(ConfigError::InvalidStreamingRadius(self.streaming.horizontal_radius));
+        }
+        if self.streaming.vertical_radius <= 0 {
+            return Err(ConfigError::InvalidStreamingRadius(self.streaming.vertical_radius));
+        }
+        if self.streaming.unload_margin < 0 {
+            return Err(ConfigError::InvalidUnloadMargin(self.streaming.unload_margin));
+        }
+        let lod = &self.meshing.lod;
+        if lod.hysteresis < 0.0 || lod.distances[0] <= 0.0 || !lod.distances.is_sorted() {
+            return Err(ConfigError::InvalidLodDistances(lod.distances));
+        }
+        if self.fluids.tick_seconds <= 0.0 {
+            return Err(ConfigError::InvalidFluidTick(self.fluids.tick_seconds));
+        }
+        Ok(())
+    }
+}
+
+/// Load and validate the world config, falling back to defaults if the file is missing
+pub fn load_world_config<P: AsRef<Path>>(path: P) -> Result<WorldConfigFile, ConfigError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        info!("No world config at {}, using defaults", path.display());
+        return Ok(WorldConfigFile::default());
+    }
+
+    let config: WorldConfigFile = load_config(path)?;
+    config.validate()?;
+    Ok(config)
+}
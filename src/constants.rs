@@ -11,6 +11,8 @@ pub const DEFAULT_WORLD_CHUNKS_Z: i32 = 32;
 // Texture atlas
 pub const ATLAS_TILE_SIZE: u32 = 256;
 pub const ATLAS_COLUMNS: u32 = 4;
+/// Inset of each tile's UVs so sampling never bleeds into adjacent tiles
+pub const ATLAS_UV_PADDING: f32 = 0.02;
 
 // Meshing
 pub const VOXEL_SIZE: f32 = 1.0;
@@ -1,10 +1,17 @@
 use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
 /// Types of items that can be collected
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ItemType {
+    // Saves from before items were snake_case
+    #[serde(alias = "Fur")]
     Fur,
+    Coal,
+    IronOre,
+    GoldOre,
 }
 
 /// Player inventory resource
@@ -3,6 +3,7 @@ pub mod rabbit;
 pub mod inventory;
 
 use bevy::prelude::*;
+use crate::voxel::generation::tasks::world_generated;
 
 pub use wolf::{Wolf, WolfSpawned};
 pub use rabbit::{Rabbit, RabbitSpawned};
@@ -93,9 +94,9 @@ impl Plugin for EntityPlugin {
             .init_resource::<RabbitSpawned>()
             .add_systems(Startup, rabbit::setup_rabbit_assets)
             .add_systems(Update, (
-                wolf::spawn_wolves,
+                wolf::spawn_wolves.run_if(world_generated),
                 wolf::animate_wolves,
-                rabbit::spawn_rabbits,
+                rabbit::spawn_rabbits.run_if(world_generated),
                 rabbit::animate_rabbits,
                 handle_death,
                 process_item_drops,
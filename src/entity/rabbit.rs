@@ -1,5 +1,6 @@
 use bevy::prelude::*;
-use crate::voxel::world::VoxelWorld;
+use crate::voxel::generation::BiomeMap;
+use crate::voxel::world::{VoxelWorld, WorldSeed};
 use crate::voxel::types::{VoxelType, Voxel};
 use super::Health;
 
@@ -47,6 +48,8 @@ pub fn setup_rabbit_assets(
 pub fn spawn_rabbits(
     mut commands: Commands,
     world: Res<VoxelWorld>,
+    seed: Res<WorldSeed>,
+    biomes: Res<BiomeMap>,
     mut spawned: ResMut<RabbitSpawned>,
     rabbit_scene: Option<Res<RabbitSceneHandle>>,
 ) {
@@ -110,9 +113,9 @@ pub fn spawn_rabbits(
                        above_voxel == VoxelType::Air {
                         surfaces_found += 1;
                         
-                        // Spawn rabbit at this valid surface
-                        let hash = simple_hash(world_x * 73, world_z * 67);
-                        // Removed hash filter - spawn on all valid surfaces until we reach max_rabbits
+                        // Spawn rabbit at this valid surface if the column's biome has rabbits
+                        let hash = seed.hash(world_x * 73, world_z * 67);
+                        if hash < biomes.spawn_chance(world_x, world_z, "rabbit") {
                             // Spawn rabbit
                             let rotation = hash * std::f32::consts::TAU;
                             let spawn_pos = Vec3::new(
@@ -136,7 +139,8 @@ pub fn spawn_rabbits(
                                 Health::new(10.0),
                             ));
                             rabbit_count += 1;
-                        
+                        }
+
                         break;  // Found surface for this column, move to next
                     }
                 }
@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use bevy::asset::RenderAssetUsages;
 use bevy_mesh::{Indices, PrimitiveTopology};
-use crate::voxel::world::VoxelWorld;
+use crate::voxel::generation::BiomeMap;
+use crate::voxel::world::{VoxelWorld, WorldSeed};
 use crate::voxel::types::VoxelType;
 use super::Health;
 
@@ -34,6 +35,8 @@ pub fn spawn_wolves(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     world: Res<VoxelWorld>,
+    seed: Res<WorldSeed>,
+    biomes: Res<BiomeMap>,
     mut spawned: ResMut<WolfSpawned>,
 ) {
     if spawned.spawned {
@@ -118,10 +121,10 @@ pub fn spawn_wolves(
             let world_z = z as i32;
             positions_checked += 1;
 
-            // Use hash to determine spawn chance - 50% probability for balanced distribution
-            let hash = simple_hash(world_x * 41, world_z * 43);
+            // Use hash to determine spawn chance - from the fauna table of the column's biome
+            let hash = seed.hash(world_x * 41, world_z * 43);
             
-            if hash > 0.50 { // 50% spawn chance
+            if hash < biomes.spawn_chance(world_x, world_z, "wolf") {
                 // Find surface height - iterate from BOTTOM to TOP
                 let mut surface_y = None;
                 let mut surfaces_found = 0;
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use crate::voxel::generation::tasks::GenerationProgress;
+
+/// Root of the loading screen, removed once the player can move
+#[derive(Component)]
+pub struct LoadingScreen;
+
+/// Fill of the progress bar
+#[derive(Component)]
+pub struct LoadingBar;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+/// Full-screen overlay shown while the terrain around the player is generated
+pub fn setup_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.06, 0.08)),
+            GlobalZIndex(10),
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Generating world..."),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                LoadingText,
+            ));
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(400.0),
+                        height: Val::Px(16.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderColor::all(Color::srgba(1.0, 1.0, 1.0, 0.8)),
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.35, 0.75, 0.35)),
+                        LoadingBar,
+                    ));
+                });
+        });
+}
+
+/// Show generation progress and remove the screen once the spawn area is ready
+pub fn update_loading_screen(
+    mut commands: Commands,
+    progress: Res<GenerationProgress>,
+    screen_query: Query<Entity, With<LoadingScreen>>,
+    mut bar_query: Query<&mut Node, With<LoadingBar>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+) {
+    let Ok(screen) = screen_query.single() else {
+        return;
+    };
+
+    if progress.spawn_ready {
+        commands.entity(screen).despawn();
+        return;
+    }
+
+    let percent = progress.fraction() * 100.0;
+    if let Ok(mut bar) = bar_query.single_mut() {
+        bar.width = Val::Percent(percent);
+    }
+    if let Ok(mut text) = text_query.single_mut() {
+        text.0 = format!(
+            "Generating world... {:.0}% ({}/{} chunks)",
+            percent, progress.generated, progress.to_generate
+        );
+    }
+}
@@ -1,7 +1,18 @@
+pub mod loading_screen;
+pub mod sculpt;
+
 use bevy::prelude::*;
+use crate::config::hot_reload::ConfigReloadStatus;
+use crate::voxel::fluid::VoxelsEdited;
+use crate::voxel::generation::tasks::GenerationProgress;
+use crate::voxel::mesh_tasks::ChunkMeshTasks;
+use crate::voxel::meshing::SNAPSHOT_BORDER;
+use crate::voxel::persistence::autosave::{SaveOutcome, SaveState};
+use crate::voxel::plugin::WorldConfig;
 use crate::voxel::world::VoxelWorld;
-use crate::voxel::types::{VoxelType, Voxel};
-use crate::entity::{Health, Wolf};
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::types::{ToolType, VoxelType, VoxelTypeInfo, Voxel};
+use crate::entity::{Health, ItemDrop, Wolf};
 
 /// Component to mark the block highlight entity
 #[derive(Component)]
@@ -52,9 +63,32 @@ impl Default for HeldBlock {
     }
 }
 
+/// Resource tracking how long the player has been mining the targeted block, with timed breaking
+#[derive(Resource, Default)]
+pub struct BreakProgress {
+    pub position: Option<IVec3>,
+    pub elapsed: f32,
+}
+
+impl BreakProgress {
+    fn reset(&mut self) {
+        self.position = None;
+        self.elapsed = 0.0;
+    }
+}
+
 /// Maximum distance for block interaction
 const INTERACTION_RANGE: f32 = 6.0;
 
+/// Seconds of mining per point of hardness
+const BREAK_TIME_PER_HARDNESS: f32 = 0.25;
+
+/// The player always holds the pickaxe viewmodel
+const EQUIPPED_TOOL: ToolType = ToolType::Pickaxe;
+
+/// Break time multiplier when a block needs a different tool
+const WRONG_TOOL_PENALTY: f32 = 2.0;
+
 /// Raycast step size for block detection
 const RAY_STEP: f32 = 0.1;
 
@@ -177,30 +211,84 @@ pub fn attack_entity_system(
     }
 }
 
-/// System to handle block breaking (left click)
+/// Time needed to break a block with the equipped tool
+fn break_time(info: &VoxelTypeInfo) -> f32 {
+    let penalty = if info.tool_required != ToolType::None && info.tool_required != EQUIPPED_TOOL {
+        WRONG_TOOL_PENALTY
+    } else {
+        1.0
+    };
+    info.hardness * BREAK_TIME_PER_HARDNESS * penalty
+}
+
+/// System to handle block breaking: left click, or hold it with `interaction.timed_breaking`
+/// for a duration from the voxel registry
+#[allow(clippy::too_many_arguments)]
 pub fn break_block_system(
+    mut commands: Commands,
     mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    world_config: Res<WorldConfig>,
     targeted_block: Res<TargetedBlock>,
     targeted_entity: Res<TargetedEntity>,
+    registry: Res<VoxelRegistry>,
+    mut progress: ResMut<BreakProgress>,
     mut world: ResMut<VoxelWorld>,
     mut held: ResMut<HeldBlock>,
+    mut edited: MessageWriter<VoxelsEdited>,
 ) {
+    let timed = world_config.interaction.timed_breaking;
+    let mining = if timed {
+        mouse.pressed(MouseButton::Left)
+    } else {
+        mouse.just_pressed(MouseButton::Left)
+    };
     // Only break blocks if not targeting an entity
-    if mouse.just_pressed(MouseButton::Left) && targeted_entity.entity.is_none() {
-        if let (Some(pos), Some(voxel_type)) = (targeted_block.position, targeted_block.voxel_type) {
-            // Don't break bedrock
-            if voxel_type != VoxelType::Bedrock {
-                // Store the broken block type for placing
-                held.block_type = voxel_type;
-                
-                // Set to air
-                world.set_voxel(pos, VoxelType::Air);
-                
-                // Mark neighboring chunks dirty too (for proper mesh updates at edges)
-                mark_neighbors_dirty(&mut world, pos);
-            }
+    if !mining || targeted_entity.entity.is_some() {
+        progress.reset();
+        return;
+    }
+
+    let (Some(pos), Some(voxel_type)) = (targeted_block.position, targeted_block.voxel_type) else {
+        progress.reset();
+        return;
+    };
+
+    // Negative hardness marks unbreakable blocks (bedrock)
+    let info = registry.get(voxel_type);
+    if info.hardness < 0.0 {
+        progress.reset();
+        return;
+    }
+
+    if timed {
+        // Start over when the player looks at a different block
+        if progress.position != Some(pos) {
+            progress.position = Some(pos);
+            progress.elapsed = 0.0;
+        }
+
+        progress.elapsed += time.delta_secs();
+        if progress.elapsed < break_time(info) {
+            return;
         }
+        progress.reset();
     }
+
+    // Store the broken block type for placing
+    held.block_type = voxel_type;
+    // Ores go to the inventory
+    if let Some(item_type) = info.drop {
+        commands.spawn(ItemDrop { item_type, position: pos.as_vec3() + Vec3::splat(0.5) });
+    }
+
+    // Set to air
+    world.set_voxel(pos, VoxelType::Air);
+
+    // Mark neighboring chunks dirty too (for proper mesh updates at edges)
+    mark_neighbors_dirty(&mut world, pos);
+    // Water next to the hole flows in
+    edited.write(VoxelsEdited { center: pos, reach: 0 });
 }
 
 /// System to handle block placing (right click)
@@ -210,6 +298,7 @@ pub fn place_block_system(
     mut world: ResMut<VoxelWorld>,
     held: Res<HeldBlock>,
     camera_query: Query<&Transform, With<crate::camera::controller::PlayerCamera>>,
+    mut edited: MessageWriter<VoxelsEdited>,
 ) {
     if mouse.just_pressed(MouseButton::Right) {
         if let (Some(block_pos), Some(normal)) = (targeted.position, targeted.normal) {
@@ -235,45 +324,22 @@ pub fn place_block_system(
                 }
             }
             
-            // Check if the position is valid (air or water)
+            // Check if the position is valid (air or any water)
             if let Some(existing) = world.get_voxel(place_pos) {
-                if existing == VoxelType::Air || existing == VoxelType::Water {
+                if existing == VoxelType::Air || existing.is_liquid() {
                     world.set_voxel(place_pos, held.block_type);
                     mark_neighbors_dirty(&mut world, place_pos);
+                    // Water fed by a replaced source drains
+                    edited.write(VoxelsEdited { center: place_pos, reach: 0 });
                 }
             }
         }
     }
 }
 
-/// Mark a block and its neighbors as dirty for mesh regeneration
+/// Mark the chunks whose meshes read a block dirty, including diagonal neighbors
 fn mark_neighbors_dirty(world: &mut VoxelWorld, pos: IVec3) {
-    // Mark the chunk containing this block
-    let chunk_pos = VoxelWorld::world_to_chunk(pos);
-    if let Some(chunk) = world.get_chunk_mut(chunk_pos) {
-        chunk.mark_dirty();
-    }
-    
-    // Check if we're at a chunk boundary and mark neighbor chunks
-    let local = VoxelWorld::world_to_local(pos);
-    
-    let offsets = [
-        (local.x == 0, IVec3::new(-1, 0, 0)),
-        (local.x == 15, IVec3::new(1, 0, 0)),
-        (local.y == 0, IVec3::new(0, -1, 0)),
-        (local.y == 15, IVec3::new(0, 1, 0)),
-        (local.z == 0, IVec3::new(0, 0, -1)),
-        (local.z == 15, IVec3::new(0, 0, 1)),
-    ];
-    
-    for (at_edge, offset) in offsets {
-        if at_edge {
-            let neighbor_chunk = chunk_pos + offset;
-            if let Some(chunk) = world.get_chunk_mut(neighbor_chunk) {
-                chunk.mark_dirty();
-            }
-        }
-    }
+    world.mark_dirty_around(pos, SNAPSHOT_BORDER);
 }
 
 /// System to render block highlight wireframe
@@ -553,10 +619,18 @@ pub fn toggle_debug_overlay(
 }
 
 /// Update debug overlay text with real-time info
+#[allow(clippy::too_many_arguments)]
 pub fn update_debug_overlay(
     state: Res<DebugOverlayState>,
     targeted: Res<TargetedBlock>,
     world: Res<VoxelWorld>,
+    registry: Res<VoxelRegistry>,
+    progress: Res<BreakProgress>,
+    reload_status: Res<ConfigReloadStatus>,
+    save_state: Res<SaveState>,
+    generation: Res<GenerationProgress>,
+    mesh_tasks: Res<ChunkMeshTasks>,
+    time: Res<Time>,
     camera_query: Query<&Transform, With<crate::camera::controller::PlayerCamera>>,
     mut query: Query<&mut Text, With<DebugOverlay>>,
 ) {
@@ -578,6 +652,21 @@ pub fn update_debug_overlay(
         );
         let chunk_pos = VoxelWorld::world_to_chunk(block_pos);
         text_content.push_str(&format!("Chunk: {:?}\n", chunk_pos));
+
+        if let Some(chunk) = world.get_chunk(chunk_pos) {
+            let stats = chunk.memory_stats();
+            text_content.push_str(&format!(
+                "Chunk mem: {} B ({} types, {} bits/voxel)\n",
+                stats.bytes, stats.palette_len, stats.bits_per_voxel
+            ));
+        }
+        let world_stats = world.memory_stats();
+        text_content.push_str(&format!(
+            "World mem: {:.1} MiB ({} chunks, {} uniform)\n",
+            world_stats.bytes as f32 / (1024.0 * 1024.0),
+            world_stats.chunks,
+            world_stats.uniform_chunks
+        ));
     }
 
     text_content.push_str("\n");
@@ -587,6 +676,17 @@ pub fn update_debug_overlay(
         text_content.push_str(&format!("Target: {:?}\n", pos));
         text_content.push_str(&format!("Type: {:?}\n", voxel_type));
 
+        let info = registry.get(voxel_type);
+        if info.hardness < 0.0 {
+            text_content.push_str("Hardness: unbreakable\n");
+        } else {
+            text_content.push_str(&format!("Hardness: {:.1} ({:?})\n", info.hardness, info.tool_required));
+        }
+        if progress.position == Some(pos) {
+            let fraction = progress.elapsed / break_time(info).max(f32::EPSILON);
+            text_content.push_str(&format!("Mining: {:.0}%\n", fraction.min(1.0) * 100.0));
+        }
+
         // Water scan in 5x5x5 area
         let mut water_count = 0;
         let mut water_with_air = 0;
@@ -618,8 +718,42 @@ pub fn update_debug_overlay(
         text_content.push_str("Target: None\n");
     }
 
+    // Chunks still generating in the background after the loading screen closed
+    if !generation.is_done() {
+        text_content.push_str(&format!("\nGenerating: {}/{} chunks\n", generation.generated, generation.to_generate));
+    }
+    if mesh_tasks.in_flight() > 0 || mesh_tasks.waiting_upload() > 0 {
+        text_content.push_str(&format!(
+            "Meshing: {} in flight, {} waiting upload\n",
+            mesh_tasks.in_flight(),
+            mesh_tasks.waiting_upload()
+        ));
+    }
+
+    // Save status
+    if save_state.is_saving() {
+        text_content.push_str("\nSaving...\n");
+    } else if let Some((finished, outcome)) = &save_state.last {
+        let ago = time.elapsed_secs() - finished;
+        match outcome {
+            SaveOutcome::Saved { chunks } => {
+                text_content.push_str(&format!("\nLast save: {} chunks, {:.0}s ago\n", chunks, ago));
+            }
+            SaveOutcome::Failed(e) => {
+                text_content.push_str(&format!("\nSave FAILED {:.0}s ago:\n  {}\n", ago, e));
+            }
+        }
+    }
+
+    // Config files that failed to hot reload keep their last good values
+    for (path, error) in &reload_status.errors {
+        text_content.push_str(&format!("\nConfig error in {}:\n  {}\n", path, error));
+    }
+
     text_content.push_str("\n[F3] Toggle overlay");
     text_content.push_str("\n[G] Detailed log");
+    text_content.push_str("\n[F5] Save world");
+    text_content.push_str("\n[B] Sculpt ([N] mode, [ and ] radius)");
 
     for mut text in query.iter_mut() {
         **text = text_content.clone();
@@ -635,14 +769,19 @@ impl Plugin for InteractionPlugin {
             .init_resource::<TargetedBlock>()
             .init_resource::<TargetedEntity>()
             .init_resource::<HeldBlock>()
+            .init_resource::<BreakProgress>()
             .init_resource::<DebugOverlayState>()
-            .add_systems(Startup, setup_debug_overlay)
+            .init_resource::<sculpt::SculptBrush>()
+            .add_systems(Startup, (setup_debug_overlay, loading_screen::setup_loading_screen))
+            .add_systems(Update, loading_screen::update_loading_screen)
             .add_systems(Update, (
                 update_targeted_block,
                 update_targeted_entity,
                 attack_entity_system,
-                break_block_system,
-                place_block_system,
+                sculpt::sculpt_controls_system,
+                sculpt::sculpt_system,
+                break_block_system.run_if(sculpt::sculpt_disabled),
+                place_block_system.run_if(sculpt::sculpt_disabled),
                 render_block_highlight,
                 debug_voxel_info_system,
                 toggle_debug_overlay,
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use crate::interaction::{HeldBlock, TargetedBlock};
+use crate::voxel::chunk::DENSITY_THRESHOLD;
+use crate::voxel::fluid::VoxelsEdited;
+use crate::voxel::meshing::SNAPSHOT_BORDER;
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::types::{Voxel, VoxelType};
+use crate::voxel::world::VoxelWorld;
+
+/// Smallest and largest brush radius in voxels
+const MIN_BRUSH_RADIUS: f32 = 1.0;
+const MAX_BRUSH_RADIUS: f32 = 8.0;
+
+/// What a sculpt stroke does to the terrain under the brush
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SculptMode {
+    /// Add material
+    #[default]
+    Raise,
+    /// Remove material
+    Lower,
+    /// Even out bumps by moving each fill toward the average of its neighbors
+    Smooth,
+    /// Level the terrain to the height the stroke started at
+    Flatten,
+}
+
+impl SculptMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Raise => Self::Lower,
+            Self::Lower => Self::Smooth,
+            Self::Smooth => Self::Flatten,
+            Self::Flatten => Self::Raise,
+        }
+    }
+}
+
+/// Spherical brush for smooth terrain editing. While enabled, left click sculpts instead of
+/// breaking blocks and right click does nothing.
+#[derive(Resource)]
+pub struct SculptBrush {
+    pub enabled: bool,
+    pub mode: SculptMode,
+    /// Radius in voxels
+    pub radius: f32,
+    /// Fill change per second at the brush center, as a fraction of a full voxel
+    pub strength: f32,
+    /// Height flatten levels to, set when a stroke starts
+    flatten_height: Option<f32>,
+}
+
+impl Default for SculptBrush {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: SculptMode::Raise,
+            radius: 3.0,
+            strength: 2.0,
+            flatten_height: None,
+        }
+    }
+}
+
+/// Run condition: blocks are broken and placed, not sculpted
+pub fn sculpt_disabled(brush: Res<SculptBrush>) -> bool {
+    !brush.enabled
+}
+
+/// Change the fill of the voxels within `radius` of `center`. `amount` is the fill change at
+/// the center as a fraction of a full voxel and falls off smoothly toward the edge. Voxels
+/// that become solid take the most common solid type around them, or `material` if there is
+/// none; unbreakable voxels and liquids are left alone. Returns whether anything changed.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_brush(
+    world: &mut VoxelWorld,
+    registry: &VoxelRegistry,
+    center: Vec3,
+    radius: f32,
+    mode: SculptMode,
+    amount: f32,
+    flatten_height: f32,
+    material: VoxelType,
+) -> bool {
+    let min = (center - Vec3::splat(radius)).floor().as_ivec3();
+    let max = (center + Vec3::splat(radius)).ceil().as_ivec3();
+
+    // Read every fill before writing any, so smoothing does not depend on visiting order
+    let mut changes = Vec::new();
+    for z in min.z..=max.z {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let pos = IVec3::new(x, y, z);
+                let distance = (pos.as_vec3() + Vec3::splat(0.5)).distance(center);
+                if distance > radius {
+                    continue;
+                }
+                let (Some(voxel), Some(fill)) = (world.get_voxel(pos), world.get_density(pos)) else {
+                    continue;
+                };
+                if voxel.is_liquid() || (voxel.is_solid() && registry.get(voxel).hardness < 0.0) {
+                    continue;
+                }
+
+                let falloff = (1.0 - distance / radius).powi(2);
+                let step = (amount * falloff).min(1.0);
+                let current = fill as f32 / 255.0;
+                let target = match mode {
+                    SculptMode::Raise => current + step,
+                    SculptMode::Lower => current - step,
+                    SculptMode::Smooth => {
+                        let sum: f32 = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z]
+                            .iter()
+                            .map(|offset| world.get_density(pos + *offset).unwrap_or(fill) as f32 / 255.0)
+                            .sum();
+                        current + (sum / 6.0 - current) * step
+                    }
+                    SculptMode::Flatten => {
+                        let level = (flatten_height - y as f32).clamp(0.0, 1.0);
+                        current + (level - current) * step
+                    }
+                };
+                let new_fill = (target.clamp(0.0, 1.0) * 255.0).round() as u8;
+                if new_fill != fill {
+                    changes.push((pos, new_fill, voxel));
+                }
+            }
+        }
+    }
+
+    for &(pos, fill, voxel) in &changes {
+        let material = if fill >= DENSITY_THRESHOLD && !voxel.is_solid() {
+            surrounding_material(world, pos).unwrap_or(material)
+        } else {
+            voxel
+        };
+        world.set_density(pos, fill, material);
+    }
+
+    if !changes.is_empty() {
+        world.mark_dirty_around(center.floor().as_ivec3(), radius.ceil() as i32 + SNAPSHOT_BORDER);
+    }
+    !changes.is_empty()
+}
+
+/// Most common solid voxel next to a position
+fn surrounding_material(world: &VoxelWorld, pos: IVec3) -> Option<VoxelType> {
+    let mut counts: Vec<(VoxelType, u32)> = Vec::new();
+    for offset in [IVec3::NEG_Y, IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z, IVec3::Y] {
+        let Some(voxel) = world.get_voxel(pos + offset).filter(|voxel| voxel.is_solid()) else {
+            continue;
+        };
+        match counts.iter_mut().find(|(v, _)| *v == voxel) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((voxel, 1)),
+        }
+    }
+    counts.into_iter().max_by_key(|(_, count)| *count).map(|(voxel, _)| voxel)
+}
+
+/// Toggle sculpting (B), cycle the mode (N) and resize the brush ([ and ])
+pub fn sculpt_controls_system(keyboard: Res<ButtonInput<KeyCode>>, mut brush: ResMut<SculptBrush>) {
+    if keyboard.just_pressed(KeyCode::KeyB) {
+        brush.enabled = !brush.enabled;
+        info!("Sculpting {}", if brush.enabled { "enabled" } else { "disabled" });
+    }
+    if !brush.enabled {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::KeyN) {
+        brush.mode = brush.mode.next();
+        info!("Sculpt mode: {:?}", brush.mode);
+    }
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        brush.radius = (brush.radius - 1.0).max(MIN_BRUSH_RADIUS);
+    }
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        brush.radius = (brush.radius + 1.0).min(MAX_BRUSH_RADIUS);
+    }
+}
+
+/// Sculpt the targeted terrain while left click is held
+#[allow(clippy::too_many_arguments)]
+pub fn sculpt_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    targeted: Res<TargetedBlock>,
+    held: Res<HeldBlock>,
+    registry: Res<VoxelRegistry>,
+    mut brush: ResMut<SculptBrush>,
+    mut world: ResMut<VoxelWorld>,
+    mut edited: MessageWriter<VoxelsEdited>,
+) {
+    if !brush.enabled || !mouse.pressed(MouseButton::Left) {
+        brush.flatten_height = None;
+        return;
+    }
+    let (Some(pos), Some(normal)) = (targeted.position, targeted.normal) else {
+        return;
+    };
+
+    // Center the brush on the face being looked at
+    let center = pos.as_vec3() + Vec3::splat(0.5) + normal.as_vec3() * 0.5;
+    let flatten_height = *brush.flatten_height.get_or_insert(center.y);
+    let amount = brush.strength * time.delta_secs();
+    let changed = apply_brush(
+        &mut world,
+        &registry,
+        center,
+        brush.radius,
+        brush.mode,
+        amount,
+        flatten_height,
+        held.block_type,
+    );
+    if changed {
+        edited.write(VoxelsEdited { center: center.floor().as_ivec3(), reach: brush.radius.ceil() as i32 });
+    }
+}
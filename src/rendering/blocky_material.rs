@@ -0,0 +1,49 @@
+use bevy::{
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderType},
+};
+use bevy_shader::ShaderRef;
+use crate::constants::{ATLAS_COLUMNS, ATLAS_UV_PADDING};
+
+/// Blocky terrain material: standard PBR with the atlas sampled by the extension
+pub type BlockyMaterial = ExtendedMaterial<StandardMaterial, AtlasTiling>;
+
+#[derive(Clone, Copy, ShaderType, Debug)]
+pub struct AtlasTilingUniforms {
+    /// Size of one tile in atlas UVs, without the padding on each side
+    pub tile_extent: Vec2,
+    /// Texels with a lower alpha are cut out, also in shadows
+    pub alpha_cutoff: f32,
+}
+
+impl Default for AtlasTilingUniforms {
+    fn default() -> Self {
+        let tile = 1.0 / ATLAS_COLUMNS as f32 - 2.0 * ATLAS_UV_PADDING;
+        Self {
+            tile_extent: Vec2::splat(tile),
+            alpha_cutoff: 0.5,
+        }
+    }
+}
+
+/// Repeats a face's atlas tile across greedy-merged quads. Meshes carry the tile repeat
+/// count in UV_0 and the tile corner in the atlas in UV_1.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct AtlasTiling {
+    #[uniform(100)]
+    pub uniforms: AtlasTilingUniforms,
+    #[texture(101)]
+    #[sampler(102)]
+    pub atlas: Handle<Image>,
+}
+
+impl MaterialExtension for AtlasTiling {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/blocky_atlas.wgsl".into()
+    }
+
+    fn prepass_fragment_shader() -> ShaderRef {
+        "shaders/blocky_atlas_prepass.wgsl".into()
+    }
+}
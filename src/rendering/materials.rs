@@ -2,34 +2,43 @@ use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerD
 use bevy::prelude::*;
 use bevy::render::render_resource::Face;
 use crate::rendering::atlas::TextureAtlas;
+use crate::rendering::blocky_material::{AtlasTiling, AtlasTilingUniforms, BlockyMaterial};
 use crate::rendering::triplanar_material::{TriplanarMaterial, TriplanarMaterialHandle, TriplanarUniforms};
+use crate::rendering::water_material::{VoxelWaterMaterial, WaterFlow, WaterFlowUniforms};
 
 #[derive(Resource)]
 pub struct VoxelMaterial {
-    pub handle: Handle<StandardMaterial>,
+    pub handle: Handle<BlockyMaterial>,
 }
 
 #[derive(Resource)]
 pub struct WaterMaterial {
-    pub handle: Handle<StandardMaterial>,
+    pub handle: Handle<VoxelWaterMaterial>,
 }
 
 pub fn setup_voxel_material(
     mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut water_materials: ResMut<Assets<VoxelWaterMaterial>>,
+    mut blocky_materials: ResMut<Assets<BlockyMaterial>>,
     atlas: Res<TextureAtlas>,
 ) {
-    // Solid block material
-    let material_handle = materials.add(StandardMaterial {
-        base_color_texture: Some(atlas.handle.clone()),
-        perceptual_roughness: 0.9,
-        metallic: 0.0,
-        reflectance: 0.1,
-        // Enable backface culling for performance now that meshing is stable
-        cull_mode: Some(Face::Back),
-        // Use a mask so leaves/foliage can leverage alpha but keep opaque blocks solid
-        alpha_mode: AlphaMode::Mask(0.5),
-        ..default()
+    // Solid block material; the atlas is sampled by the extension so tiles repeat across merged faces
+    let tiling = AtlasTilingUniforms::default();
+    let material_handle = blocky_materials.add(BlockyMaterial {
+        base: StandardMaterial {
+            perceptual_roughness: 0.9,
+            metallic: 0.0,
+            reflectance: 0.1,
+            // Enable backface culling for performance now that meshing is stable
+            cull_mode: Some(Face::Back),
+            // Use a mask so leaves/foliage can leverage alpha but keep opaque blocks solid
+            alpha_mode: AlphaMode::Mask(tiling.alpha_cutoff),
+            ..default()
+        },
+        extension: AtlasTiling {
+            uniforms: tiling,
+            atlas: atlas.handle.clone(),
+        },
     });
 
     commands.insert_resource(VoxelMaterial {
@@ -38,16 +47,22 @@ pub fn setup_voxel_material(
 
     // Water material - semi-transparent blue with proper depth handling
     // Use positive depth_bias to push water behind terrain, preventing visible seams
-    let water_handle = materials.add(StandardMaterial {
-        base_color: Color::srgba(0.0, 0.3, 0.8, 0.7), // Semi-transparent blue
-        alpha_mode: AlphaMode::Blend,
-        perceptual_roughness: 0.05, // Very smooth surface
-        metallic: 0.0,
-        reflectance: 0.8, // High reflection for water look
-        double_sided: true, // Visible from below
-        cull_mode: None, // Render both sides
-        depth_bias: 1.0, // Push water behind terrain to hide seams
-        ..default()
+    let water_handle = water_materials.add(VoxelWaterMaterial {
+        base: StandardMaterial {
+            base_color: Color::srgba(0.0, 0.3, 0.8, 0.7), // Semi-transparent blue
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.05, // Very smooth surface
+            metallic: 0.0,
+            reflectance: 0.8, // High reflection for water look
+            double_sided: true, // Visible from below
+            cull_mode: None, // Render both sides
+            depth_bias: 1.0, // Push water behind terrain to hide seams
+            ..default()
+        },
+        // Ripples follow the flow direction the water mesher stores per vertex
+        extension: WaterFlow {
+            uniforms: WaterFlowUniforms::default(),
+        },
     });
 
     commands.insert_resource(WaterMaterial {
@@ -85,6 +100,7 @@ pub fn setup_triplanar_material(
     mut commands: Commands,
     mut materials: ResMut<Assets<TriplanarMaterial>>,
     asset_server: Res<AssetServer>,
+    atlas: Res<TextureAtlas>,
 ) {
     let material_handle = materials.add(TriplanarMaterial {
         uniforms: TriplanarUniforms {
@@ -93,6 +109,7 @@ pub fn setup_triplanar_material(
             blend_sharpness: 4.0,   // Moderate blend between projections
             normal_intensity: 1.0,  // Full normal map strength
             parallax_scale: 0.04,   // Subtle parallax depth
+            ..default()
         },
         // Grass textures (for TopSoil top faces)
         grass_albedo: Some(asset_server.load("pbr/grass/albedo.png")),
@@ -106,6 +123,8 @@ pub fn setup_triplanar_material(
         // Dirt textures (for SubSoil, sides)
         dirt_albedo: Some(asset_server.load("pbr/dirt/albedo.png")),
         dirt_normal: Some(asset_server.load("pbr/dirt/normal.png")),
+        // Ores are drawn with their atlas tile
+        atlas: Some(atlas.handle.clone()),
     });
 
     commands.insert_resource(TriplanarMaterialHandle {
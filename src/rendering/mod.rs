@@ -1,4 +1,6 @@
 pub mod atlas;
+pub mod blocky_material;
 pub mod materials;
 pub mod plugin;
 pub mod triplanar_material;
+pub mod water_material;
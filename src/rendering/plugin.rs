@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use crate::rendering::atlas::load_texture_atlas;
+use crate::rendering::blocky_material::BlockyMaterial;
 use crate::rendering::materials::{configure_atlas_sampler, setup_voxel_material, setup_triplanar_material};
 use crate::rendering::triplanar_material::TriplanarMaterial;
+use crate::rendering::water_material::VoxelWaterMaterial;
 
 pub struct RenderingPlugin;
 
@@ -10,6 +12,10 @@ impl Plugin for RenderingPlugin {
         app
             // Register TriplanarMaterial as a custom material type
             .add_plugins(MaterialPlugin::<TriplanarMaterial>::default())
+            // Blocky terrain repeats atlas tiles across greedy-merged faces
+            .add_plugins(MaterialPlugin::<BlockyMaterial>::default())
+            // Voxel water ripples along its flow direction
+            .add_plugins(MaterialPlugin::<VoxelWaterMaterial>::default())
             .add_systems(Startup, (
                 load_texture_atlas,
                 setup_voxel_material,
@@ -3,6 +3,7 @@ use bevy::{
     render::render_resource::{AsBindGroup, ShaderType},
 };
 use bevy_shader::ShaderRef;
+use crate::constants::{ATLAS_COLUMNS, ATLAS_UV_PADDING};
 
 /// All triplanar material uniforms in a single struct for proper GPU alignment
 #[derive(Clone, Copy, ShaderType, Debug)]
@@ -17,6 +18,10 @@ pub struct TriplanarUniforms {
     pub normal_intensity: f32,
     /// Parallax depth scale for displacement
     pub parallax_scale: f32,
+    /// Size of one atlas tile in atlas UVs, without the padding on each side
+    pub atlas_tile_extent: f32,
+    /// Tiles per atlas row
+    pub atlas_columns: u32,
 }
 
 impl Default for TriplanarUniforms {
@@ -27,6 +32,8 @@ impl Default for TriplanarUniforms {
             blend_sharpness: 4.0,
             normal_intensity: 1.0,
             parallax_scale: 0.04,
+            atlas_tile_extent: 1.0 / ATLAS_COLUMNS as f32 - 2.0 * ATLAS_UV_PADDING,
+            atlas_columns: ATLAS_COLUMNS,
         }
     }
 }
@@ -61,6 +68,10 @@ pub struct TriplanarMaterial {
     pub dirt_albedo: Option<Handle<Image>>,
     #[texture(9)]
     pub dirt_normal: Option<Handle<Image>>,
+
+    // Block atlas, for ore tiles drawn over the splat materials
+    #[texture(10)]
+    pub atlas: Option<Handle<Image>>,
 }
 
 impl Default for TriplanarMaterial {
@@ -75,6 +86,7 @@ impl Default for TriplanarMaterial {
             sand_normal: None,
             dirt_albedo: None,
             dirt_normal: None,
+            atlas: None,
         }
     }
 }
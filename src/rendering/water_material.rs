@@ -0,0 +1,43 @@
+use bevy::{
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderType},
+};
+use bevy_shader::ShaderRef;
+
+/// Voxel water material: standard PBR with ripples scrolled along the mesh's flow
+pub type VoxelWaterMaterial = ExtendedMaterial<StandardMaterial, WaterFlow>;
+
+#[derive(Clone, Copy, ShaderType, Debug)]
+pub struct WaterFlowUniforms {
+    /// World units per second the ripples move at full flow
+    pub flow_speed: f32,
+    /// Ripples per world unit
+    pub ripple_scale: f32,
+    /// How far ripples tilt the surface normal
+    pub ripple_strength: f32,
+}
+
+impl Default for WaterFlowUniforms {
+    fn default() -> Self {
+        Self {
+            flow_speed: 1.5,
+            ripple_scale: 1.2,
+            ripple_strength: 0.15,
+        }
+    }
+}
+
+/// Animates voxel water. Meshes carry world-space UVs in UV_0 and the flow direction in the
+/// UV plane in UV_1; still water only ripples in place.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct WaterFlow {
+    #[uniform(100)]
+    pub uniforms: WaterFlowUniforms,
+}
+
+impl MaterialExtension for WaterFlow {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/voxel_water.wgsl".into()
+    }
+}
@@ -2,13 +2,17 @@ pub mod grass_material;
 
 use bevy::asset::RenderAssetUsages;
 use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
 use bevy::light::NotShadowCaster;
 use bevy_mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
 use crate::constants::CHUNK_SIZE;
-use crate::voxel::world::VoxelWorld;
+use crate::voxel::world::{VoxelWorld, WorldSeed};
+use crate::voxel::generation::BiomeMap;
+use crate::voxel::generation::tasks::world_generated;
 use crate::voxel::types::{VoxelType, Voxel};
 use crate::voxel::meshing::ChunkMesh;
-use crate::rendering::materials::WaterMaterial;
+use crate::voxel::streaming::ChunkUnloaded;
+use crate::rendering::blocky_material::BlockyMaterial;
 use crate::camera::controller::PlayerCamera;
 
 pub use grass_material::{GrassMaterial, GrassMaterialPlugin, GrassMaterialHandles};
@@ -17,6 +21,8 @@ pub use grass_material::{GrassMaterial, GrassMaterialPlugin, GrassMaterialHandle
 struct GrassInstance {
     position: Vec3,
     normal: Vec3,
+    /// Biome grass color the blade is tinted with
+    tint: [f32; 4],
 }
 
 /// Component for grass blade instances
@@ -43,6 +49,12 @@ pub struct ParticlesSpawned(pub bool);
 #[derive(Component)]
 pub struct ChunkGrassAttached;
 
+/// Grass patch built from the mesh of a chunk, removed when the chunk is unloaded
+#[derive(Component)]
+pub struct ChunkGrassPatch {
+    pub chunk_position: IVec3,
+}
+
 /// Cached grass assets for the procedural patches
 #[derive(Resource, Default)]
 pub struct GrassPatchAssets {
@@ -98,14 +110,14 @@ pub fn setup_grass_patch_assets(
 pub fn attach_procedural_grass_to_chunks(
     mut commands: Commands,
     assets: Res<GrassPatchAssets>,
-    water_material: Res<WaterMaterial>,
+    biomes: Res<BiomeMap>,
     mut meshes: ResMut<Assets<Mesh>>,
-    // Query chunks with StandardMaterial (blocky mode)
+    // Query chunks with BlockyMaterial (blocky mode); water meshes use their own material
     blocky_chunk_query: Query<(
         Entity,
         &ChunkMesh,
         &Mesh3d,
-        &MeshMaterial3d<StandardMaterial>,
+        &MeshMaterial3d<BlockyMaterial>,
         &Transform,
     ), Without<ChunkGrassAttached>>,
     // Query chunks with TriplanarMaterial (surface nets mode)
@@ -118,25 +130,22 @@ pub fn attach_procedural_grass_to_chunks(
     ), Without<ChunkGrassAttached>>,
 ) {
     // Process blocky chunks
-    for (entity, chunk, chunk_mesh, material, transform) in blocky_chunk_query.iter() {
-        // Skip water surfaces
-        if material.0 == water_material.handle {
-            continue;
-        }
-
-        process_chunk_for_grass(&mut commands, &assets, &mut meshes, entity, chunk, chunk_mesh, transform);
+    for (entity, chunk, chunk_mesh, _material, transform) in blocky_chunk_query.iter() {
+        process_chunk_for_grass(&mut commands, &assets, &biomes, &mut meshes, entity, chunk, chunk_mesh, transform);
     }
 
     // Process triplanar chunks (surface nets mode)
     for (entity, chunk, chunk_mesh, _material, transform) in triplanar_chunk_query.iter() {
-        process_chunk_for_grass(&mut commands, &assets, &mut meshes, entity, chunk, chunk_mesh, transform);
+        process_chunk_for_grass(&mut commands, &assets, &biomes, &mut meshes, entity, chunk, chunk_mesh, transform);
     }
 }
 
 /// Helper function to spawn grass on a chunk
+#[allow(clippy::too_many_arguments)]
 fn process_chunk_for_grass(
     commands: &mut Commands,
     assets: &Res<GrassPatchAssets>,
+    biomes: &BiomeMap,
     meshes: &mut ResMut<Assets<Mesh>>,
     entity: Entity,
     chunk: &ChunkMesh,
@@ -148,11 +157,21 @@ fn process_chunk_for_grass(
     };
 
     // Density: blades per square unit; max_count: limit per chunk
-    let instances = collect_grass_instances(chunk_source_mesh, transform, 20, 2000);
+    let mut instances = collect_grass_instances(chunk_source_mesh, transform, 20, 2000);
     if instances.is_empty() {
         return;
     }
 
+    // Blend the biome grass colors per column, so the tint fades across biome borders
+    let mut tints: HashMap<(i32, i32), [f32; 4]> = HashMap::new();
+    for instance in &mut instances {
+        let column = (instance.position.x.floor() as i32, instance.position.z.floor() as i32);
+        instance.tint = *tints.entry(column).or_insert_with(|| {
+            let [r, g, b] = biomes.grass_color(column.0, column.1);
+            [r, g, b, 1.0]
+        });
+    }
+
     let template_mesh = match meshes.get(&assets.blade_mesh) {
         Some(mesh) => mesh,
         None => return,
@@ -179,9 +198,28 @@ fn process_chunk_for_grass(
         Visibility::Visible,
         InheritedVisibility::VISIBLE,
         ViewVisibility::default(),
+        ChunkGrassPatch { chunk_position: chunk.chunk_position },
     ));
 }
 
+/// Despawn the grass of chunks that were streamed out
+pub fn despawn_unloaded_grass(
+    mut commands: Commands,
+    mut unloaded: MessageReader<ChunkUnloaded>,
+    grass_query: Query<(Entity, &ChunkGrassPatch)>,
+) {
+    let positions: HashSet<IVec3> = unloaded.read().map(|message| message.0).collect();
+    if positions.is_empty() {
+        return;
+    }
+
+    for (entity, patch) in grass_query.iter() {
+        if positions.contains(&patch.chunk_position) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
 /// Extract grass instances from a mesh by sampling upward-facing triangles
 fn collect_grass_instances(
     mesh: &Mesh,
@@ -285,7 +323,7 @@ fn collect_grass_instances(
             let bary = Vec3::new(1.0 - r1, r1 * (1.0 - r2), r1 * r2);
             let position = v0 * bary.x + v1 * bary.y + v2 * bary.z;
 
-            instances.push(GrassInstance { position, normal: normal_dir });
+            instances.push(GrassInstance { position, normal: normal_dir, tint: [1.0; 4] });
             if instances.len() >= max_count {
                 return instances;
             }
@@ -323,6 +361,7 @@ fn build_grass_patch_mesh(template: &Mesh, instances: &[GrassInstance]) -> Optio
     let mut out_positions = Vec::with_capacity(positions.len() * instances.len());
     let mut out_normals = Vec::with_capacity(normals.as_ref().map(|n| n.len()).unwrap_or(0) * instances.len());
     let mut out_uvs: Vec<[f32; 2]> = Vec::with_capacity(uvs.as_ref().map(|u| u.len()).unwrap_or(0) * instances.len());
+    let mut out_colors: Vec<[f32; 4]> = Vec::with_capacity(positions.len() * instances.len());
     let mut out_indices = Vec::with_capacity(indices.len() * instances.len());
 
     for (i, instance) in instances.iter().enumerate() {
@@ -361,6 +400,8 @@ fn build_grass_patch_mesh(template: &Mesh, instances: &[GrassInstance]) -> Optio
         if let Some(src_uvs) = &uvs {
             out_uvs.extend(src_uvs.iter());
         }
+
+        out_colors.extend(std::iter::repeat_n(instance.tint, positions.len()));
     }
 
     let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
@@ -374,6 +415,8 @@ fn build_grass_patch_mesh(template: &Mesh, instances: &[GrassInstance]) -> Optio
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, out_uvs);
     }
 
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, out_colors);
+
     mesh.insert_indices(Indices::U32(out_indices));
 
     Some(mesh)
@@ -579,6 +622,7 @@ pub fn spawn_rock_props(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     world: Res<VoxelWorld>,
+    seed: Res<WorldSeed>,
     mut spawned: ResMut<RocksSpawned>,
 ) {
     if spawned.0 {
@@ -620,7 +664,7 @@ pub fn spawn_rock_props(
             let world_z = z as i32;
 
             // Use hash to determine if rock spawns here
-            let hash = simple_hash(world_x * 31, world_z * 37);
+            let hash = seed.hash(world_x * 31, world_z * 37);
             if hash > 0.995 { // Very sparse rocks
                 // Find surface height
                 for y in (0..64).rev() {
@@ -829,7 +873,8 @@ impl Plugin for VegetationPlugin {
                 Update,
                 (
                     attach_procedural_grass_to_chunks,
-                    spawn_rock_props,
+                    despawn_unloaded_grass,
+                    spawn_rock_props.run_if(world_generated),
                     spawn_floating_particles,
                     animate_particles,
                 ),
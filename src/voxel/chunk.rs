@@ -1,18 +1,43 @@
 use crate::constants::{CHUNK_SIZE, CHUNK_VOLUME};
-use crate::voxel::types::VoxelType;
+use crate::voxel::palette::PalettedStorage;
+use crate::voxel::types::{Voxel, VoxelType};
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
 
-/// Serializable chunk data (voxels only)
-#[derive(Serialize, Deserialize)]
+/// Fill level from which a voxel counts as inside the terrain
+pub const DENSITY_THRESHOLD: u8 = 128;
+
+/// Serializable chunk data (voxels plus the density channel of sculpted chunks)
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ChunkData {
-    pub voxels: Vec<VoxelType>,
+    pub voxels: PalettedStorage,
     pub position: IVec3,
+    pub density: Option<Vec<u8>>,
+}
+
+/// Storage cost of a single chunk
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkMemoryStats {
+    /// Distinct voxel types in the chunk
+    pub palette_len: usize,
+    /// 0 for homogeneous chunks
+    pub bits_per_voxel: u8,
+    pub bytes: usize,
 }
 
 pub struct Chunk {
-    voxels: [VoxelType; CHUNK_VOLUME],
+    voxels: PalettedStorage,
+    /// Fill level of every voxel, 0 empty to 255 full. Only allocated once the chunk is
+    /// sculpted; until then the fill follows the voxel type.
+    density: Option<Vec<u8>>,
+    /// Sky light in the high and block light in the low four bits of every voxel. Not saved;
+    /// None until the chunk is lit after being loaded or generated.
+    light: Option<Vec<u8>>,
     dirty: bool,
+    /// Voxels changed since the chunk was last written to disk (separate from mesh dirty)
+    needs_save: bool,
+    /// Bumped on every edit, so meshes built from an older copy can be recognized as stale
+    version: u32,
     mesh_entity: Option<Entity>,
     water_mesh_entity: Option<Entity>,
     position: IVec3, // Chunk coords (not world)
@@ -21,24 +46,151 @@ pub struct Chunk {
 impl Chunk {
     pub fn new(position: IVec3) -> Self {
         Self {
-            voxels: [VoxelType::Air; CHUNK_VOLUME],
+            voxels: PalettedStorage::filled(VoxelType::Air),
+            density: None,
+            light: None,
             dirty: true,
+            needs_save: false,
+            version: 0,
             mesh_entity: None,
             water_mesh_entity: None,
             position,
         }
     }
 
+    pub(crate) fn voxels(&self) -> &PalettedStorage {
+        &self.voxels
+    }
+
     pub fn get(&self, local: UVec3) -> VoxelType {
         let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
-        self.voxels[index]
+        self.voxels.get(index)
     }
 
     pub fn set(&mut self, local: UVec3, voxel: VoxelType) {
         let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
-        if self.voxels[index] != voxel {
-            self.voxels[index] = voxel;
+        if self.voxels.set(index, voxel) {
+            // Placed and broken blocks are whole voxels
+            if let Some(density) = &mut self.density {
+                density[index] = full_density(voxel);
+            }
+            self.dirty = true;
+            self.needs_save = true;
+            self.version = self.version.wrapping_add(1);
+        }
+    }
+
+    /// Fill level of a voxel, 0 empty to 255 full
+    pub fn density(&self, local: UVec3) -> u8 {
+        let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
+        match &self.density {
+            Some(density) => density[index],
+            None => full_density(self.voxels.get(index)),
+        }
+    }
+
+    /// Set the fill level of a voxel, allocating the density channel on first use. The voxel
+    /// type follows the fill: it becomes `material` when filled past `DENSITY_THRESHOLD` and
+    /// air when emptied below it. Liquids keep their type.
+    pub fn set_density(&mut self, local: UVec3, density: u8, material: VoxelType) {
+        let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
+        let voxel = self.voxels.get(index);
+        if voxel.is_liquid() {
+            return;
+        }
+
+        let voxels = &self.voxels;
+        let channel = self
+            .density
+            .get_or_insert_with(|| (0..CHUNK_VOLUME).map(|i| full_density(voxels.get(i))).collect());
+        if channel[index] == density {
+            return;
+        }
+        channel[index] = density;
+
+        if density >= DENSITY_THRESHOLD && !voxel.is_solid() {
+            self.voxels.set(index, material);
+        } else if density < DENSITY_THRESHOLD && voxel.is_solid() {
+            self.voxels.set(index, VoxelType::Air);
+        }
+        self.dirty = true;
+        self.needs_save = true;
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Set a liquid together with its level as a fill. Partial fills allocate the density
+    /// channel like sculpting does.
+    pub fn set_liquid(&mut self, local: UVec3, liquid: VoxelType, fill: u8) {
+        let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
+        let mut changed = self.voxels.set(index, liquid);
+
+        let voxels = &self.voxels;
+        if self.density.is_some() || fill != full_density(liquid) {
+            let channel = self
+                .density
+                .get_or_insert_with(|| (0..CHUNK_VOLUME).map(|i| full_density(voxels.get(i))).collect());
+            changed |= channel[index] != fill;
+            channel[index] = fill;
+        }
+        if changed {
             self.dirty = true;
+            self.needs_save = true;
+            self.version = self.version.wrapping_add(1);
+        }
+    }
+
+    /// Whether the chunk has been sculpted or holds flowing liquid and carries its own fill levels
+    pub fn has_density(&self) -> bool {
+        self.density.is_some()
+    }
+
+    /// Packed light of a voxel, None until the chunk has been lit
+    pub fn light(&self, local: UVec3) -> Option<u8> {
+        let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
+        self.light.as_ref().map(|light| light[index])
+    }
+
+    /// Set the packed light of a voxel in a lit chunk. Light is derived from the voxels, so
+    /// this does not mark the chunk for saving or remeshing.
+    pub fn set_light(&mut self, local: UVec3, light: u8) {
+        let index = Self::index(local.x as usize, local.y as usize, local.z as usize);
+        if let Some(channel) = &mut self.light {
+            channel[index] = light;
+        }
+    }
+
+    /// Start lighting the chunk from darkness
+    pub fn reset_light(&mut self) {
+        self.light = Some(vec![0; CHUNK_VOLUME]);
+    }
+
+    /// Drop the light, so the chunk is lit again from scratch
+    pub fn clear_light(&mut self) {
+        self.light = None;
+    }
+
+    pub fn is_lit(&self) -> bool {
+        self.light.is_some()
+    }
+
+    /// All voxels in index order
+    pub fn iter(&self) -> impl Iterator<Item = VoxelType> + '_ {
+        self.voxels.iter()
+    }
+
+    /// Value of every voxel if the chunk is homogeneous (all air, all rock, ...)
+    pub fn single_value(&self) -> Option<VoxelType> {
+        self.voxels.single_value()
+    }
+
+    pub fn memory_stats(&self) -> ChunkMemoryStats {
+        ChunkMemoryStats {
+            palette_len: self.voxels.palette_len(),
+            bits_per_voxel: self.voxels.bits_per_voxel(),
+            bytes: std::mem::size_of::<Self>() - std::mem::size_of::<PalettedStorage>()
+                + self.voxels.memory_bytes()
+                + self.density.as_ref().map_or(0, Vec::len)
+                + self.light.as_ref().map_or(0, Vec::len),
         }
     }
 
@@ -48,12 +200,29 @@ impl Chunk {
 
     pub fn mark_dirty(&mut self) {
         self.dirty = true;
+        self.version = self.version.wrapping_add(1);
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
     }
 
     pub fn clear_dirty(&mut self) {
         self.dirty = false;
     }
 
+    pub fn needs_save(&self) -> bool {
+        self.needs_save
+    }
+
+    pub fn mark_needs_save(&mut self) {
+        self.needs_save = true;
+    }
+
+    pub fn clear_needs_save(&mut self) {
+        self.needs_save = false;
+    }
+
     pub fn set_mesh_entity(&mut self, entity: Entity) {
         self.mesh_entity = Some(entity);
     }
@@ -83,7 +252,7 @@ impl Chunk {
     }
 
     // For meshing - index conversion
-    fn index(x: usize, y: usize, z: usize) -> usize {
+    pub(crate) fn index(x: usize, y: usize, z: usize) -> usize {
         x + (y * CHUNK_SIZE) + (z * CHUNK_SIZE * CHUNK_SIZE)
     }
     
@@ -98,22 +267,34 @@ impl Chunk {
     /// Convert chunk to serializable data
     pub fn to_data(&self) -> ChunkData {
         ChunkData {
-            voxels: self.voxels.to_vec(),
+            // Drop palette entries left over from edits so saves stay small
+            voxels: self.voxels.compacted(),
             position: self.position,
+            density: self.density.clone(),
         }
     }
 
     /// Create chunk from serializable data
     pub fn from_data(data: ChunkData) -> Self {
-        let mut voxels = [VoxelType::Air; CHUNK_VOLUME];
-        for (i, v) in data.voxels.into_iter().enumerate() {
-            if i < CHUNK_VOLUME {
-                voxels[i] = v;
-            }
+        let mut voxels = data.voxels;
+        if !voxels.rebuild_counts() {
+            warn!("Corrupt voxel data in chunk {:?}, replacing with air", data.position);
+            voxels = PalettedStorage::filled(VoxelType::Air);
         }
+        let density = data.density.filter(|density| {
+            let valid = density.len() == CHUNK_VOLUME;
+            if !valid {
+                warn!("Corrupt density data in chunk {:?}, dropping it", data.position);
+            }
+            valid
+        });
         Self {
             voxels,
+            density,
+            light: None,
             dirty: true, // Mark dirty so mesh gets generated
+            needs_save: false,
+            version: 0,
             mesh_entity: None,
             water_mesh_entity: None,
             position: data.position,
@@ -121,3 +302,8 @@ impl Chunk {
     }
 }
 
+
+/// Fill level of a voxel that was never sculpted; liquids count as full like solids
+fn full_density(voxel: VoxelType) -> u8 {
+    if voxel.is_solid() || voxel.is_liquid() { 255 } else { 0 }
+}
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use std::collections::{HashSet, VecDeque};
+use crate::voxel::meshing::SNAPSHOT_BORDER;
+use crate::voxel::plugin::WorldConfig;
+use crate::voxel::types::{Voxel, VoxelType};
+use crate::voxel::world::VoxelWorld;
+
+/// Level of a water source. Flowing water loses one level per voxel it spreads sideways.
+pub const SOURCE_LEVEL: u8 = 8;
+
+const HORIZONTAL: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+const NEIGHBORS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+/// Sent when the player changed the voxels within `reach` of `center`
+#[derive(Message, Clone, Copy, Debug)]
+pub struct VoxelsEdited {
+    pub center: IVec3,
+    pub reach: i32,
+}
+
+/// Cellular water simulation. Positions that may change are queued and updated a limited
+/// number per step, so only water near edits costs anything and large floods spread over
+/// several steps instead of stalling a frame.
+#[derive(Resource, Default)]
+pub struct FluidSimulation {
+    queue: VecDeque<IVec3>,
+    /// Positions in the queue, so each is updated once per step
+    scheduled: HashSet<IVec3>,
+    /// Seconds since the last step
+    elapsed: f32,
+}
+
+impl FluidSimulation {
+    /// Queue a position for the next step
+    pub fn schedule(&mut self, pos: IVec3) {
+        if self.scheduled.insert(pos) {
+            self.queue.push_back(pos);
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Update the positions due this step, at most `max_updates`, and return the ones that
+    /// changed. Positions queued by this step wait for the next one, so water moves one voxel
+    /// per step.
+    pub fn step(&mut self, world: &mut VoxelWorld, max_updates: usize) -> Vec<IVec3> {
+        let count = self.queue.len().min(max_updates);
+
+        // Decide every update before applying any, so the result does not depend on queue order
+        let mut updates = Vec::with_capacity(count);
+        for pos in self.queue.drain(..count) {
+            self.scheduled.remove(&pos);
+            if let Some(update) = next_state(world, pos) {
+                updates.push((pos, update));
+            }
+        }
+
+        let mut changed = Vec::with_capacity(updates.len());
+        for (pos, (voxel, level)) in updates {
+            if voxel.is_liquid() {
+                world.set_liquid(pos, voxel, level_fill(level));
+            } else {
+                world.set_voxel(pos, voxel);
+            }
+            for offset in NEIGHBORS {
+                self.schedule(pos + offset);
+            }
+            changed.push(pos);
+        }
+        changed
+    }
+}
+
+/// Fill a water level is stored as in the density channel
+pub fn level_fill(level: u8) -> u8 {
+    (level.min(SOURCE_LEVEL) as u32 * 255 / SOURCE_LEVEL as u32) as u8
+}
+
+/// Level of the liquid at a position, 0 if there is none
+pub fn water_level(world: &VoxelWorld, pos: IVec3) -> u8 {
+    match world.get_voxel(pos) {
+        Some(VoxelType::Water) => SOURCE_LEVEL,
+        Some(voxel) if voxel.is_liquid() => {
+            let fill = world.get_density(pos).unwrap_or(255) as u32;
+            ((fill * SOURCE_LEVEL as u32 + 127) / 255) as u8
+        }
+        _ => 0,
+    }
+}
+
+/// What a position should hold given its neighbors, None if it stays as it is. Sources never
+/// change; air and flowing water take the level the water around them gives them.
+fn next_state(world: &VoxelWorld, pos: IVec3) -> Option<(VoxelType, u8)> {
+    let current = world.get_voxel(pos)?;
+    if current != VoxelType::Air && current != VoxelType::FlowingWater {
+        return None;
+    }
+
+    // Water surrounded by sources on a floor becomes a source itself
+    let sources = HORIZONTAL
+        .iter()
+        .filter(|&&offset| world.get_voxel(pos + offset) == Some(VoxelType::Water))
+        .count();
+    if sources >= 2 && rests_on_floor(world, pos) {
+        return Some((VoxelType::Water, SOURCE_LEVEL));
+    }
+
+    // Falling water stays full; otherwise it spreads sideways from water lying on something
+    let level = if world.get_voxel(pos + IVec3::Y).is_some_and(|voxel| voxel.is_liquid()) {
+        SOURCE_LEVEL
+    } else {
+        HORIZONTAL
+            .iter()
+            .map(|&offset| pos + offset)
+            .filter(|&neighbor| rests_on_floor(world, neighbor))
+            .map(|neighbor| water_level(world, neighbor).saturating_sub(1))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let next = if level == 0 { VoxelType::Air } else { VoxelType::FlowingWater };
+    let unchanged = next == current && (level == 0 || water_level(world, pos) == level);
+    (!unchanged).then_some((next, level))
+}
+
+/// Whether water at a position is held up by a solid voxel or a source below it. Water over
+/// air or flowing water falls instead of spreading. Unloaded voxels count as solid.
+fn rests_on_floor(world: &VoxelWorld, pos: IVec3) -> bool {
+    match world.get_voxel(pos + IVec3::NEG_Y) {
+        Some(voxel) => voxel.is_solid() || voxel == VoxelType::Water,
+        None => true,
+    }
+}
+
+/// Run condition: fluids.enabled in world.yaml
+pub fn fluids_enabled(world_config: Res<WorldConfig>) -> bool {
+    world_config.fluids.enabled
+}
+
+/// Queue the voxels around player edits that may start or stop water flowing
+pub fn schedule_edited_fluids(
+    mut edited: MessageReader<VoxelsEdited>,
+    mut simulation: ResMut<FluidSimulation>,
+    world: Res<VoxelWorld>,
+) {
+    for edit in edited.read() {
+        // One voxel further out, so water next to the edit notices it
+        let reach = edit.reach + 1;
+        for z in -reach..=reach {
+            for y in -reach..=reach {
+                for x in -reach..=reach {
+                    let pos = edit.center + IVec3::new(x, y, z);
+                    let near_water = std::iter::once(IVec3::ZERO)
+                        .chain(NEIGHBORS)
+                        .any(|offset| world.get_voxel(pos + offset).is_some_and(|voxel| voxel.is_liquid()));
+                    if near_water {
+                        simulation.schedule(pos);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Advance the simulation every `fluids.tick_seconds` and remesh the chunks whose water changed
+pub fn step_fluids(
+    time: Res<Time>,
+    world_config: Res<WorldConfig>,
+    mut simulation: ResMut<FluidSimulation>,
+    mut world: ResMut<VoxelWorld>,
+) {
+    simulation.elapsed += time.delta_secs();
+    if simulation.elapsed < world_config.fluids.tick_seconds {
+        return;
+    }
+    // One step per frame at most, a slow frame does not queue up more
+    simulation.elapsed = 0.0;
+    if simulation.pending() == 0 {
+        return;
+    }
+
+    let changed = simulation.step(&mut world, world_config.fluids.max_updates_per_tick);
+    for pos in changed {
+        world.mark_dirty_around(pos, SNAPSHOT_BORDER);
+    }
+}
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use std::sync::Arc;
+use crate::config::biomes::{BiomeLayer, BiomesConfigFile, ClimateSection};
+use crate::voxel::generation::GeneratorError;
+use crate::voxel::generation::perlin::fbm3;
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::types::VoxelType;
+use crate::voxel::world::WorldSeed;
+
+/// A biome from biomes.yaml with its voxel names resolved
+#[derive(Clone, Debug)]
+pub struct Biome {
+    pub id: String,
+    /// Temperature and humidity the biome is found in
+    pub climate: Vec2,
+    pub height_offset: f32,
+    pub roughness: f32,
+    /// (voxel, thickness) from the surface down
+    pub layers: Vec<(VoxelType, u32)>,
+    pub shore_layers: Vec<(VoxelType, u32)>,
+    pub base: VoxelType,
+    pub grass_color: [f32; 3],
+    /// (creature, chance) pairs
+    pub fauna: Vec<(String, f32)>,
+}
+
+impl Biome {
+    /// Voxel `depth` voxels below the surface, 0 being the surface voxel itself
+    pub fn voxel_at_depth(&self, depth: i32, shore: bool) -> VoxelType {
+        let layers = if shore && !self.shore_layers.is_empty() { &self.shore_layers } else { &self.layers };
+        let mut top = 0;
+        for &(voxel, thickness) in layers {
+            top += thickness as i32;
+            if depth < top {
+                return voxel;
+            }
+        }
+        self.base
+    }
+
+    /// Chance of a creature spawning on a column of this biome, 0 if it does not live here
+    pub fn spawn_chance(&self, creature: &str) -> f32 {
+        self.fauna
+            .iter()
+            .find(|(name, _)| name == creature)
+            .map_or(0.0, |&(_, chance)| chance)
+    }
+}
+
+/// Temperature and humidity at a column, both roughly 0..=1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+/// How much each biome contributes to a column, indices into `BiomeMap::biomes`. Weights
+/// sum to 1 and the dominant biome comes first.
+#[derive(Clone, Debug)]
+pub struct BiomeBlend {
+    pub weights: Vec<(usize, f32)>,
+}
+
+impl BiomeBlend {
+    pub fn dominant(&self) -> usize {
+        self.weights[0].0
+    }
+
+    /// Weighted average of a per-biome value
+    pub fn mix(&self, biomes: &[Biome], value: impl Fn(&Biome) -> f32) -> f32 {
+        self.weights.iter().map(|&(index, weight)| value(&biomes[index]) * weight).sum()
+    }
+}
+
+/// Picks biomes from temperature and humidity noise. Columns near the border between biomes
+/// blend them, so heights and colors change smoothly and layers are dithered instead of
+/// switching along a hard line.
+#[derive(Resource, Clone)]
+pub struct BiomeMap {
+    seed: WorldSeed,
+    climate: ClimateSection,
+    biomes: Arc<[Biome]>,
+}
+
+impl BiomeMap {
+    pub fn new(seed: WorldSeed, config: &BiomesConfigFile, registry: &VoxelRegistry) -> Result<Self, GeneratorError> {
+        let voxel = |name: &str| registry.id(name).ok_or_else(|| GeneratorError::UnknownVoxelType(name.to_string()));
+        let layers = |layers: &[BiomeLayer]| {
+            layers
+                .iter()
+                .map(|layer| voxel(&layer.voxel).map(|voxel| (voxel, layer.thickness)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let biomes = config
+            .biomes
+            .iter()
+            .map(|def| {
+                Ok(Biome {
+                    id: def.id.clone(),
+                    climate: Vec2::new(def.temperature, def.humidity),
+                    height_offset: def.height_offset,
+                    roughness: def.roughness,
+                    layers: layers(&def.layers)?,
+                    shore_layers: layers(&def.shore_layers)?,
+                    base: voxel(&def.base)?,
+                    grass_color: def.grass_color,
+                    fauna: def.fauna.iter().map(|spawn| (spawn.creature.clone(), spawn.chance)).collect(),
+                })
+            })
+            .collect::<Result<Vec<_>, GeneratorError>>()?;
+        if biomes.is_empty() {
+            return Err(GeneratorError::InvalidOptions("no biomes defined".to_string()));
+        }
+        Ok(Self { seed, climate: config.climate, biomes: biomes.into() })
+    }
+
+    /// The built-in biomes with the built-in voxel types
+    pub fn builtin(seed: WorldSeed) -> Self {
+        Self::new(seed, &BiomesConfigFile::default(), &VoxelRegistry::builtin())
+            .expect("built-in biomes only use built-in voxel types")
+    }
+
+    pub fn biomes(&self) -> &[Biome] {
+        &self.biomes
+    }
+
+    pub fn climate(&self, world_x: i32, world_z: i32) -> Climate {
+        let p = Vec3::new(world_x as f32, 0.0, world_z as f32) * self.climate.scale;
+        // Separate slices of the noise, so temperature and humidity are unrelated
+        let sample = |y: f32| (fbm3(self.seed, p + Vec3::Y * y, 3) * 1.5 + 0.5).clamp(0.0, 1.0);
+        Climate { temperature: sample(0.5), humidity: sample(100.5) }
+    }
+
+    /// Weights of the biomes whose climate is within the blend distance of the closest one
+    pub fn blend(&self, world_x: i32, world_z: i32) -> BiomeBlend {
+        let climate = self.climate(world_x, world_z);
+        let point = Vec2::new(climate.temperature, climate.humidity);
+        let distances: Vec<f32> = self.biomes.iter().map(|biome| biome.climate.distance(point)).collect();
+        let closest = distances.iter().copied().fold(f32::MAX, f32::min);
+
+        let mut weights: Vec<(usize, f32)> = distances
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &distance)| {
+                let t = 1.0 - (distance - closest) / self.climate.blend;
+                (t > 0.0).then_some((index, t * t * (3.0 - 2.0 * t)))
+            })
+            .collect();
+        weights.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+        for (_, weight) in &mut weights {
+            *weight /= total;
+        }
+        BiomeBlend { weights }
+    }
+
+    /// Biome with the closest climate
+    pub fn biome_at(&self, world_x: i32, world_z: i32) -> &Biome {
+        &self.biomes[self.blend(world_x, world_z).dominant()]
+    }
+
+    /// Biome whose layers a column uses. Picked among the blended biomes with a per-column
+    /// random roll, so borders are a scattered mix of both biomes.
+    pub fn surface_biome(&self, blend: &BiomeBlend, world_x: i32, world_z: i32) -> &Biome {
+        let mut roll = self.seed.hash3(world_x, 9_001, world_z);
+        for &(index, weight) in &blend.weights {
+            roll -= weight;
+            if roll < 0.0 {
+                return &self.biomes[index];
+            }
+        }
+        &self.biomes[blend.dominant()]
+    }
+
+    pub fn grass_color(&self, world_x: i32, world_z: i32) -> [f32; 3] {
+        let blend = self.blend(world_x, world_z);
+        std::array::from_fn(|i| blend.mix(&self.biomes, |biome| biome.grass_color[i]))
+    }
+
+    /// Chance of a creature spawning on a column, blended across biome borders
+    pub fn spawn_chance(&self, world_x: i32, world_z: i32, creature: &str) -> f32 {
+        self.blend(world_x, world_z).mix(&self.biomes, |biome| biome.spawn_chance(creature))
+    }
+}
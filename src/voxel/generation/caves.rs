@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use crate::constants::{CHUNK_SIZE, CHUNK_SIZE_I32};
+use crate::voxel::generation::perlin::{fbm3, perlin3};
+use crate::voxel::world::WorldSeed;
+
+/// Horizontal size of the regions worms start in. Each region holds a few worms.
+const WORM_REGION: i32 = 96;
+
+/// Most worms one region starts
+const MAX_WORMS_PER_REGION: i32 = 6;
+
+/// Steps of one voxel a worm takes at most. Worms never leave the regions next to the one
+/// they start in, so a chunk only has to look at those.
+const MAX_WORM_LENGTH: i32 = 90;
+
+/// Worm tunnel radius range in voxels
+const MIN_WORM_RADIUS: f32 = 1.5;
+const MAX_WORM_RADIUS: f32 = 3.0;
+
+/// Heights worms start between
+const WORM_MIN_START_Y: i32 = 8;
+const WORM_MAX_START_Y: i32 = 40;
+
+/// Cheese caves: large open chambers where low frequency noise is above the threshold
+const CHEESE_FREQUENCY: f32 = 0.025;
+const CHEESE_THRESHOLD: f32 = 0.33;
+/// Chambers are flattened, they are wider than tall
+const CHEESE_SQUASH: f32 = 1.8;
+/// Chambers only open below this height
+const CHEESE_MAX_Y: i32 = 36;
+
+/// Carves worm tunnels and cheese chambers out of the ground. Only says which positions are
+/// hollow; the terrain generator decides where caves may actually open, so they stay clear of
+/// the surface, water, bedrock and dungeons.
+pub struct CaveCarver {
+    seed: WorldSeed,
+}
+
+/// Positions of one chunk carved out by caves
+pub struct ChunkCaves {
+    carved: Vec<bool>,
+}
+
+impl ChunkCaves {
+    fn index(local: UVec3) -> usize {
+        (local.x + local.y * CHUNK_SIZE as u32 + local.z * (CHUNK_SIZE * CHUNK_SIZE) as u32) as usize
+    }
+
+    pub fn is_carved(&self, local: UVec3) -> bool {
+        self.carved[Self::index(local)]
+    }
+}
+
+/// Center and radius of one step along a worm
+#[derive(Clone, Copy, Debug)]
+pub struct WormPoint {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl CaveCarver {
+    pub fn new(seed: WorldSeed) -> Self {
+        Self { seed }
+    }
+
+    /// Carved positions of the chunk at chunk coords `chunk_pos`
+    pub fn carve_chunk(&self, chunk_pos: IVec3) -> ChunkCaves {
+        let origin = chunk_pos * CHUNK_SIZE_I32;
+        let mut carved = vec![false; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+
+        for z in 0..CHUNK_SIZE as u32 {
+            for y in 0..CHUNK_SIZE as u32 {
+                for x in 0..CHUNK_SIZE as u32 {
+                    let local = UVec3::new(x, y, z);
+                    if self.is_cheese(origin + local.as_ivec3()) {
+                        carved[ChunkCaves::index(local)] = true;
+                    }
+                }
+            }
+        }
+
+        let chunk_min = origin.as_vec3();
+        let chunk_max = chunk_min + Vec3::splat(CHUNK_SIZE as f32);
+        for point in self.worms_near(chunk_pos).into_iter().flatten() {
+            // Skip steps whose sphere misses the chunk
+            let reach = Vec3::splat(point.radius);
+            if (point.center + reach).cmplt(chunk_min).any() || (point.center - reach).cmpge(chunk_max).any() {
+                continue;
+            }
+            let min = (point.center - reach - chunk_min).floor().as_ivec3().max(IVec3::ZERO);
+            let max = (point.center + reach - chunk_min).ceil().as_ivec3().min(IVec3::splat(CHUNK_SIZE_I32 - 1));
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        let local = IVec3::new(x, y, z);
+                        let cell_center = chunk_min + local.as_vec3() + Vec3::splat(0.5);
+                        if cell_center.distance_squared(point.center) <= point.radius * point.radius {
+                            carved[ChunkCaves::index(local.as_uvec3())] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        ChunkCaves { carved }
+    }
+
+    /// Whether a single position is carved, without carving its whole chunk
+    pub fn is_carved(&self, pos: IVec3) -> bool {
+        let cell_center = pos.as_vec3() + Vec3::splat(0.5);
+        self.is_cheese(pos)
+            || self
+                .worms_near(pos.div_euclid(IVec3::splat(CHUNK_SIZE_I32)))
+                .iter()
+                .flatten()
+                .any(|point| cell_center.distance_squared(point.center) <= point.radius * point.radius)
+    }
+
+    /// Whether a position lies in a cheese chamber
+    pub fn is_cheese(&self, pos: IVec3) -> bool {
+        if pos.y > CHEESE_MAX_Y {
+            return false;
+        }
+        let p = pos.as_vec3() * Vec3::new(1.0, CHEESE_SQUASH, 1.0) * CHEESE_FREQUENCY;
+        // Chambers shrink toward their top height instead of ending in a flat ceiling
+        let taper = (pos.y as f32 / CHEESE_MAX_Y as f32).powi(3) * 0.2;
+        fbm3(self.seed, p, 2) > CHEESE_THRESHOLD + taper
+    }
+
+    /// Paths of every worm that may reach into the chunk at `chunk_pos`
+    pub fn worms_near(&self, chunk_pos: IVec3) -> Vec<Vec<WormPoint>> {
+        let center = (chunk_pos * CHUNK_SIZE_I32).xz().div_euclid(IVec2::splat(WORM_REGION));
+        let mut worms = Vec::new();
+        for rz in -1..=1 {
+            for rx in -1..=1 {
+                let region = center + IVec2::new(rx, rz);
+                for index in 0..self.worm_count(region) {
+                    worms.push(self.worm(region, index));
+                }
+            }
+        }
+        worms
+    }
+
+    fn worm_count(&self, region: IVec2) -> i32 {
+        let roll = self.seed.hash3(region.x, -7, region.y);
+        (roll * (MAX_WORMS_PER_REGION + 1) as f32) as i32
+    }
+
+    /// Steps of a worm. Its heading turns with 3D noise along the way, and it keeps to the
+    /// band worms start in so it does not tunnel into bedrock or out of the ground.
+    fn worm(&self, region: IVec2, index: i32) -> Vec<WormPoint> {
+        let roll = |salt: i32| self.seed.hash3(region.x.wrapping_mul(31).wrapping_add(index), salt, region.y);
+        let start = Vec3::new(
+            (region.x * WORM_REGION) as f32 + roll(1) * WORM_REGION as f32,
+            WORM_MIN_START_Y as f32 + roll(2) * (WORM_MAX_START_Y - WORM_MIN_START_Y) as f32,
+            (region.y * WORM_REGION) as f32 + roll(3) * WORM_REGION as f32,
+        );
+        let length = MAX_WORM_LENGTH / 2 + (roll(4) * (MAX_WORM_LENGTH / 2) as f32) as i32;
+        let mut yaw = roll(5) * std::f32::consts::TAU;
+        let mut pitch = 0.0f32;
+        let noise_offset = Vec3::new(roll(6), roll(7), roll(8)) * 1000.0;
+
+        let mut position = start;
+        let mut points = Vec::with_capacity(length as usize);
+        for step in 0..length {
+            let t = step as f32 * 0.06;
+            let sample = noise_offset + Vec3::new(t, 0.0, 0.0);
+            yaw += perlin3(self.seed, sample) * 0.35;
+            pitch = (pitch + perlin3(self.seed, sample + Vec3::Y * 50.0) * 0.2).clamp(-0.6, 0.6);
+            // Steer back toward the band worms start in
+            if position.y < WORM_MIN_START_Y as f32 {
+                pitch = pitch.abs();
+            } else if position.y > WORM_MAX_START_Y as f32 {
+                pitch = -pitch.abs();
+            }
+
+            let width = (perlin3(self.seed, sample + Vec3::Z * 50.0) * 0.5 + 0.5).clamp(0.0, 1.0);
+            let radius = MIN_WORM_RADIUS + width * (MAX_WORM_RADIUS - MIN_WORM_RADIUS);
+            points.push(WormPoint { center: position, radius });
+            position += Vec3::new(yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+        }
+        points
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use std::sync::Arc;
+use crate::config::ores::{OresConfigFile, MAX_VEIN_SIZE};
+use crate::constants::CHUNK_SIZE_I32;
+use crate::voxel::chunk::Chunk;
+use crate::voxel::generation::{BiomeMap, Dungeon, GeneratorContext, GeneratorError, TerrainGenerator};
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::types::VoxelType;
+use crate::voxel::world::WorldSeed;
+
+/// Steps a vein grows in
+const VEIN_DIRECTIONS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+/// A deposit from ores.yaml with its names resolved
+struct Deposit {
+    /// Separate seed per deposit, so deposits of the same ore do not place the same veins
+    seed: WorldSeed,
+    ore: VoxelType,
+    hosts: Vec<VoxelType>,
+    vein_size: u32,
+    veins_per_chunk: f32,
+    min_y: i32,
+    max_y: i32,
+    /// Indices into `BiomeMap::biomes`, every biome if empty
+    biomes: Vec<usize>,
+}
+
+/// Places ore veins into generated terrain. Veins start at seeded positions in each chunk and
+/// grow into a blob of up to `vein_size` voxels, which can spill into the chunks around it, so
+/// every chunk also places the parts of its neighbors' veins that reach into it.
+pub struct DepositGenerator {
+    deposits: Vec<Deposit>,
+    biomes: BiomeMap,
+}
+
+impl DepositGenerator {
+    pub fn new(seed: WorldSeed, config: &OresConfigFile, biomes: BiomeMap, registry: &VoxelRegistry) -> Result<Self, GeneratorError> {
+        let voxel = |name: &str| registry.id(name).ok_or_else(|| GeneratorError::UnknownVoxelType(name.to_string()));
+        let deposits = config
+            .deposits
+            .iter()
+            .enumerate()
+            .map(|(index, def)| {
+                let biome = |id: &String| {
+                    biomes
+                        .biomes()
+                        .iter()
+                        .position(|biome| &biome.id == id)
+                        .ok_or_else(|| GeneratorError::UnknownBiome(id.clone()))
+                };
+                Ok(Deposit {
+                    seed: WorldSeed(seed.0.wrapping_add((index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15))),
+                    ore: voxel(&def.ore)?,
+                    hosts: def.hosts.iter().map(|host| voxel(host)).collect::<Result<_, _>>()?,
+                    vein_size: def.vein_size.clamp(1, MAX_VEIN_SIZE),
+                    veins_per_chunk: def.veins_per_chunk.max(0.0),
+                    min_y: def.min_y,
+                    max_y: def.max_y,
+                    biomes: def.biomes.iter().map(biome).collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, GeneratorError>>()?;
+        Ok(Self { deposits, biomes })
+    }
+
+    /// Deposits and biomes of the current world
+    pub fn from_context(ctx: &GeneratorContext) -> Result<Self, GeneratorError> {
+        let biomes = BiomeMap::new(ctx.seed, ctx.biomes, ctx.registry)?;
+        Self::new(ctx.seed, ctx.ores, biomes, ctx.registry)
+    }
+
+    /// The built-in deposits and biomes with the built-in voxel types
+    pub fn builtin(seed: WorldSeed) -> Self {
+        Self::new(seed, &OresConfigFile::default(), BiomeMap::builtin(seed), &VoxelRegistry::builtin())
+            .expect("built-in deposits only use built-in voxel types and biomes")
+    }
+
+    /// Replace host voxels of the chunk at chunk coords `pos` with the veins passing through it
+    pub fn place_ores(&self, pos: IVec3, chunk: &mut Chunk) {
+        if chunk.single_value() == Some(VoxelType::Air) {
+            return;
+        }
+        let origin = pos * CHUNK_SIZE_I32;
+        for deposit in &self.deposits {
+            for dz in -1..=1 {
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        for vein in self.veins(deposit, pos + IVec3::new(dx, dy, dz)) {
+                            for voxel in vein {
+                                let local = voxel - origin;
+                                if local.cmplt(IVec3::ZERO).any()
+                                    || local.cmpge(IVec3::splat(CHUNK_SIZE_I32)).any()
+                                    || !(deposit.min_y..=deposit.max_y).contains(&voxel.y)
+                                {
+                                    continue;
+                                }
+                                let local = local.as_uvec3();
+                                if deposit.hosts.contains(&chunk.get(local)) {
+                                    chunk.set(local, deposit.ore);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Voxels of the veins of a deposit that start in the chunk at `chunk_pos`
+    fn veins(&self, deposit: &Deposit, chunk_pos: IVec3) -> Vec<Vec<IVec3>> {
+        // Veins start within the depth range
+        let origin = chunk_pos * CHUNK_SIZE_I32;
+        let bottom = origin.y.max(deposit.min_y);
+        let top = (origin.y + CHUNK_SIZE_I32 - 1).min(deposit.max_y);
+        if bottom > top {
+            return Vec::new();
+        }
+
+        let roll = |vein: i32, salt: i32| {
+            deposit.seed.hash3(
+                chunk_pos.x.wrapping_mul(31).wrapping_add(vein),
+                chunk_pos.y.wrapping_mul(1024).wrapping_add(salt),
+                chunk_pos.z,
+            )
+        };
+        let whole = deposit.veins_per_chunk.floor();
+        let count = whole as i32 + i32::from(roll(-1, 0) < deposit.veins_per_chunk - whole);
+
+        let offset = |vein: i32, salt: i32, size: i32| ((roll(vein, salt) * size as f32) as i32).min(size - 1);
+        (0..count)
+            .filter_map(|vein| {
+                let start = IVec3::new(
+                    origin.x + offset(vein, 1, CHUNK_SIZE_I32),
+                    bottom + offset(vein, 2, top - bottom + 1),
+                    origin.z + offset(vein, 3, CHUNK_SIZE_I32),
+                );
+                if !deposit.biomes.is_empty() && !deposit.biomes.contains(&self.biomes.blend(start.x, start.z).dominant()) {
+                    return None;
+                }
+
+                // Each step grows the blob by one voxel next to a random voxel already in it
+                let mut voxels = Vec::with_capacity(deposit.vein_size as usize);
+                voxels.push(start);
+                for step in 1..deposit.vein_size as i32 {
+                    let from = voxels[offset(vein, 2 * step + 4, voxels.len() as i32) as usize];
+                    let direction = VEIN_DIRECTIONS[offset(vein, 2 * step + 5, VEIN_DIRECTIONS.len() as i32) as usize];
+                    voxels.push(from + direction);
+                }
+                Some(voxels)
+            })
+            .collect()
+    }
+}
+
+/// A terrain generator followed by the ore deposit pass
+pub struct WithDeposits {
+    pub terrain: Box<dyn TerrainGenerator>,
+    pub deposits: DepositGenerator,
+}
+
+impl TerrainGenerator for WithDeposits {
+    fn generate_chunk(&self, pos: IVec3, chunk: &mut Chunk) {
+        self.terrain.generate_chunk(pos, chunk);
+        self.deposits.place_ores(pos, chunk);
+    }
+
+    fn dungeon_at(&self, world_x: i32, world_z: i32) -> Option<Arc<Dungeon>> {
+        self.terrain.dungeon_at(world_x, world_z)
+    }
+}
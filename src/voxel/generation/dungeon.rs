@@ -0,0 +1,526 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::voxel::generation::Rolls;
+use crate::voxel::types::VoxelType;
+use crate::voxel::world::WorldSeed;
+
+/// Dungeons are laid out per cell of this many voxels along x and z, at most one per cell.
+/// Cells line up with chunk borders, so every chunk lies in a single cell.
+pub const DUNGEON_CELL: i32 = 96;
+
+/// Chance of a cell having a dungeon
+const DUNGEON_CHANCE: f32 = 0.75;
+
+/// Voxels between a dungeon and the border of its cell, so dungeons never touch
+const CELL_MARGIN: i32 = 8;
+
+/// Range of the dungeon footprint along x and z
+const MIN_DUNGEON_SIZE: i32 = 32;
+const MAX_DUNGEON_SIZE: i32 = 64;
+
+/// Areas are split in two while both halves can be at least this large
+const MIN_LEAF: i32 = 13;
+
+/// Smallest room interior along x and z
+const MIN_ROOM: i32 = 5;
+
+/// Lowest and highest floor voxel of a room. Floors sit on top of the bedrock.
+const MIN_FLOOR_Y: i32 = 3;
+const MAX_FLOOR_Y: i32 = 8;
+
+/// Range of room interior heights
+const MIN_ROOM_HEIGHT: i32 = 3;
+const MAX_ROOM_HEIGHT: i32 = 7;
+
+/// Corridors are this wide and tall inside
+const CORRIDOR_WIDTH: i32 = 3;
+const CORRIDOR_HEIGHT: i32 = 3;
+
+/// Chance of one more corridor between two random rooms, so the layout is not always a tree
+const LOOP_CHANCE: f32 = 0.5;
+
+/// Most entrance shafts per dungeon
+const MAX_ENTRANCES: usize = 3;
+
+/// Entrance shafts have this much room inside, with a spiral of steps around the middle
+const SHAFT_SIZE: i32 = 3;
+
+/// Layouts kept around; chunks of the same cell are usually generated close together
+const CACHE_SIZE: usize = 64;
+
+/// Inclusive box of voxels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoxelBox {
+    pub min: IVec3,
+    pub max: IVec3,
+}
+
+impl VoxelBox {
+    pub fn contains(&self, pos: IVec3) -> bool {
+        pos.cmpge(self.min).all() && pos.cmple(self.max).all()
+    }
+
+    /// The box grown by `by` voxels on every side
+    pub fn expand(&self, by: i32) -> Self {
+        Self { min: self.min - IVec3::splat(by), max: self.max + IVec3::splat(by) }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    pub fn center(&self) -> IVec3 {
+        (self.min + self.max) / 2
+    }
+}
+
+/// A room of a dungeon
+#[derive(Clone, Debug)]
+pub struct Room {
+    /// Air inside the room, from the voxel above the floor to the one below the ceiling
+    pub interior: VoxelBox,
+}
+
+impl Room {
+    /// Y of the floor voxel
+    pub fn floor_y(&self) -> i32 {
+        self.interior.min.y - 1
+    }
+
+    /// Middle of the floor, where creatures can stand
+    pub fn spawn_point(&self) -> IVec3 {
+        IVec3::new(self.interior.center().x, self.interior.min.y, self.interior.center().z)
+    }
+}
+
+/// A corridor from the middle of one room to another: first along x, then along z. Its
+/// floor ramps up or down one voxel per step, halfway between the rooms.
+#[derive(Clone, Debug)]
+pub struct Corridor {
+    pub rooms: (usize, usize),
+    start: IVec3,
+    end: IVec3,
+    /// Distance along the path where the ramp starts
+    ramp_start: i32,
+}
+
+impl Corridor {
+    fn new(rooms: (usize, usize), from: &Room, to: &Room) -> Self {
+        let start = from.spawn_point();
+        let end = to.spawn_point();
+        let mut corridor = Self { rooms, start, end, ramp_start: 0 };
+
+        // Center the ramp between the room walls the corridor passes through
+        let length = corridor.length();
+        let inside = |room: &Room, s: i32| {
+            let p = corridor.point(s);
+            room.interior.contains(IVec3::new(p.x, room.interior.min.y, p.y))
+        };
+        let exit = (0..=length).find(|&s| !inside(from, s)).unwrap_or(length);
+        let entry = (0..=length).rev().find(|&s| !inside(to, s)).unwrap_or(exit);
+        corridor.ramp_start = (exit + entry) / 2 - (end.y - start.y).abs() / 2;
+        corridor
+    }
+
+    fn corner(&self) -> IVec2 {
+        IVec2::new(self.end.x, self.start.z)
+    }
+
+    fn length(&self) -> i32 {
+        (self.end.x - self.start.x).abs() + (self.end.z - self.start.z).abs()
+    }
+
+    /// Column at distance `s` along the path
+    fn point(&self, s: i32) -> IVec2 {
+        let along_x = (self.end.x - self.start.x).abs();
+        if s <= along_x {
+            IVec2::new(self.start.x + s * (self.end.x - self.start.x).signum(), self.start.z)
+        } else {
+            IVec2::new(self.end.x, self.start.z + (s - along_x) * (self.end.z - self.start.z).signum())
+        }
+    }
+
+    /// Y of the air voxel just above the floor at distance `s` along the path
+    fn floor_at(&self, s: i32) -> i32 {
+        let rise = self.end.y - self.start.y;
+        self.start.y + rise.signum() * (s - self.ramp_start).clamp(0, rise.abs())
+    }
+
+    /// The two straight legs of the corridor as (box of columns, distance at its start, axis)
+    fn legs(&self) -> [(IRect, i32, bool); 2] {
+        let half = CORRIDOR_WIDTH / 2;
+        let corner = self.corner();
+        let along_x = IRect::from_corners(self.start.xz(), corner).inflate(half);
+        let along_z = IRect::from_corners(corner, self.end.xz()).inflate(half);
+        [(along_x, 0, true), (along_z, (self.end.x - self.start.x).abs(), false)]
+    }
+
+    /// Whether the corridor's floor or walls take up a column
+    pub fn covers(&self, column: IVec2) -> bool {
+        self.distance_at(column).is_some()
+    }
+
+    /// Distance along the path next to a column, or None if the corridor does not cover it
+    fn distance_at(&self, column: IVec2) -> Option<i32> {
+        self.legs().into_iter().find_map(|(rect, offset, along_x)| {
+            rect.contains(column).then(|| {
+                let s = if along_x {
+                    (column.x - self.start.x).abs()
+                } else {
+                    offset + (column.y - self.start.z).abs()
+                };
+                s.clamp(0, self.length())
+            })
+        })
+    }
+
+    fn contains(&self, pos: IVec3) -> bool {
+        self.distance_at(pos.xz()).is_some_and(|s| {
+            let floor = self.floor_at(s);
+            (floor..floor + CORRIDOR_HEIGHT).contains(&pos.y)
+        })
+    }
+
+    fn bounds(&self) -> VoxelBox {
+        let [(a, _, _), (b, _, _)] = self.legs();
+        let rect = a.union(b);
+        VoxelBox {
+            min: IVec3::new(rect.min.x, self.start.y.min(self.end.y), rect.min.y),
+            max: IVec3::new(rect.max.x, self.start.y.max(self.end.y) + CORRIDOR_HEIGHT - 1, rect.max.y),
+        }
+    }
+}
+
+/// A shaft from a room up to the surface, with a spiral of steps along its sides
+#[derive(Clone, Debug)]
+pub struct Entrance {
+    pub room: usize,
+    /// Air inside the shaft, from the room floor to just above the ground
+    pub interior: VoxelBox,
+}
+
+impl Entrance {
+    /// Where the shaft opens at the surface
+    pub fn opening(&self) -> IVec3 {
+        IVec3::new(self.interior.center().x, self.interior.max.y, self.interior.center().z)
+    }
+
+    /// Steps go round the middle column of the shaft, one voxel higher each step
+    fn is_step(&self, pos: IVec3) -> bool {
+        const RING: [IVec2; 8] = [
+            IVec2::new(-1, -1),
+            IVec2::new(0, -1),
+            IVec2::new(1, -1),
+            IVec2::new(1, 0),
+            IVec2::new(1, 1),
+            IVec2::new(0, 1),
+            IVec2::new(-1, 1),
+            IVec2::new(-1, 0),
+        ];
+        let step = RING[(pos.y - self.interior.min.y).rem_euclid(RING.len() as i32) as usize];
+        pos.xz() - self.interior.center().xz() == step
+    }
+}
+
+/// One dungeon: rooms, the corridors between them and the entrances up to the surface
+#[derive(Clone, Debug)]
+pub struct Dungeon {
+    pub cell: IVec2,
+    /// Every voxel the dungeon sets, walls included
+    pub bounds: VoxelBox,
+    pub rooms: Vec<Room>,
+    pub corridors: Vec<Corridor>,
+    pub entrances: Vec<Entrance>,
+}
+
+impl Dungeon {
+    /// Voxel of the dungeon at a position: air inside, floor and wall voxels around the
+    /// inside, or None where the dungeon leaves the terrain alone
+    pub fn voxel_at(&self, pos: IVec3) -> Option<VoxelType> {
+        if !self.bounds.contains(pos) {
+            return None;
+        }
+        if let Some(entrance) = self.entrances.iter().find(|entrance| entrance.interior.contains(pos)) {
+            return Some(if entrance.is_step(pos) { VoxelType::DungeonFloor } else { VoxelType::Air });
+        }
+        if self.is_inside(pos) {
+            return Some(VoxelType::Air);
+        }
+
+        // Walls are the voxels next to the inside, and the shafts stay open at the top
+        let near = |offset: IVec3| {
+            let neighbor = pos + offset;
+            self.is_inside(neighbor)
+                || self
+                    .entrances
+                    .iter()
+                    .any(|entrance| entrance.interior.contains(neighbor) && pos.y <= entrance.interior.max.y)
+        };
+        if near(IVec3::Y) || near(IVec3::NEG_Y) {
+            return Some(VoxelType::DungeonFloor);
+        }
+        for z in -1..=1 {
+            for y in -1..=1 {
+                for x in -1..=1 {
+                    if near(IVec3::new(x, y, z)) {
+                        return Some(VoxelType::DungeonWall);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether a position is inside a room or corridor
+    pub fn is_inside(&self, pos: IVec3) -> bool {
+        self.rooms.iter().any(|room| room.interior.contains(pos))
+            || self.corridors.iter().any(|corridor| corridor.contains(pos))
+    }
+
+    /// Whether a position is within `margin` voxels of the dungeon's walls
+    pub fn is_near(&self, pos: IVec3, margin: i32) -> bool {
+        let reach = margin + 1;
+        self.bounds.expand(margin).contains(pos)
+            && (self.rooms.iter().any(|room| room.interior.expand(reach).contains(pos))
+                || self.corridors.iter().any(|corridor| corridor.bounds().expand(reach).contains(pos))
+                || self.entrances.iter().any(|entrance| entrance.interior.expand(reach).contains(pos)))
+    }
+
+    /// Only the rooms, corridors and entrances reaching into `area`, so looking up voxels
+    /// there does not go through the whole dungeon. None if nothing does.
+    pub fn clip(&self, area: VoxelBox) -> Option<Dungeon> {
+        if !self.bounds.intersects(&area) {
+            return None;
+        }
+        let near = |space: VoxelBox| space.expand(1).intersects(&area);
+        let rooms: Vec<Room> = self.rooms.iter().filter(|room| near(room.interior)).cloned().collect();
+        let corridors: Vec<Corridor> =
+            self.corridors.iter().filter(|corridor| near(corridor.bounds())).cloned().collect();
+        let entrances: Vec<Entrance> =
+            self.entrances.iter().filter(|entrance| near(entrance.interior)).cloned().collect();
+        let bounds = bounds_of(&rooms, &corridors, &entrances)?;
+        Some(Dungeon { cell: self.cell, bounds, rooms, corridors, entrances })
+    }
+
+    /// Whether anything placed in `area` could cover an entrance, i.e. it overlaps the columns
+    /// of a shaft or its walls anywhere above the room the shaft starts in
+    pub fn covers_entrance(&self, area: &VoxelBox) -> bool {
+        self.entrances.iter().any(|entrance| {
+            let mut shaft = entrance.interior.expand(1);
+            shaft.max.y = i32::MAX;
+            shaft.intersects(area)
+        })
+    }
+
+    /// Room a position is in, if any
+    pub fn room_at(&self, pos: IVec3) -> Option<&Room> {
+        self.rooms.iter().find(|room| room.interior.contains(pos))
+    }
+}
+
+/// Lays out dungeons from the world seed: each cell may hold one, split into rooms by binary
+/// space partitioning and connected by corridors, with entrance shafts where the ground above
+/// is dry. Heights of the terrain come from the caller, so entrances open at the surface.
+pub struct DungeonGenerator {
+    seed: WorldSeed,
+    cache: Mutex<HashMap<IVec2, Option<Arc<Dungeon>>>>,
+}
+
+impl DungeonGenerator {
+    pub fn new(seed: WorldSeed) -> Self {
+        Self { seed, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// Cell a world column is in
+    pub fn cell_of(world_x: i32, world_z: i32) -> IVec2 {
+        IVec2::new(world_x, world_z).div_euclid(IVec2::splat(DUNGEON_CELL))
+    }
+
+    /// Dungeon of a cell. `terrain_height` gives the ground height of a column and
+    /// `water_level` the height water fills up to, entrances only open on dry ground.
+    pub fn dungeon(
+        &self,
+        cell: IVec2,
+        terrain_height: impl Fn(i32, i32) -> i32,
+        water_level: i32,
+    ) -> Option<Arc<Dungeon>> {
+        if let Some(dungeon) = self.cache.lock().unwrap().get(&cell) {
+            return dungeon.clone();
+        }
+        let dungeon = self.lay_out(cell, terrain_height, water_level).map(Arc::new);
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(cell, dungeon.clone());
+        dungeon
+    }
+
+    fn lay_out(&self, cell: IVec2, terrain_height: impl Fn(i32, i32) -> i32, water_level: i32) -> Option<Dungeon> {
+        let mut rolls = Rolls::new(self.seed, cell);
+        if rolls.next() >= DUNGEON_CHANCE {
+            return None;
+        }
+
+        let size = IVec2::new(
+            rolls.range(MIN_DUNGEON_SIZE, MAX_DUNGEON_SIZE),
+            rolls.range(MIN_DUNGEON_SIZE, MAX_DUNGEON_SIZE),
+        );
+        let free = IVec2::splat(DUNGEON_CELL - 2 * CELL_MARGIN) - size;
+        let min = cell * DUNGEON_CELL + CELL_MARGIN + IVec2::new(rolls.range(0, free.x), rolls.range(0, free.y));
+        let area = IRect::from_corners(min, min + size - 1);
+
+        let mut rooms = Vec::new();
+        let mut corridors = Vec::new();
+        split(area, &mut rolls, &mut rooms, &mut corridors);
+        if rolls.next() < LOOP_CHANCE && rooms.len() > 2 {
+            let a = rolls.range(0, rooms.len() as i32 - 1) as usize;
+            let b = (a + rolls.range(1, rooms.len() as i32 - 1) as usize) % rooms.len();
+            corridors.push(Corridor::new((a, b), &rooms[a], &rooms[b]));
+        }
+
+        let entrances = place_entrances(&rooms, &corridors, &mut rolls, terrain_height, water_level);
+
+        let bounds = bounds_of(&rooms, &corridors, &entrances)?;
+        Some(Dungeon { cell, bounds, rooms, corridors, entrances })
+    }
+}
+
+/// Box around the spaces and their walls
+fn bounds_of(rooms: &[Room], corridors: &[Corridor], entrances: &[Entrance]) -> Option<VoxelBox> {
+    let bounds = rooms
+        .iter()
+        .map(|room| room.interior)
+        .chain(corridors.iter().map(Corridor::bounds))
+        .chain(entrances.iter().map(|entrance| entrance.interior))
+        .reduce(|a, b| a.union(&b))?;
+    Some(bounds.expand(1))
+}
+
+/// Split an area in two until it is too small, put a room in every leaf and connect the
+/// closest rooms of the two halves. Returns the indices of the rooms in the area.
+fn split(area: IRect, rolls: &mut Rolls, rooms: &mut Vec<Room>, corridors: &mut Vec<Corridor>) -> Vec<usize> {
+    let size = area.size() + 1;
+    let can_split_x = size.x >= 2 * MIN_LEAF;
+    let can_split_z = size.y >= 2 * MIN_LEAF;
+    if !can_split_x && !can_split_z {
+        rooms.push(room_in(area, rolls));
+        return vec![rooms.len() - 1];
+    }
+
+    // Split the longer side, or either one of a square
+    let along_x = match (can_split_x, can_split_z) {
+        (true, true) if size.x == size.y => rolls.next() < 0.5,
+        (true, true) => size.x > size.y,
+        (can_x, _) => can_x,
+    };
+    let (first, second) = if along_x {
+        let at = area.min.x + rolls.range(MIN_LEAF, size.x - MIN_LEAF);
+        (IRect::new(area.min.x, area.min.y, at - 1, area.max.y), IRect::new(at, area.min.y, area.max.x, area.max.y))
+    } else {
+        let at = area.min.y + rolls.range(MIN_LEAF, size.y - MIN_LEAF);
+        (IRect::new(area.min.x, area.min.y, area.max.x, at - 1), IRect::new(area.min.x, at, area.max.x, area.max.y))
+    };
+
+    let first = split(first, rolls, rooms, corridors);
+    let second = split(second, rolls, rooms, corridors);
+    let (a, b) = first
+        .iter()
+        .flat_map(|&a| second.iter().map(move |&b| (a, b)))
+        .min_by_key(|&(a, b)| {
+            let d = rooms[a].interior.center() - rooms[b].interior.center();
+            d.x.abs() + d.z.abs()
+        })
+        .expect("both halves hold rooms");
+    corridors.push(Corridor::new((a, b), &rooms[a], &rooms[b]));
+    first.into_iter().chain(second).collect()
+}
+
+/// A room of random size, floor height and interior height within a leaf, one voxel away
+/// from its edges so neighboring rooms keep separate walls
+fn room_in(leaf: IRect, rolls: &mut Rolls) -> Room {
+    let space = leaf.size() + 1 - 2;
+    let size = IVec2::new(rolls.range(MIN_ROOM, space.x), rolls.range(MIN_ROOM, space.y));
+    let min = leaf.min + 1 + IVec2::new(rolls.range(0, space.x - size.x), rolls.range(0, space.y - size.y));
+    let floor = rolls.range(MIN_FLOOR_Y, MAX_FLOOR_Y);
+    let height = rolls.range(MIN_ROOM_HEIGHT, MAX_ROOM_HEIGHT);
+    Room {
+        interior: VoxelBox {
+            min: IVec3::new(min.x, floor + 1, min.y),
+            max: IVec3::new(min.x + size.x - 1, floor + height, min.y + size.y - 1),
+        },
+    }
+}
+
+/// Entrance shafts in room corners that no corridor passes through, up to the ground above.
+/// Shafts only go where the ground around them is above the water, so they stay dry.
+fn place_entrances(
+    rooms: &[Room],
+    corridors: &[Corridor],
+    rolls: &mut Rolls,
+    terrain_height: impl Fn(i32, i32) -> i32,
+    water_level: i32,
+) -> Vec<Entrance> {
+    let wanted = rolls.range(1, MAX_ENTRANCES as i32) as usize;
+    let first = rolls.range(0, rooms.len() as i32 - 1) as usize;
+    let mut entrances = Vec::new();
+
+    for index in (0..rooms.len()).map(|i| (first + i) % rooms.len()) {
+        if entrances.len() == wanted {
+            break;
+        }
+        let room = &rooms[index].interior;
+        if room.max.x - room.min.x + 1 < SHAFT_SIZE || room.max.z - room.min.z + 1 < SHAFT_SIZE {
+            continue;
+        }
+        let corners = [
+            IVec2::new(room.min.x, room.min.z),
+            IVec2::new(room.max.x - SHAFT_SIZE + 1, room.min.z),
+            IVec2::new(room.min.x, room.max.z - SHAFT_SIZE + 1),
+            IVec2::new(room.max.x - SHAFT_SIZE + 1, room.max.z - SHAFT_SIZE + 1),
+        ];
+        let start = rolls.range(0, 3) as usize;
+        for corner in (0..4).map(|i| corners[(start + i) % 4]) {
+            let shaft = IRect::from_corners(corner, corner + SHAFT_SIZE - 1);
+            // Both rects include their max, which `IRect::intersect` would count as empty
+            let blocked = corridors.iter().any(|corridor| {
+                corridor
+                    .legs()
+                    .iter()
+                    .any(|(leg, _, _)| leg.min.cmple(shaft.max).all() && shaft.min.cmple(leg.max).all())
+            });
+            if blocked {
+                continue;
+            }
+
+            // The shaft's walls and the ring of columns around them must be on dry ground
+            let around = shaft.inflate(2);
+            let heights: Vec<i32> = (around.min.y..=around.max.y)
+                .flat_map(|z| (around.min.x..=around.max.x).map(move |x| (x, z)))
+                .map(|(x, z)| terrain_height(x, z))
+                .collect();
+            if heights.iter().any(|&height| height <= water_level) {
+                continue;
+            }
+            let top = heights.iter().copied().max().unwrap_or(water_level) + 1;
+            if top <= room.max.y {
+                continue;
+            }
+
+            entrances.push(Entrance {
+                room: index,
+                interior: VoxelBox {
+                    min: IVec3::new(shaft.min.x, room.min.y, shaft.min.y),
+                    max: IVec3::new(shaft.max.x, top, shaft.max.y),
+                },
+            });
+            break;
+        }
+    }
+    entrances
+}
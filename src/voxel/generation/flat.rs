@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use crate::constants::{CHUNK_SIZE, CHUNK_SIZE_I32};
+use crate::voxel::chunk::Chunk;
+use crate::voxel::generation::{GeneratorContext, GeneratorError, TerrainGenerator};
+use crate::voxel::types::VoxelType;
+
+/// Fill every horizontal layer of the chunk with the voxel returned for its world Y
+fn fill_layers(pos: IVec3, chunk: &mut Chunk, voxel_at: impl Fn(i32) -> VoxelType) {
+    for y in 0..CHUNK_SIZE {
+        let voxel = voxel_at(pos.y * CHUNK_SIZE_I32 + y as i32);
+        if voxel == VoxelType::Air {
+            continue;
+        }
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.set(UVec3::new(x as u32, y as u32, z as u32), voxel);
+            }
+        }
+    }
+}
+
+/// Solid ground up to a fixed height, for debugging meshing and physics
+pub struct FlatGenerator {
+    pub height: i32,
+    pub voxel: VoxelType,
+}
+
+impl FlatGenerator {
+    pub fn from_settings(ctx: &GeneratorContext) -> Result<Self, GeneratorError> {
+        Ok(Self { height: ctx.settings.flat_height, voxel: VoxelType::TopSoil })
+    }
+}
+
+impl TerrainGenerator for FlatGenerator {
+    fn generate_chunk(&self, pos: IVec3, chunk: &mut Chunk) {
+        fill_layers(pos, chunk, |y| if y <= self.height { self.voxel } else { VoxelType::Air });
+    }
+}
+
+/// Nothing but air
+pub struct VoidGenerator;
+
+impl TerrainGenerator for VoidGenerator {
+    fn generate_chunk(&self, _pos: IVec3, _chunk: &mut Chunk) {}
+}
+
+/// Stack of flat layers from Y = 0 upwards, e.g. bedrock, rock, soil
+pub struct SuperflatGenerator {
+    /// Voxel of each Y level, bottom first
+    levels: Vec<VoxelType>,
+}
+
+impl SuperflatGenerator {
+    /// Layers are (voxel, thickness) pairs from the bottom up
+    pub fn new(layers: &[(VoxelType, u32)]) -> Self {
+        let levels = layers
+            .iter()
+            .flat_map(|&(voxel, thickness)| std::iter::repeat_n(voxel, thickness as usize))
+            .collect();
+        Self { levels }
+    }
+
+    pub fn from_settings(ctx: &GeneratorContext) -> Result<Self, GeneratorError> {
+        let layers = &ctx.settings.superflat_layers;
+        if layers.is_empty() {
+            return Err(GeneratorError::InvalidOptions("superflat needs at least one layer".to_string()));
+        }
+
+        let layers: Vec<(VoxelType, u32)> = layers
+            .iter()
+            .map(|layer| {
+                ctx.registry
+                    .id(&layer.voxel)
+                    .map(|voxel| (voxel, layer.thickness))
+                    .ok_or_else(|| GeneratorError::UnknownVoxelType(layer.voxel.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(&layers))
+    }
+}
+
+impl TerrainGenerator for SuperflatGenerator {
+    fn generate_chunk(&self, pos: IVec3, chunk: &mut Chunk) {
+        fill_layers(pos, chunk, |y| {
+            usize::try_from(y)
+                .ok()
+                .and_then(|y| self.levels.get(y).copied())
+                .unwrap_or(VoxelType::Air)
+        });
+    }
+}
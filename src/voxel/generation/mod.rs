@@ -0,0 +1,201 @@
+pub mod biome;
+pub mod caves;
+pub mod deposits;
+pub mod dungeon;
+pub mod flat;
+pub mod noise;
+pub mod perlin;
+pub mod structures;
+pub mod tasks;
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use crate::config::biomes::BiomesConfigFile;
+use crate::config::ores::OresConfigFile;
+use crate::config::structures::StructuresConfigFile;
+use crate::config::world::GeneratorSection;
+use crate::voxel::chunk::Chunk;
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::world::WorldSeed;
+
+pub use biome::{Biome, BiomeMap};
+pub use deposits::{DepositGenerator, WithDeposits};
+pub use dungeon::{Dungeon, DungeonGenerator, Entrance, Room, VoxelBox, DUNGEON_CELL};
+pub use flat::{FlatGenerator, SuperflatGenerator, VoidGenerator};
+pub use noise::NoiseGenerator;
+pub use structures::{PlacedStructure, StructureGenerator};
+
+/// Fills chunks with terrain. Must give the same voxels for the same position every time,
+/// since unedited chunks are regenerated instead of saved.
+pub trait TerrainGenerator: Send + Sync + 'static {
+    /// Fill `chunk`, which starts out as all air, with the terrain at chunk coords `pos`
+    fn generate_chunk(&self, pos: IVec3, chunk: &mut Chunk);
+
+    /// Dungeon of the cell a column is in, for generators that place dungeons
+    fn dungeon_at(&self, _world_x: i32, _world_z: i32) -> Option<Arc<Dungeon>> {
+        None
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GeneratorError {
+    #[error("Unknown terrain generator '{0}'")]
+    UnknownGenerator(String),
+    #[error("Generator uses voxel type '{0}' which is not in the registry")]
+    UnknownVoxelType(String),
+    #[error("Deposit or structure is restricted to biome '{0}' which is not defined")]
+    UnknownBiome(String),
+    #[error("Structure placement uses template '{0}' which is not defined")]
+    UnknownTemplate(String),
+    #[error("Invalid generator options: {0}")]
+    InvalidOptions(String),
+}
+
+/// Random rolls for laying out something at a cell or column, taken in a fixed order so the
+/// same seed gives the same layout. A splitmix64 stream, since `WorldSeed::hash` is made for
+/// lattice noise and its values for consecutive inputs are too alike.
+pub(crate) struct Rolls(u64);
+
+impl Rolls {
+    pub(crate) fn new(seed: WorldSeed, at: IVec2) -> Self {
+        let at = (at.x as u32 as u64) << 32 | at.y as u32 as u64;
+        let mut rolls = Self(seed.0 ^ at.wrapping_mul(0xd6e8_feb8_6659_fd93));
+        rolls.next_u64();
+        rolls
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Value in 0..1
+    pub(crate) fn next(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Whole number within min..=max
+    pub(crate) fn range(&mut self, min: i32, max: i32) -> i32 {
+        (min + (self.next() * (max - min + 1) as f32) as i32).min(max)
+    }
+}
+
+/// Everything a generator factory may need to build a generator for the current world
+pub struct GeneratorContext<'a> {
+    pub seed: WorldSeed,
+    pub settings: &'a GeneratorSection,
+    pub registry: &'a VoxelRegistry,
+    pub biomes: &'a BiomesConfigFile,
+    pub ores: &'a OresConfigFile,
+    pub structures: &'a StructuresConfigFile,
+}
+
+pub type GeneratorFactory =
+    Box<dyn Fn(&GeneratorContext) -> Result<Box<dyn TerrainGenerator>, GeneratorError> + Send + Sync>;
+
+/// Generators selectable by name from the `generator.type` setting in world.yaml
+#[derive(Resource)]
+pub struct TerrainGenerators {
+    factories: HashMap<String, GeneratorFactory>,
+}
+
+impl Default for TerrainGenerators {
+    fn default() -> Self {
+        let mut generators = Self { factories: HashMap::new() };
+        generators.register("noise", |ctx| {
+            let biomes = BiomeMap::new(ctx.seed, ctx.biomes, ctx.registry)?;
+            let structures = StructureGenerator::new(ctx.seed, ctx.structures, biomes.clone(), ctx.registry)?;
+            Ok(Box::new(NoiseGenerator::with_config(ctx.seed, biomes, structures)))
+        });
+        generators.register("flat", |ctx| FlatGenerator::from_settings(ctx).map(|g| Box::new(g) as _));
+        generators.register("void", |_| Ok(Box::new(VoidGenerator)));
+        generators.register("superflat", |ctx| SuperflatGenerator::from_settings(ctx).map(|g| Box::new(g) as _));
+        generators
+    }
+}
+
+impl TerrainGenerators {
+    /// Add a generator, replacing any existing one with the same name
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&GeneratorContext) -> Result<Box<dyn TerrainGenerator>, GeneratorError> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    /// Build the generator named in the settings, with ore deposits placed after its terrain
+    pub fn create(&self, ctx: &GeneratorContext) -> Result<WorldGenerator, GeneratorError> {
+        let factory = self
+            .factories
+            .get(&ctx.settings.kind)
+            .ok_or_else(|| GeneratorError::UnknownGenerator(ctx.settings.kind.clone()))?;
+        let terrain = factory(ctx)?;
+        if ctx.ores.deposits.is_empty() {
+            return Ok(WorldGenerator(Arc::from(terrain)));
+        }
+        let deposits = DepositGenerator::from_context(ctx)?;
+        Ok(WorldGenerator::new(WithDeposits { terrain, deposits }))
+    }
+}
+
+/// Register custom generators on the app, before or after adding `VoxelPlugin`
+pub trait TerrainGeneratorAppExt {
+    fn register_terrain_generator(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&GeneratorContext) -> Result<Box<dyn TerrainGenerator>, GeneratorError> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl TerrainGeneratorAppExt for App {
+    fn register_terrain_generator(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&GeneratorContext) -> Result<Box<dyn TerrainGenerator>, GeneratorError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.init_resource::<TerrainGenerators>();
+        self.world_mut().resource_mut::<TerrainGenerators>().register(name, factory);
+        self
+    }
+}
+
+/// Generator used for the current world
+#[derive(Resource, Clone)]
+pub struct WorldGenerator(pub Arc<dyn TerrainGenerator>);
+
+impl WorldGenerator {
+    pub fn new(generator: impl TerrainGenerator) -> Self {
+        Self(Arc::new(generator))
+    }
+
+    /// A freshly generated chunk at chunk coords `pos`
+    pub fn generate(&self, pos: IVec3) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        self.0.generate_chunk(pos, &mut chunk);
+        chunk
+    }
+
+    /// Dungeon of the cell a column is in, if the generator placed one there
+    pub fn dungeon_at(&self, world_x: i32, world_z: i32) -> Option<Arc<Dungeon>> {
+        self.0.dungeon_at(world_x, world_z)
+    }
+
+    /// Dungeons of every cell overlapping the columns from `min` to `max`, inclusive
+    pub fn dungeons_in(&self, min: IVec2, max: IVec2) -> Vec<Arc<Dungeon>> {
+        let (first, last) = (DungeonGenerator::cell_of(min.x, min.y), DungeonGenerator::cell_of(max.x, max.y));
+        (first.y..=last.y)
+            .flat_map(|z| (first.x..=last.x).map(move |x| IVec2::new(x, z) * DUNGEON_CELL))
+            .filter_map(|column| self.dungeon_at(column.x, column.y))
+            .collect()
+    }
+}
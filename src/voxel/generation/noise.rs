@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use crate::constants::{CHUNK_SIZE, CHUNK_SIZE_I32};
+use crate::voxel::chunk::Chunk;
+use crate::voxel::generation::TerrainGenerator;
+use crate::voxel::generation::biome::{BiomeBlend, BiomeMap};
+use crate::voxel::generation::caves::CaveCarver;
+use crate::voxel::generation::dungeon::{Dungeon, DungeonGenerator, VoxelBox, DUNGEON_CELL};
+use crate::voxel::generation::structures::{PlacedStructure, StructureGenerator};
+use crate::voxel::types::VoxelType;
+use crate::voxel::world::WorldSeed;
+
+/// Top of the bedrock layer
+const BEDROCK_TOP: i32 = 3;
+
+/// Solid voxels kept between caves and the surface, and between caves and water
+const CAVE_ROOF: i32 = 3;
+
+/// Solid voxels kept between caves and dungeons
+const DUNGEON_MARGIN: i32 = 2;
+
+/// The default terrain: fbm hills, mountains, rivers, biomes, trees, caves and dungeons
+pub struct NoiseGenerator {
+    seed: WorldSeed,
+    caves: CaveCarver,
+    dungeons: DungeonGenerator,
+    biomes: BiomeMap,
+    structures: StructureGenerator,
+}
+
+impl NoiseGenerator {
+    /// Generator with the built-in biomes and structures
+    pub fn new(seed: WorldSeed) -> Self {
+        Self::with_config(seed, BiomeMap::builtin(seed), StructureGenerator::builtin(seed))
+    }
+
+    pub fn with_config(seed: WorldSeed, biomes: BiomeMap, structures: StructureGenerator) -> Self {
+        Self {
+            seed,
+            caves: CaveCarver::new(seed),
+            dungeons: DungeonGenerator::new(seed),
+            biomes,
+            structures,
+        }
+    }
+
+    pub fn biomes(&self) -> &BiomeMap {
+        &self.biomes
+    }
+
+    pub fn structures(&self) -> &StructureGenerator {
+        &self.structures
+    }
+
+    /// Structures standing in a chunk column
+    pub fn structures_in(&self, column: IVec2) -> Arc<[PlacedStructure]> {
+        self.structures.structures_in(column, |x, z| self.structure_ground(x, z), |bounds| self.covers_entrance(bounds))
+    }
+
+    /// Height of the ground structures may stand on in a column: above the water and beaches
+    fn structure_ground(&self, world_x: i32, world_z: i32) -> Option<i32> {
+        let height = self.terrain_height(world_x, world_z);
+        (height > WATER_LEVEL + 2).then_some(height)
+    }
+
+    /// Whether something placed in `area` could cover the entrance of a dungeon
+    fn covers_entrance(&self, area: &VoxelBox) -> bool {
+        let (first, last) = (
+            DungeonGenerator::cell_of(area.min.x, area.min.z),
+            DungeonGenerator::cell_of(area.max.x, area.max.z),
+        );
+        (first.y..=last.y)
+            .flat_map(|z| (first.x..=last.x).map(move |x| IVec2::new(x, z) * DUNGEON_CELL))
+            .filter_map(|column| self.dungeon_at(column.x, column.y))
+            .any(|dungeon| dungeon.covers_entrance(area))
+    }
+
+    /// Whether generation leaves a cave at a position. Caves are dry: they never hold water
+    /// and never open into the sea, lakes or rivers, so no water sits next to cave air.
+    pub fn is_cave(&self, pos: IVec3) -> bool {
+        may_carve(pos, self.cave_ceiling(pos.x, pos.z), self.dungeon_at(pos.x, pos.z).as_deref())
+            && self.caves.is_carved(pos)
+    }
+
+    /// The dungeon of the cell a column is in, if that cell has one
+    pub fn dungeon_at(&self, world_x: i32, world_z: i32) -> Option<Arc<Dungeon>> {
+        let cell = DungeonGenerator::cell_of(world_x, world_z);
+        self.dungeons.dungeon(cell, |x, z| self.terrain_height(x, z), WATER_LEVEL)
+    }
+
+    /// Highest Y a cave may reach in a column: a roof below the surface, and below the ground
+    /// under any water next to the column so caves never open sideways into it
+    fn cave_ceiling(&self, world_x: i32, world_z: i32) -> i32 {
+        let lowest = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .map(|(dx, dz)| self.terrain_height(world_x + dx, world_z + dz))
+            .filter(|&height| height < WATER_LEVEL)
+            .fold(self.terrain_height(world_x, world_z), i32::min);
+        lowest - CAVE_ROOF
+    }
+
+    fn generate_noise_chunk(&self, chunk_pos: IVec3, chunk: &mut Chunk) {
+        let seed = self.seed;
+        let chunk_world_x = chunk_pos.x * CHUNK_SIZE_I32;
+        let chunk_world_z = chunk_pos.z * CHUNK_SIZE_I32;
+        let chunk_world_y = chunk_pos.y * CHUNK_SIZE_I32;
+        let carved = self.caves.carve_chunk(chunk_pos);
+        let min = chunk_pos * CHUNK_SIZE_I32;
+        let area = VoxelBox { min, max: min + CHUNK_SIZE_I32 - 1 };
+        let dungeon = self.dungeon_at(min.x, min.z).and_then(|dungeon| dungeon.clip(area.expand(DUNGEON_MARGIN + 1)));
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let world_x = chunk_world_x + x as i32;
+                let world_z = chunk_world_z + z as i32;
+
+                let blend = self.biomes.blend(world_x, world_z);
+                let terrain_height = self.column_height(world_x, world_z, &blend);
+                let biome = self.biomes.surface_biome(&blend, world_x, world_z);
+                let cave_ceiling = self.cave_ceiling(world_x, world_z);
+
+                for y in 0..CHUNK_SIZE {
+                    let world_y = chunk_world_y + y as i32;
+
+                    // Check for dungeon structures first
+                    let pos = IVec3::new(world_x, world_y, world_z);
+                    if let Some(dungeon_voxel) = dungeon.as_ref().and_then(|dungeon| dungeon.voxel_at(pos)) {
+                        chunk.set(UVec3::new(x as u32, y as u32, z as u32), dungeon_voxel);
+                        continue;
+                    }
+
+                    // Caves stay air even below the water level; see `cave_ceiling`
+                    let local = UVec3::new(x as u32, y as u32, z as u32);
+                    if carved.is_carved(local) && may_carve(pos, cave_ceiling, dungeon.as_ref()) {
+                        continue;
+                    }
+
+                    let voxel = if world_y > terrain_height {
+                        // Above terrain - check if below water level (lakes/rivers)
+                        if world_y <= WATER_LEVEL {
+                            VoxelType::Water
+                        } else {
+                            VoxelType::Air
+                        }
+                    } else if world_y == 0 {
+                        VoxelType::Bedrock
+                    } else if world_y <= BEDROCK_TOP {
+                        // Deep bedrock layer with some rock
+                        if seed.hash(world_x, world_z + world_y * 1000) > 0.3 {
+                            VoxelType::Bedrock
+                        } else {
+                            VoxelType::Rock
+                        }
+                    } else {
+                        // Layers of the column's biome; near the water level (beaches and
+                        // shorelines) biomes may use their shore layers instead
+                        let depth = terrain_height - world_y;
+                        let near_water = terrain_height <= WATER_LEVEL + 2;
+                        biome.voxel_at_depth(depth, near_water)
+                    };
+
+                    chunk.set(UVec3::new(x as u32, y as u32, z as u32), voxel);
+                }
+            }
+        }
+
+        // Trees, boulders and ruins, including the parts of neighboring chunks' structures
+        self.structures.place_structures(
+            chunk_pos,
+            chunk,
+            |x, z| self.structure_ground(x, z),
+            |bounds| self.covers_entrance(bounds),
+        );
+    }
+
+    /// Height of the ground surface in a column
+    pub fn terrain_height(&self, world_x: i32, world_z: i32) -> i32 {
+        self.column_height(world_x, world_z, &self.biomes.blend(world_x, world_z))
+    }
+
+    /// Ground height with the column's biome blend already looked up. Biome offsets and hill
+    /// roughness are blended, so the ground does not step at biome borders.
+    fn column_height(&self, world_x: i32, world_z: i32, blend: &BiomeBlend) -> i32 {
+        let seed = self.seed;
+        let biomes = self.biomes.biomes();
+        let x = world_x as f32;
+        let z = world_z as f32;
+
+        // Base terrain with multiple noise layers
+        // Base ranges from 16-36, keeping most land above water level (18)
+        let base = fbm(seed, x * 0.008, z * 0.008, 4) * 20.0 + 16.0;
+
+        // Hills - larger features, taller in rough biomes
+        let hills = fbm(seed, x * 0.02, z * 0.02, 3) * 10.0 * blend.mix(biomes, |biome| biome.roughness);
+        let offset = blend.mix(biomes, |biome| biome.height_offset);
+
+        // Mountains - occasional tall peaks
+        let mountain_mask = fbm(seed, x * 0.005, z * 0.005, 2);
+        let mountains = if mountain_mask > 0.65 {
+            (mountain_mask - 0.65) * 50.0
+        } else {
+            0.0
+        };
+
+        // River valleys - carve into terrain (wider rivers)
+        let river_noise = (fbm(seed, x * 0.015, z * 0.015, 2) * TAU).sin();
+        let river_factor = if river_noise.abs() < 0.2 {
+            -10.0 * (1.0 - river_noise.abs() / 0.2)
+        } else {
+            0.0
+        };
+
+        (base + hills + offset + mountains + river_factor).clamp(1.0, 58.0) as i32
+    }
+}
+
+impl TerrainGenerator for NoiseGenerator {
+    fn generate_chunk(&self, chunk_pos: IVec3, chunk: &mut Chunk) {
+        self.generate_noise_chunk(chunk_pos, chunk);
+    }
+
+    fn dungeon_at(&self, world_x: i32, world_z: i32) -> Option<Arc<Dungeon>> {
+        NoiseGenerator::dungeon_at(self, world_x, world_z)
+    }
+}
+
+/// Whether a carved position is allowed to become a cave
+fn may_carve(pos: IVec3, ceiling: i32, dungeon: Option<&Dungeon>) -> bool {
+    pos.y > BEDROCK_TOP && pos.y < ceiling && !dungeon.is_some_and(|dungeon| dungeon.is_near(pos, DUNGEON_MARGIN))
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn value_noise(seed: WorldSeed, x: f32, z: f32) -> f32 {
+    let xi = x.floor() as i32;
+    let zi = z.floor() as i32;
+    let xf = x - x.floor();
+    let zf = z - z.floor();
+
+    let v00 = seed.hash(xi, zi);
+    let v10 = seed.hash(xi + 1, zi);
+    let v01 = seed.hash(xi, zi + 1);
+    let v11 = seed.hash(xi + 1, zi + 1);
+
+    let u = smoothstep(xf);
+    let v = smoothstep(zf);
+
+    lerp(lerp(v00, v10, u), lerp(v01, v11, u), v)
+}
+
+fn fbm(seed: WorldSeed, x: f32, z: f32, octaves: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for _ in 0..octaves {
+        value += amplitude * value_noise(seed, x * frequency, z * frequency);
+        max_value += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    value / max_value
+}
+
+// Water level constant - areas below this height will be filled with water
+pub const WATER_LEVEL: i32 = 18;
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use crate::voxel::world::WorldSeed;
+
+/// Gradients of the lattice points, the 12 edge directions of a cube
+const GRADIENTS: [Vec3; 12] = [
+    Vec3::new(1.0, 1.0, 0.0),
+    Vec3::new(-1.0, 1.0, 0.0),
+    Vec3::new(1.0, -1.0, 0.0),
+    Vec3::new(-1.0, -1.0, 0.0),
+    Vec3::new(1.0, 0.0, 1.0),
+    Vec3::new(-1.0, 0.0, 1.0),
+    Vec3::new(1.0, 0.0, -1.0),
+    Vec3::new(-1.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 1.0),
+    Vec3::new(0.0, -1.0, 1.0),
+    Vec3::new(0.0, 1.0, -1.0),
+    Vec3::new(0.0, -1.0, -1.0),
+];
+
+/// Quintic fade, so the noise has no visible creases at lattice cells
+fn fade(t: Vec3) -> Vec3 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn gradient(seed: WorldSeed, cell: IVec3) -> Vec3 {
+    let index = (seed.hash3(cell.x, cell.y, cell.z) * GRADIENTS.len() as f32) as usize;
+    GRADIENTS[index.min(GRADIENTS.len() - 1)]
+}
+
+/// 3D Perlin gradient noise in roughly -1..=1, 0 at every lattice point
+pub fn perlin3(seed: WorldSeed, p: Vec3) -> f32 {
+    let cell = p.floor();
+    let base = cell.as_ivec3();
+    let local = p - cell;
+    let t = fade(local);
+
+    let corner = |offset: IVec3| gradient(seed, base + offset).dot(local - offset.as_vec3());
+    let x00 = corner(IVec3::new(0, 0, 0)).lerp(corner(IVec3::new(1, 0, 0)), t.x);
+    let x10 = corner(IVec3::new(0, 1, 0)).lerp(corner(IVec3::new(1, 1, 0)), t.x);
+    let x01 = corner(IVec3::new(0, 0, 1)).lerp(corner(IVec3::new(1, 0, 1)), t.x);
+    let x11 = corner(IVec3::new(0, 1, 1)).lerp(corner(IVec3::new(1, 1, 1)), t.x);
+    x00.lerp(x10, t.y).lerp(x01.lerp(x11, t.y), t.z)
+}
+
+/// Octaves of Perlin noise, each at double the frequency and half the amplitude of the last.
+/// Normalized to roughly -1..=1.
+pub fn fbm3(seed: WorldSeed, p: Vec3, octaves: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for octave in 0..octaves {
+        // Offset each octave so their lattice points do not line up
+        let offset = Vec3::splat(octave as f32 * 17.31);
+        value += amplitude * perlin3(seed, p * frequency + offset);
+        max_value += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    value / max_value
+}
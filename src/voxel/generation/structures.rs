@@ -0,0 +1,280 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::config::structures::{ShapeForm, StructuresConfigFile, MAX_STRUCTURE_REACH};
+use crate::constants::CHUNK_SIZE_I32;
+use crate::voxel::chunk::Chunk;
+use crate::voxel::generation::{BiomeMap, GeneratorError, Rolls, VoxelBox};
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::types::VoxelType;
+use crate::voxel::world::WorldSeed;
+
+/// Chunk columns whose structures are kept around; every chunk also needs its neighbors'
+const CACHE_SIZE: usize = 256;
+
+/// A shape of a template with its voxel name resolved
+struct Shape {
+    voxel: VoxelType,
+    form: ShapeForm,
+    replace: bool,
+    chance: f32,
+}
+
+impl Shape {
+    fn contains(&self, local: IVec3) -> bool {
+        match self.form {
+            ShapeForm::Box { min, max } => local.cmpge(IVec3::from(min)).all() && local.cmple(IVec3::from(max)).all(),
+            ShapeForm::Ellipsoid { center, radius } => {
+                let offset = (local.as_vec3() - Vec3::from(center)) / Vec3::from(radius).abs().max(Vec3::splat(0.01));
+                offset.length_squared() <= 1.0
+            }
+        }
+    }
+}
+
+/// A template from structures.yaml with its voxel names resolved
+struct Template {
+    id: String,
+    shapes: Vec<Shape>,
+    /// Offsets from the anchor the shapes cover, before turning
+    extent: VoxelBox,
+}
+
+/// A placement from structures.yaml with its names resolved
+struct Placement {
+    /// Separate seed per placement, so placements of the same template land elsewhere
+    seed: WorldSeed,
+    template: usize,
+    chance: f32,
+    /// Indices into `BiomeMap::biomes`, every biome if empty
+    biomes: Vec<usize>,
+}
+
+/// A structure standing in the world
+#[derive(Clone, Debug)]
+pub struct PlacedStructure {
+    /// Index of the template, see `StructureGenerator::template_id`
+    pub template: usize,
+    /// Air voxel on top of the ground the structure stands on
+    pub anchor: IVec3,
+    /// Quarter turns around the anchor
+    pub turns: u8,
+    /// Every voxel the structure may set
+    pub bounds: VoxelBox,
+}
+
+/// Offset turned a number of quarter turns around the y axis
+fn turn(offset: IVec3, turns: u8) -> IVec3 {
+    match turns % 4 {
+        0 => offset,
+        1 => IVec3::new(-offset.z, offset.y, offset.x),
+        2 => IVec3::new(-offset.x, offset.y, -offset.z),
+        _ => IVec3::new(offset.z, offset.y, -offset.x),
+    }
+}
+
+/// Places template structures like trees, boulders and ruins on the terrain. Structures are
+/// picked per chunk column and can reach into the columns around it, so every chunk also
+/// places the parts of its neighbors' structures that reach into it.
+pub struct StructureGenerator {
+    templates: Vec<Template>,
+    placements: Vec<Placement>,
+    biomes: BiomeMap,
+    seed: WorldSeed,
+    cache: Mutex<HashMap<IVec2, Arc<[PlacedStructure]>>>,
+}
+
+impl StructureGenerator {
+    pub fn new(
+        seed: WorldSeed,
+        config: &StructuresConfigFile,
+        biomes: BiomeMap,
+        registry: &VoxelRegistry,
+    ) -> Result<Self, GeneratorError> {
+        let voxel = |name: &str| registry.id(name).ok_or_else(|| GeneratorError::UnknownVoxelType(name.to_string()));
+        let templates = config
+            .templates
+            .iter()
+            .map(|def| {
+                let shapes = def
+                    .shapes
+                    .iter()
+                    .map(|shape| {
+                        Ok(Shape {
+                            voxel: voxel(&shape.voxel)?,
+                            form: shape.form,
+                            replace: shape.replace,
+                            chance: shape.chance,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, GeneratorError>>()?;
+                let extent = def
+                    .shapes
+                    .iter()
+                    .map(|shape| {
+                        let (min, max) = shape.form.extent();
+                        VoxelBox { min, max }
+                    })
+                    .reduce(|a, b| a.union(&b))
+                    .unwrap_or(VoxelBox { min: IVec3::ZERO, max: IVec3::ZERO });
+                let reach = extent.min.xz().abs().max(extent.max.xz().abs()).max_element();
+                if reach > MAX_STRUCTURE_REACH {
+                    return Err(GeneratorError::InvalidOptions(format!(
+                        "structure '{}' reaches {reach} voxels from its anchor, at most {MAX_STRUCTURE_REACH}",
+                        def.id
+                    )));
+                }
+                Ok(Template { id: def.id.clone(), shapes, extent })
+            })
+            .collect::<Result<Vec<_>, GeneratorError>>()?;
+
+        let placements = config
+            .placements
+            .iter()
+            .enumerate()
+            .map(|(index, def)| {
+                let template = templates
+                    .iter()
+                    .position(|template| template.id == def.template)
+                    .ok_or_else(|| GeneratorError::UnknownTemplate(def.template.clone()))?;
+                let biome = |id: &String| {
+                    biomes
+                        .biomes()
+                        .iter()
+                        .position(|biome| &biome.id == id)
+                        .ok_or_else(|| GeneratorError::UnknownBiome(id.clone()))
+                };
+                Ok(Placement {
+                    seed: WorldSeed(seed.0.wrapping_add((index as u64 + 1).wrapping_mul(0xc2b2_ae3d_27d4_eb4f))),
+                    template,
+                    chance: def.chance.clamp(0.0, 1.0),
+                    biomes: def.biomes.iter().map(biome).collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, GeneratorError>>()?;
+
+        Ok(Self { templates, placements, biomes, seed, cache: Mutex::new(HashMap::new()) })
+    }
+
+    /// The built-in structures and biomes with the built-in voxel types
+    pub fn builtin(seed: WorldSeed) -> Self {
+        Self::new(seed, &StructuresConfigFile::default(), BiomeMap::builtin(seed), &VoxelRegistry::builtin())
+            .expect("built-in structures only use built-in voxel types, templates and biomes")
+    }
+
+    pub fn template_id(&self, structure: &PlacedStructure) -> &str {
+        &self.templates[structure.template].id
+    }
+
+    /// Structures standing in a chunk column. `ground` gives the height of the ground a
+    /// structure may stand on in a column, or None where structures do not go, and structures
+    /// whose bounds are `blocked` are left out.
+    pub fn structures_in(
+        &self,
+        column: IVec2,
+        ground: impl Fn(i32, i32) -> Option<i32>,
+        blocked: impl Fn(&VoxelBox) -> bool,
+    ) -> Arc<[PlacedStructure]> {
+        if let Some(structures) = self.cache.lock().unwrap().get(&column) {
+            return structures.clone();
+        }
+        let structures: Arc<[PlacedStructure]> = self.lay_out(column, ground, blocked).into();
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(column, structures.clone());
+        structures
+    }
+
+    fn lay_out(
+        &self,
+        column: IVec2,
+        ground: impl Fn(i32, i32) -> Option<i32>,
+        blocked: impl Fn(&VoxelBox) -> bool,
+    ) -> Vec<PlacedStructure> {
+        let origin = column * CHUNK_SIZE_I32;
+        let columns = (CHUNK_SIZE_I32 * CHUNK_SIZE_I32) as f32;
+        let mut structures = Vec::new();
+        for placement in &self.placements {
+            let mut rolls = Rolls::new(placement.seed, column);
+            let expected = placement.chance * columns;
+            let count = expected as i32 + i32::from(rolls.next() < expected.fract());
+            for _ in 0..count {
+                let x = origin.x + rolls.range(0, CHUNK_SIZE_I32 - 1);
+                let z = origin.y + rolls.range(0, CHUNK_SIZE_I32 - 1);
+                let turns = rolls.range(0, 3) as u8;
+                if !placement.biomes.is_empty() && !placement.biomes.contains(&self.biomes.blend(x, z).dominant()) {
+                    continue;
+                }
+                let Some(height) = ground(x, z) else {
+                    continue;
+                };
+
+                let anchor = IVec3::new(x, height + 1, z);
+                let extent = self.templates[placement.template].extent;
+                let (a, b) = (turn(extent.min, turns), turn(extent.max, turns));
+                let bounds = VoxelBox { min: anchor + a.min(b), max: anchor + a.max(b) };
+                if !blocked(&bounds) {
+                    structures.push(PlacedStructure { template: placement.template, anchor, turns, bounds });
+                }
+            }
+        }
+        structures
+    }
+
+    /// Set the voxels of the structures reaching into the chunk at chunk coords `pos`. Takes
+    /// the same `ground` and `blocked` as `structures_in`.
+    pub fn place_structures(
+        &self,
+        pos: IVec3,
+        chunk: &mut Chunk,
+        ground: impl Fn(i32, i32) -> Option<i32>,
+        blocked: impl Fn(&VoxelBox) -> bool,
+    ) {
+        let origin = pos * CHUNK_SIZE_I32;
+        let area = VoxelBox { min: origin, max: origin + CHUNK_SIZE_I32 - 1 };
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let column = pos.xz() + IVec2::new(dx, dz);
+                for structure in self.structures_in(column, &ground, &blocked).iter() {
+                    if structure.bounds.intersects(&area) {
+                        self.stamp(structure, &area, chunk);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Set the voxels of a structure within `area`, the box of the chunk
+    fn stamp(&self, structure: &PlacedStructure, area: &VoxelBox, chunk: &mut Chunk) {
+        let back = (4 - structure.turns % 4) % 4;
+        for (index, shape) in self.templates[structure.template].shapes.iter().enumerate() {
+            let (min, max) = shape.form.extent();
+            let (a, b) = (turn(min, structure.turns), turn(max, structure.turns));
+            let min = (structure.anchor + a.min(b)).max(area.min);
+            let max = (structure.anchor + a.max(b)).min(area.max);
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        let pos = IVec3::new(x, y, z);
+                        if !shape.contains(turn(pos - structure.anchor, back)) {
+                            continue;
+                        }
+                        // Crumbling is decided per world position, so it does not depend on
+                        // which chunk is generated first
+                        if shape.chance < 1.0
+                            && self.seed.hash3(x, y.wrapping_mul(64).wrapping_add(index as i32), z) >= shape.chance
+                        {
+                            continue;
+                        }
+                        let local = (pos - area.min).as_uvec3();
+                        if shape.replace || chunk.get(local) == VoxelType::Air {
+                            chunk.set(local, shape.voxel);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
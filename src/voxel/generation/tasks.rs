@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use bevy::tasks::{futures::check_ready, AsyncComputeTaskPool, Task};
+use std::collections::{HashSet, VecDeque};
+use std::time::Instant;
+use crate::camera::controller::PlayerCamera;
+use crate::constants::CHUNK_SIZE_I32;
+use crate::voxel::chunk::Chunk;
+use crate::voxel::generation::{WorldGenerator, DUNGEON_CELL};
+use crate::voxel::persistence::{WorldPersistence, WorldStorage};
+use crate::voxel::persistence::autosave::SaveState;
+use crate::voxel::plugin::WorldConfig;
+use crate::voxel::streaming;
+use crate::voxel::types::VoxelType;
+use crate::voxel::world::VoxelWorld;
+
+/// Horizontal radius in chunks around the player that must exist before it can move
+const SPAWN_RADIUS_CHUNKS: i32 = 2;
+
+/// Chunks waiting to be generated and the tasks generating them
+#[derive(Resource, Default)]
+pub struct ChunkGenerationQueue {
+    pending: VecDeque<IVec3>,
+    tasks: Vec<Task<Chunk>>,
+    /// Pending and in-flight positions, so streaming never queues a chunk twice
+    queued: HashSet<IVec3>,
+    /// Saved chunks are read from here instead of being generated (streamed worlds)
+    storage: Option<WorldStorage>,
+    /// Pending chunks are ordered by distance to the player on the next dispatch
+    sorted: bool,
+    started: Option<Instant>,
+    /// The summary is logged once, when the first batch of chunks is done
+    summary_logged: bool,
+    stats: GenerationStats,
+}
+
+impl ChunkGenerationQueue {
+    pub fn new(positions: Vec<IVec3>) -> Self {
+        Self {
+            queued: positions.iter().copied().collect(),
+            pending: positions.into(),
+            ..default()
+        }
+    }
+
+    /// Empty queue for a streamed world, loading saved chunks from `storage` when present
+    pub fn streamed(storage: Option<WorldStorage>) -> Self {
+        Self { storage, ..default() }
+    }
+
+    /// Queue a chunk unless it is already pending or in flight. Returns true if it was added.
+    pub fn request(&mut self, pos: IVec3) -> bool {
+        if !self.queued.insert(pos) {
+            return false;
+        }
+        self.pending.push_back(pos);
+        self.sorted = false;
+        true
+    }
+
+    /// Drop pending chunks that are no longer wanted. Returns the number dropped.
+    pub fn retain_pending(&mut self, mut keep: impl FnMut(IVec3) -> bool) -> usize {
+        let before = self.pending.len();
+        let queued = &mut self.queued;
+        self.pending.retain(|&pos| keep(pos) || !queued.remove(&pos));
+        before - self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.tasks.is_empty()
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.tasks.len()
+    }
+}
+
+/// Voxel totals logged once generation finishes
+#[derive(Default)]
+struct GenerationStats {
+    sand: u32,
+    dungeon_wall: u32,
+    dungeon_floor: u32,
+}
+
+impl GenerationStats {
+    fn record(&mut self, chunk: &Chunk) {
+        let mut dungeon_wall = 0u32;
+        let mut dungeon_floor = 0u32;
+        for voxel in chunk.iter() {
+            match voxel {
+                VoxelType::Sand => self.sand += 1,
+                VoxelType::DungeonWall => dungeon_wall += 1,
+                VoxelType::DungeonFloor => dungeon_floor += 1,
+                _ => {}
+            }
+        }
+        self.dungeon_wall += dungeon_wall;
+        self.dungeon_floor += dungeon_floor;
+
+        if dungeon_wall > 0 || dungeon_floor > 0 {
+            info!("Chunk {:?} (world pos {:?}) has {} dungeon walls, {} dungeon floors",
+                  chunk.position(), chunk.position() * CHUNK_SIZE_I32, dungeon_wall, dungeon_floor);
+        }
+    }
+}
+
+/// World generation progress for the loading screen
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct GenerationProgress {
+    /// Chunks read from the save
+    pub loaded: usize,
+    pub generated: usize,
+    /// Chunks that have to be generated in total (so far, when streaming)
+    pub to_generate: usize,
+    /// The chunks around the player exist, so it can move without falling through
+    pub spawn_ready: bool,
+}
+
+impl GenerationProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.to_generate == 0 {
+            1.0
+        } else {
+            self.generated as f32 / self.to_generate as f32
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.generated >= self.to_generate
+    }
+}
+
+/// Run condition: the terrain around the player is ready
+pub fn spawn_area_ready(progress: Res<GenerationProgress>) -> bool {
+    progress.spawn_ready
+}
+
+/// Run condition: every chunk of the world has been loaded or generated
+pub fn world_generated(progress: Res<GenerationProgress>) -> bool {
+    progress.is_done()
+}
+
+fn player_chunk(camera_query: &Query<&Transform, With<PlayerCamera>>) -> IVec3 {
+    camera_query
+        .single()
+        .map(|transform| VoxelWorld::world_to_chunk(transform.translation.floor().as_ivec3()))
+        .unwrap_or(IVec3::ZERO)
+}
+
+/// Start generation tasks on the async compute pool, nearest chunks first, keeping
+/// only a few per thread in flight so the closest ones are never stuck behind the rest
+pub fn dispatch_generation_tasks(
+    mut queue: ResMut<ChunkGenerationQueue>,
+    generator: Option<Res<WorldGenerator>>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+) {
+    let Some(generator) = generator else {
+        return;
+    };
+    if queue.pending.is_empty() {
+        return;
+    }
+
+    if !queue.sorted {
+        // Runs after PostStartup, so a restored player position is already in place
+        let center = player_chunk(&camera_query);
+        queue.pending.make_contiguous().sort_by_key(|pos| (*pos - center).length_squared());
+        queue.sorted = true;
+        queue.started.get_or_insert_with(Instant::now);
+    }
+
+    let pool = AsyncComputeTaskPool::get();
+    let max_tasks = (pool.thread_num() * 2).max(4);
+    while queue.tasks.len() < max_tasks
+        && let Some(pos) = queue.pending.pop_front()
+    {
+        let generator = generator.clone();
+        let storage = queue.storage.clone();
+        queue.tasks.push(pool.spawn(async move {
+            if let Some(storage) = storage {
+                match storage.load_chunk(pos) {
+                    Ok(Some(chunk)) => return chunk,
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to load chunk {:?}: {}. Regenerating it...", pos, e),
+                }
+            }
+            generator.generate(pos)
+        }));
+    }
+}
+
+/// Insert finished chunks into the world and update the progress
+pub fn collect_generated_chunks(
+    mut queue: ResMut<ChunkGenerationQueue>,
+    mut progress: ResMut<GenerationProgress>,
+    mut world: ResMut<VoxelWorld>,
+    mut save_state: ResMut<SaveState>,
+    persistence_settings: Res<WorldPersistence>,
+    world_config: Res<WorldConfig>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+) {
+    if queue.tasks.is_empty() && progress.spawn_ready {
+        return;
+    }
+
+    let ChunkGenerationQueue { tasks, queued, stats, .. } = &mut *queue;
+    let mut finished = 0;
+    tasks.retain_mut(|task| match check_ready(task) {
+        Some(chunk) => {
+            stats.record(&chunk);
+            queued.remove(&chunk.position());
+            world.insert_chunk(chunk);
+            finished += 1;
+            false
+        }
+        None => true,
+    });
+    progress.generated += finished;
+
+    if !progress.spawn_ready {
+        progress.spawn_ready = progress.is_done()
+            || spawn_area_loaded(&world, &world_config, player_chunk(&camera_query));
+        if progress.spawn_ready {
+            info!("Terrain around the player is ready");
+        }
+    }
+
+    if finished > 0 && queue.is_empty() && !queue.summary_logged {
+        queue.summary_logged = true;
+        log_summary(&queue, &progress);
+        // Generated chunks are flagged unsaved (all-air ones are not, they are cheap to
+        // regenerate); write them now so the next start loads instead of regenerating
+        if persistence_settings.auto_save {
+            save_state.request_save();
+        }
+    }
+}
+
+/// The player's column of chunks and its neighbors, from the bottom of the world (or of
+/// the streamed area) up to just above the player
+fn spawn_area_loaded(world: &VoxelWorld, world_config: &WorldConfig, center: IVec3) -> bool {
+    let streaming = &world_config.streaming;
+    let bottom = if streaming.enabled { center.y - streaming.vertical_radius } else { 0 };
+    (-SPAWN_RADIUS_CHUNKS..=SPAWN_RADIUS_CHUNKS).all(|dx| {
+        (-SPAWN_RADIUS_CHUNKS..=SPAWN_RADIUS_CHUNKS).all(|dz| {
+            (bottom..=center.y + 1).all(|y| {
+                let pos = IVec3::new(center.x + dx, y, center.z + dz);
+                let wanted = !streaming.enabled || streaming::in_radius(streaming, center, pos, 0);
+                !wanted || !world.chunk_in_bounds(pos) || world.chunk_exists(pos)
+            })
+        })
+    })
+}
+
+fn log_summary(queue: &ChunkGenerationQueue, progress: &GenerationProgress) {
+    let elapsed = queue.started.map(|start| start.elapsed().as_secs_f32()).unwrap_or(0.0);
+    info!("=== WORLD GENERATION SUMMARY ===");
+    info!("Generation time: {:.2}s", elapsed);
+    info!("Chunks loaded from disk: {}, generated: {}", progress.loaded, progress.generated);
+    info!("Total sand blocks: {}", queue.stats.sand);
+    info!("Total dungeon wall blocks: {}", queue.stats.dungeon_wall);
+    info!("Total dungeon floor blocks: {}", queue.stats.dungeon_floor);
+    info!("Dungeons are laid out per {}x{} voxel cell, up to one each", DUNGEON_CELL, DUNGEON_CELL);
+    info!("Sand appears near water (terrain height <= 24) and in sandy biomes");
+}
@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use std::collections::{HashSet, VecDeque};
+use crate::constants::CHUNK_SIZE_I32;
+use crate::voxel::types::{Voxel, VoxelType};
+use crate::voxel::world::VoxelWorld;
+
+/// Light level of open sky and of the brightest emitters
+pub const MAX_LIGHT: u8 = 15;
+
+/// Light of voxels under open sky with no block light, packed
+pub const FULL_SKY: u8 = MAX_LIGHT << 4;
+
+/// Each light level is this much dimmer than the one above it
+const LIGHT_FALLOFF: f32 = 0.8;
+
+/// Color of block light at full brightness; sky light is white
+const BLOCK_LIGHT_TINT: [f32; 3] = [1.0, 0.85, 0.6];
+
+/// Loaded chunks lit per frame. The rest wait, and are not meshed until lit.
+const CHUNKS_PER_FRAME: usize = 16;
+
+const NEIGHBORS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+/// Light is kept in two channels: light from the sky and light given off by voxels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightChannel {
+    Sky,
+    Block,
+}
+
+impl LightChannel {
+    pub fn level(self, light: u8) -> u8 {
+        match self {
+            Self::Sky => light >> 4,
+            Self::Block => light & 0x0F,
+        }
+    }
+
+    fn with_level(self, light: u8, level: u8) -> u8 {
+        match self {
+            Self::Sky => (light & 0x0F) | level << 4,
+            Self::Block => (light & 0xF0) | level,
+        }
+    }
+}
+
+/// Whether light can enter a voxel: anything but opaque solids
+pub fn lets_light_through(voxel: VoxelType) -> bool {
+    !voxel.is_solid() || voxel.is_transparent()
+}
+
+/// Whether replacing `old` with `new` can change the light around it
+pub fn changes_light(old: VoxelType, new: VoxelType) -> bool {
+    old.is_solid() != new.is_solid()
+        || lets_light_through(old) != lets_light_through(new)
+        || old.light_emission() != new.light_emission()
+}
+
+/// Vertex color for averaged sky and block light levels. Block light is warm, and both fade
+/// geometrically so caves go dark a few voxels from their entrance.
+pub fn light_color(sky: f32, block: f32) -> [f32; 3] {
+    let brightness = |level: f32| LIGHT_FALLOFF.powf(MAX_LIGHT as f32 - level);
+    let (sky, block) = (brightness(sky), brightness(block));
+    BLOCK_LIGHT_TINT.map(|tint| sky.max(block * tint))
+}
+
+/// Level light has after moving one voxel in `direction` into `voxel`. Full sky light falls
+/// straight down through air and liquids without fading.
+fn spread(channel: LightChannel, level: u8, direction: IVec3, voxel: VoxelType) -> u8 {
+    if channel == LightChannel::Sky && direction == IVec3::NEG_Y && level == MAX_LIGHT && !voxel.is_solid() {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(1)
+    }
+}
+
+/// Sky light a voxel gets from above when the chunk above it is not loaded, which is taken
+/// to be open sky
+fn sky_from_unloaded_above(world: &VoxelWorld, pos: IVec3, voxel: VoxelType) -> Option<u8> {
+    let above = VoxelWorld::world_to_chunk(pos + IVec3::Y);
+    (!world.chunk_exists(above) && lets_light_through(voxel)).then(|| spread(LightChannel::Sky, MAX_LIGHT, IVec3::NEG_Y, voxel))
+}
+
+/// Breadth-first light flood fill over the world for one channel. Removals run first and
+/// hand the light bordering what they cleared to the spreading pass.
+struct Propagation {
+    channel: LightChannel,
+    spreading: VecDeque<IVec3>,
+    removing: VecDeque<(IVec3, u8)>,
+}
+
+impl Propagation {
+    fn new(channel: LightChannel) -> Self {
+        Self { channel, spreading: VecDeque::new(), removing: VecDeque::new() }
+    }
+
+    fn level(&self, world: &VoxelWorld, pos: IVec3) -> Option<u8> {
+        world.get_light(pos).map(|light| self.channel.level(light))
+    }
+
+    fn set_level(&self, world: &mut VoxelWorld, pos: IVec3, level: u8, changed: &mut HashSet<IVec3>) {
+        if let Some(light) = world.get_light(pos) {
+            world.set_light(pos, self.channel.with_level(light, level));
+            touch(changed, pos);
+        }
+    }
+
+    /// Give a voxel a level and spread it from there
+    fn light(&mut self, world: &mut VoxelWorld, pos: IVec3, level: u8, changed: &mut HashSet<IVec3>) {
+        self.set_level(world, pos, level, changed);
+        self.spreading.push_back(pos);
+    }
+
+    /// Clear the light of a voxel and everything that was lit through it
+    fn darken(&mut self, world: &mut VoxelWorld, pos: IVec3, changed: &mut HashSet<IVec3>) {
+        if let Some(level) = self.level(world, pos).filter(|&level| level > 0) {
+            self.set_level(world, pos, 0, changed);
+            self.removing.push_back((pos, level));
+        }
+    }
+
+    fn run(&mut self, world: &mut VoxelWorld, changed: &mut HashSet<IVec3>) {
+        while let Some((pos, level)) = self.removing.pop_front() {
+            for direction in NEIGHBORS {
+                let neighbor = pos + direction;
+                let Some(current) = self.level(world, neighbor).filter(|&current| current > 0) else {
+                    continue;
+                };
+                let fell_through = self.channel == LightChannel::Sky
+                    && direction == IVec3::NEG_Y
+                    && level == MAX_LIGHT
+                    && current == MAX_LIGHT;
+                if current < level || fell_through {
+                    self.set_level(world, neighbor, 0, changed);
+                    self.removing.push_back((neighbor, current));
+                    // Emitters keep their own light
+                    let emission = world.get_voxel(neighbor).map_or(0, |voxel| voxel.light_emission());
+                    if self.channel == LightChannel::Block && emission > 0 {
+                        self.light(world, neighbor, emission, changed);
+                    }
+                } else {
+                    // Lit from elsewhere, it lights the cleared voxels again
+                    self.spreading.push_back(neighbor);
+                }
+            }
+        }
+
+        while let Some(pos) = self.spreading.pop_front() {
+            let Some(level) = self.level(world, pos).filter(|&level| level > 1) else {
+                continue;
+            };
+            for direction in NEIGHBORS {
+                let neighbor = pos + direction;
+                let Some(voxel) = world.get_voxel(neighbor).filter(|&voxel| lets_light_through(voxel)) else {
+                    continue;
+                };
+                let Some(current) = self.level(world, neighbor) else {
+                    continue;
+                };
+                let next = spread(self.channel, level, direction, voxel);
+                if next > current {
+                    self.light(world, neighbor, next, changed);
+                }
+            }
+        }
+    }
+}
+
+/// Remember the chunks whose meshes read a voxel's light: its own and, on a border, the
+/// neighbors sharing it
+fn touch(changed: &mut HashSet<IVec3>, pos: IVec3) {
+    let min = VoxelWorld::world_to_chunk(pos - IVec3::ONE);
+    let max = VoxelWorld::world_to_chunk(pos + IVec3::ONE);
+    for z in min.z..=max.z {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                changed.insert(IVec3::new(x, y, z));
+            }
+        }
+    }
+}
+
+/// Light a newly loaded chunk: its emitters, sky from above and the light of its lit
+/// neighbors spread into it and, where brighter, on into the neighbors
+pub fn light_chunk(world: &mut VoxelWorld, chunk_pos: IVec3, changed: &mut HashSet<IVec3>) {
+    let Some(chunk) = world.get_chunk_mut(chunk_pos) else {
+        return;
+    };
+    chunk.reset_light();
+    // Neighbors meshed before this chunk was lit took its voxels for open sky
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                changed.insert(chunk_pos + IVec3::new(x, y, z));
+            }
+        }
+    }
+
+    let origin = VoxelWorld::chunk_to_world(chunk_pos);
+    let top = CHUNK_SIZE_I32 - 1;
+    let mut sky = Propagation::new(LightChannel::Sky);
+    let mut block = Propagation::new(LightChannel::Block);
+
+    for z in 0..CHUNK_SIZE_I32 {
+        for y in 0..CHUNK_SIZE_I32 {
+            for x in 0..CHUNK_SIZE_I32 {
+                let pos = origin + IVec3::new(x, y, z);
+                let Some(voxel) = world.get_voxel(pos) else {
+                    continue;
+                };
+                let emission = voxel.light_emission();
+                if emission > 0 {
+                    block.light(world, pos, emission, changed);
+                }
+                if y == top
+                    && let Some(level) = sky_from_unloaded_above(world, pos, voxel)
+                {
+                    sky.light(world, pos, level, changed);
+                }
+            }
+        }
+    }
+
+    // Light already in the neighbors flows in across the faces
+    for direction in NEIGHBORS {
+        for (u, v) in (0..CHUNK_SIZE_I32).flat_map(|u| (0..CHUNK_SIZE_I32).map(move |v| (u, v))) {
+            let outside = origin + face_cell(direction, u, v) + direction;
+            if world.get_light(outside).is_some() {
+                sky.spreading.push_back(outside);
+                block.spreading.push_back(outside);
+            }
+        }
+    }
+    sky.run(world, changed);
+    block.run(world, changed);
+
+    // The chunk below took this one for open sky while it was not loaded
+    for (x, z) in (0..CHUNK_SIZE_I32).flat_map(|x| (0..CHUNK_SIZE_I32).map(move |z| (x, z))) {
+        let bottom = origin + IVec3::new(x, 0, z);
+        let below = bottom + IVec3::NEG_Y;
+        let (Some(voxel), Some(current)) = (world.get_voxel(below), sky.level(world, below)) else {
+            continue;
+        };
+        let assumed = spread(LightChannel::Sky, MAX_LIGHT, IVec3::NEG_Y, voxel);
+        let from_above = match (world.get_voxel(bottom), sky.level(world, bottom)) {
+            (Some(above), Some(level)) if lets_light_through(above) => spread(LightChannel::Sky, level, IVec3::NEG_Y, voxel),
+            _ => 0,
+        };
+        if current > 0 && current == assumed && from_above < current {
+            sky.darken(world, below, changed);
+        }
+    }
+    sky.run(world, changed);
+}
+
+/// Cell on the face of a chunk that points in `direction`, at (u, v) across the face
+fn face_cell(direction: IVec3, u: i32, v: i32) -> IVec3 {
+    let last = CHUNK_SIZE_I32 - 1;
+    match direction {
+        IVec3::X => IVec3::new(last, u, v),
+        IVec3::NEG_X => IVec3::new(0, u, v),
+        IVec3::Y => IVec3::new(u, last, v),
+        IVec3::NEG_Y => IVec3::new(u, 0, v),
+        IVec3::Z => IVec3::new(u, v, last),
+        _ => IVec3::new(u, v, 0),
+    }
+}
+
+/// Update the light around an edited voxel: light that reached it or came from it is
+/// removed, then the light around it and its own emission spread in again
+pub fn relight_voxel(world: &mut VoxelWorld, pos: IVec3, changed: &mut HashSet<IVec3>) {
+    let Some(voxel) = world.get_voxel(pos) else {
+        return;
+    };
+    if world.get_light(pos).is_none() {
+        // The chunk is still waiting to be lit as a whole
+        return;
+    }
+
+    for channel in [LightChannel::Sky, LightChannel::Block] {
+        let mut propagation = Propagation::new(channel);
+        propagation.darken(world, pos, changed);
+
+        if lets_light_through(voxel) {
+            for direction in NEIGHBORS {
+                propagation.spreading.push_back(pos + direction);
+            }
+        }
+        match channel {
+            LightChannel::Sky => {
+                if let Some(level) = sky_from_unloaded_above(world, pos, voxel) {
+                    propagation.light(world, pos, level, changed);
+                }
+            }
+            LightChannel::Block => {
+                let emission = voxel.light_emission();
+                if emission > 0 {
+                    propagation.light(world, pos, emission, changed);
+                }
+            }
+        }
+        propagation.run(world, changed);
+    }
+}
+
+/// Relight the voxels edited since the last frame and light a few newly loaded chunks, then
+/// remesh the chunks whose light changed
+pub fn update_lighting(mut world: ResMut<VoxelWorld>) {
+    let mut changed = HashSet::new();
+    for pos in world.take_light_edits() {
+        relight_voxel(&mut world, pos, &mut changed);
+    }
+    for chunk_pos in world.take_unlit_chunks(CHUNKS_PER_FRAME) {
+        light_chunk(&mut world, chunk_pos, &mut changed);
+    }
+
+    for chunk_pos in changed {
+        if let Some(chunk) = world.get_chunk_mut(chunk_pos) {
+            chunk.mark_dirty();
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use bevy::prelude::*;
+use bevy::tasks::{futures::check_ready, AsyncComputeTaskPool, Task};
+use std::collections::HashMap;
+use crate::camera::controller::PlayerCamera;
+use crate::constants::{CHUNK_SIZE_I32, VOXEL_SIZE};
+use crate::rendering::blocky_material::BlockyMaterial;
+use crate::rendering::materials::{VoxelMaterial, WaterMaterial};
+use crate::rendering::triplanar_material::{TriplanarMaterial, TriplanarMaterialHandle};
+use crate::voxel::chunk::Chunk;
+use crate::voxel::meshing::{generate_chunk_mesh_with_mode, ChunkMesh, ChunkMeshResult, ChunkSnapshot, MeshMode, MeshSettings};
+use crate::voxel::plugin::WorldConfig;
+use crate::voxel::streaming::ChunkUnloaded;
+use crate::voxel::world::VoxelWorld;
+
+/// A mesh being built in the background from a snapshot of a chunk
+struct MeshTask {
+    /// Chunk version the snapshot was taken at
+    version: u32,
+    mode: MeshMode,
+    task: Task<ChunkMeshResult>,
+}
+
+/// A finished mesh waiting for its turn to be uploaded
+struct ReadyMesh {
+    position: IVec3,
+    version: u32,
+    mode: MeshMode,
+    result: ChunkMeshResult,
+}
+
+/// Chunk meshes being built on the async compute pool and those waiting to be uploaded
+#[derive(Resource, Default)]
+pub struct ChunkMeshTasks {
+    tasks: HashMap<IVec3, MeshTask>,
+    ready: Vec<ReadyMesh>,
+    /// Level of detail of chunks not meshed at full detail
+    lods: HashMap<IVec3, usize>,
+}
+
+impl ChunkMeshTasks {
+    pub fn in_flight(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn waiting_upload(&self) -> usize {
+        self.ready.len()
+    }
+
+    /// Level of detail a chunk is meshed at, an index into `LOD_SCALES`
+    pub fn lod(&self, chunk_pos: IVec3) -> usize {
+        self.lods.get(&chunk_pos).copied().unwrap_or(0)
+    }
+}
+
+fn camera_chunk(camera_query: &Query<&Transform, With<PlayerCamera>>) -> IVec3 {
+    camera_query
+        .single()
+        .map(|transform| VoxelWorld::world_to_chunk(transform.translation.floor().as_ivec3()))
+        .unwrap_or(IVec3::ZERO)
+}
+
+/// Pick the level of detail of every loaded chunk from its distance to the camera and remesh
+/// the chunks whose level changed, see `LodSection::next_level`.
+pub fn update_chunk_lods(
+    mut mesh_tasks: ResMut<ChunkMeshTasks>,
+    mut world: ResMut<VoxelWorld>,
+    world_config: Res<WorldConfig>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+) {
+    let Ok(camera) = camera_query.single() else {
+        return;
+    };
+    let settings = &world_config.lod;
+    if !settings.enabled && mesh_tasks.lods.is_empty() {
+        return;
+    }
+    let camera_chunk = camera.translation / (CHUNK_SIZE_I32 as f32 * VOXEL_SIZE);
+
+    let mut changed = Vec::new();
+    for pos in world.loaded_chunk_positions() {
+        let distance = camera_chunk.distance(pos.as_vec3() + Vec3::splat(0.5));
+        let current = mesh_tasks.lod(pos);
+        let lod = settings.next_level(current, distance);
+        if lod != current {
+            changed.push((pos, lod));
+        }
+    }
+
+    for (pos, lod) in changed {
+        if lod == 0 {
+            mesh_tasks.lods.remove(&pos);
+        } else {
+            mesh_tasks.lods.insert(pos, lod);
+        }
+        if let Some(chunk) = world.get_chunk_mut(pos) {
+            chunk.mark_dirty();
+        }
+    }
+}
+
+/// Snapshot dirty chunks and mesh them on the async compute pool, nearest to the camera first.
+/// A chunk edited again while its mesh is being built gets a new task; the old one is dropped,
+/// which cancels it. So are the tasks of chunks that were streamed out.
+pub fn dispatch_mesh_tasks(
+    mut mesh_tasks: ResMut<ChunkMeshTasks>,
+    mut world: ResMut<VoxelWorld>,
+    mut unloaded: MessageReader<ChunkUnloaded>,
+    mesh_settings: Res<MeshSettings>,
+    world_config: Res<WorldConfig>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+) {
+    for ChunkUnloaded(pos) in unloaded.read() {
+        mesh_tasks.tasks.remove(pos);
+        mesh_tasks.lods.remove(pos);
+        mesh_tasks.ready.retain(|mesh| mesh.position != *pos);
+    }
+
+    // Chunks stay dirty until all their neighbors are generated and lit, so borders are
+    // meshed once against final terrain and light
+    let mut dirty_chunks: Vec<IVec3> = world
+        .dirty_chunks()
+        .filter(|&pos| world.neighbors_loaded(pos) && world.neighbors_lit(pos))
+        .collect();
+    if dirty_chunks.is_empty() {
+        return;
+    }
+
+    let center = camera_chunk(&camera_query);
+    dirty_chunks.sort_by_key(|pos| (*pos - center).length_squared());
+
+    let pool = AsyncComputeTaskPool::get();
+    let max_tasks = (pool.thread_num() * 2).max(4);
+    for chunk_pos in dirty_chunks {
+        // Superseded tasks are replaced, so they do not count against the limit
+        if mesh_tasks.tasks.len() >= max_tasks && !mesh_tasks.tasks.contains_key(&chunk_pos) {
+            break;
+        }
+        let Some(capture) = ChunkSnapshot::capture_lod(&world, chunk_pos, mesh_tasks.lod(chunk_pos)) else {
+            continue;
+        };
+        let Some(chunk) = world.get_chunk_mut(chunk_pos) else {
+            continue;
+        };
+        chunk.clear_dirty();
+
+        let (mode, greedy) = (mesh_settings.mode, world_config.greedy_meshing);
+        let task = pool.spawn(async move { generate_chunk_mesh_with_mode(&capture.into_snapshot(), mode, greedy) });
+        mesh_tasks.tasks.insert(chunk_pos, MeshTask { version: chunk.version(), mode, task });
+    }
+}
+
+/// Collect finished meshes and upload up to `meshing.upload_budget` of them per frame,
+/// nearest to the camera first. Meshes of chunks edited since their snapshot are discarded.
+#[allow(clippy::too_many_arguments)]
+pub fn upload_chunk_meshes(
+    mut commands: Commands,
+    mut mesh_tasks: ResMut<ChunkMeshTasks>,
+    mut world: ResMut<VoxelWorld>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    blocky_material: Res<VoxelMaterial>,
+    triplanar_material: Res<TriplanarMaterialHandle>,
+    water_material: Res<WaterMaterial>,
+    world_config: Res<WorldConfig>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+) {
+    let ChunkMeshTasks { tasks, ready, .. } = &mut *mesh_tasks;
+    tasks.retain(|&position, mesh_task| match check_ready(&mut mesh_task.task) {
+        Some(result) => {
+            ready.push(ReadyMesh { position, version: mesh_task.version, mode: mesh_task.mode, result });
+            false
+        }
+        None => true,
+    });
+    if ready.is_empty() {
+        return;
+    }
+
+    let center = camera_chunk(&camera_query);
+    // Farthest first, so the nearest are popped off the end
+    ready.sort_by_key(|mesh| std::cmp::Reverse((mesh.position - center).length_squared()));
+
+    let mut uploaded = 0;
+    while uploaded < world_config.mesh_upload_budget.max(1)
+        && let Some(mesh) = ready.pop()
+    {
+        let Some(chunk) = world.get_chunk_mut(mesh.position) else {
+            continue;
+        };
+        if chunk.version() != mesh.version {
+            // Edited after the snapshot was taken; a newer mesh is queued or in flight
+            continue;
+        }
+
+        let materials = ChunkMaterials {
+            blocky: &blocky_material,
+            triplanar: &triplanar_material,
+            water: &water_material,
+        };
+        apply_chunk_mesh(&mut commands, &mut meshes, chunk, mesh.mode, mesh.result, &materials);
+        uploaded += 1;
+    }
+}
+
+struct ChunkMaterials<'a> {
+    blocky: &'a VoxelMaterial,
+    triplanar: &'a TriplanarMaterialHandle,
+    water: &'a WaterMaterial,
+}
+
+/// Spawn, update or despawn the chunk's solid and water mesh entities
+fn apply_chunk_mesh(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    chunk: &mut Chunk,
+    mode: MeshMode,
+    mesh_result: ChunkMeshResult,
+    materials: &ChunkMaterials,
+) {
+    let chunk_pos = chunk.position();
+    let world_pos = VoxelWorld::chunk_to_world(chunk_pos);
+
+    // Handle solid mesh
+    if mesh_result.solid.is_empty() {
+        if let Some(entity) = chunk.mesh_entity() {
+            commands.entity(entity).despawn();
+            chunk.clear_mesh_entity();
+        }
+    } else {
+        let mesh_handle = meshes.add(mesh_result.solid.into_mesh());
+
+        if let Some(entity) = chunk.mesh_entity() {
+            let mut entity = commands.entity(entity);
+            entity.insert(Mesh3d(mesh_handle));
+            // The mesh mode can change on config reload, so keep the material in sync
+            match mode {
+                MeshMode::Blocky => {
+                    entity
+                        .remove::<MeshMaterial3d<TriplanarMaterial>>()
+                        .insert(MeshMaterial3d(materials.blocky.handle.clone()));
+                }
+                MeshMode::SurfaceNets => {
+                    entity
+                        .remove::<MeshMaterial3d<BlockyMaterial>>()
+                        .insert(MeshMaterial3d(materials.triplanar.handle.clone()));
+                }
+            }
+        } else {
+            // Spawn with appropriate material based on mesh mode
+            let transform = Transform::from_xyz(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32);
+            let entity = match mode {
+                MeshMode::Blocky => commands.spawn((
+                    Mesh3d(mesh_handle),
+                    MeshMaterial3d(materials.blocky.handle.clone()),
+                    transform,
+                    ChunkMesh { chunk_position: chunk_pos },
+                )).id(),
+                MeshMode::SurfaceNets => commands.spawn((
+                    Mesh3d(mesh_handle),
+                    MeshMaterial3d(materials.triplanar.handle.clone()),
+                    transform,
+                    ChunkMesh { chunk_position: chunk_pos },
+                )).id(),
+            };
+            chunk.set_mesh_entity(entity);
+        }
+    }
+
+    // Handle water mesh
+    if mesh_result.water.is_empty() {
+        if let Some(entity) = chunk.water_mesh_entity() {
+            commands.entity(entity).despawn();
+            chunk.clear_water_mesh_entity();
+        }
+    } else {
+        let water_mesh_handle = meshes.add(mesh_result.water.into_mesh());
+
+        if let Some(entity) = chunk.water_mesh_entity() {
+            commands.entity(entity).insert(Mesh3d(water_mesh_handle));
+        } else {
+            let entity = commands.spawn((
+                Mesh3d(water_mesh_handle),
+                MeshMaterial3d(materials.water.handle.clone()),
+                Transform::from_xyz(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32),
+                ChunkMesh { chunk_position: chunk_pos },
+            )).id();
+            chunk.set_water_mesh_entity(entity);
+        }
+    }
+}
@@ -1,15 +1,21 @@
 use bevy::asset::RenderAssetUsages;
 use bevy::prelude::*;
 use bevy_mesh::{Indices, PrimitiveTopology};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use crate::constants::VOXEL_SIZE;
+use crate::constants::{ATLAS_COLUMNS, ATLAS_UV_PADDING, CHUNK_SIZE_I32, VOXEL_SIZE};
 use crate::voxel::chunk::Chunk;
+use crate::voxel::lighting::{self, LightChannel, FULL_SKY};
+use crate::voxel::palette::PalettedStorage;
+use crate::voxel::registry;
 use crate::voxel::types::{VoxelType, Voxel};
+use crate::voxel::water_mesh::generate_water_mesh;
 use crate::voxel::world::VoxelWorld;
 
 // Surface nets imports for smooth meshing
 use fast_surface_nets::{surface_nets, SurfaceNetsBuffer};
-use ndshape::{ConstShape, ConstShape3u32};
+use ndshape::{RuntimeShape, Shape};
 
 // Debug helper: log if a solid face ends up using the water atlas tile
 const DEBUG_LOG_WATER_TILE_ON_SOLIDS: bool = true;
@@ -31,10 +37,30 @@ pub enum Face {
     West,
 }
 
+impl Face {
+    pub const ALL: [Face; 6] = [Face::Top, Face::Bottom, Face::North, Face::South, Face::East, Face::West];
+
+    /// Axis indices of the face normal and of the quad's first (v0 to v1) and second
+    /// (v3 to v0) edges
+    fn axes(self) -> (usize, usize, usize) {
+        match self {
+            Face::Top | Face::Bottom => (1, 0, 2),
+            Face::North | Face::South => (2, 0, 1),
+            Face::East | Face::West => (0, 2, 1),
+        }
+    }
+}
+
 pub struct MeshData {
     pub positions: Vec<[f32; 3]>,
     pub normals: Vec<[f32; 3]>,
     pub uvs: Vec<[f32; 2]>,
+    /// Atlas tile corner of blocky faces, whose `uvs` count tile repeats across the quad
+    pub tile_origins: Vec<[f32; 2]>,
+    /// Direction water flows in at each vertex, in the plane of `uvs`
+    pub flows: Vec<[f32; 2]>,
+    /// Ore overlay of surface nets vertices: weight and atlas tile drawn over the splat
+    pub overlays: Vec<[f32; 2]>,
     pub colors: Vec<[f32; 4]>, // Vertex colors for AO
     pub indices: Vec<u32>,
 }
@@ -45,6 +71,9 @@ impl MeshData {
             positions: Vec::new(),
             normals: Vec::new(),
             uvs: Vec::new(),
+            tile_origins: Vec::new(),
+            flows: Vec::new(),
+            overlays: Vec::new(),
             colors: Vec::new(),
             indices: Vec::new(),
         }
@@ -59,6 +88,14 @@ impl MeshData {
         mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
         mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        // Water flow rides in the second UV set, which the standard vertex stage passes through
+        if !self.tile_origins.is_empty() {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, self.tile_origins);
+        } else if !self.flows.is_empty() {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, self.flows);
+        } else if !self.overlays.is_empty() {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, self.overlays);
+        }
         mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
         mesh.insert_indices(Indices::U32(self.indices));
         mesh
@@ -71,39 +108,308 @@ pub struct ChunkMeshResult {
     pub water: MeshData,
 }
 
+/// Cells of neighbor chunks copied around a snapshot; the surface nets SDF smooths the
+/// padding cell with its neighbors, so it reads two cells deep. An edit changes the meshes
+/// of every chunk within this many voxels.
+pub const SNAPSHOT_BORDER: i32 = 2;
+
+/// Voxels per cell edge of the grid a chunk is meshed from, by level of detail
+pub const LOD_SCALES: [i32; 4] = [1, 2, 4, 8];
+
+/// Copy of a chunk plus a border of its neighbors' voxels, so meshing can run on a
+/// background task without access to the world. Distant chunks are downsampled,
+/// with each cell standing for `scale`³ voxels.
+pub struct ChunkSnapshot {
+    position: IVec3,
+    /// Voxels per cell edge, 1 at full detail
+    scale: i32,
+    /// Cells per chunk edge
+    size: i32,
+    /// Padded cells in x, y, z order; None outside the world
+    cells: Vec<Option<VoxelType>>,
+    /// Fill level of each padded cell, only captured at full detail when the chunk or a
+    /// neighbor has been sculpted
+    fills: Option<Vec<u8>>,
+    /// Packed light of each padded cell, only captured at full detail; distant chunks are
+    /// drawn as if under open sky
+    lights: Option<Vec<u8>>,
+}
+
+impl ChunkSnapshot {
+    /// Capture at full detail
+    pub fn capture(world: &VoxelWorld, chunk_pos: IVec3) -> Option<Self> {
+        let chunk = world.get_chunk(chunk_pos)?;
+        let (scale, size) = (1, CHUNK_SIZE_I32);
+        let padded = size + 2 * SNAPSHOT_BORDER;
+        let origin = VoxelWorld::chunk_to_world(chunk_pos);
+        let mut cells = Vec::with_capacity((padded * padded * padded) as usize);
+
+        for z in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+            for y in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                for x in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                    let cell = IVec3::new(x, y, z);
+                    let inside = cell.cmpge(IVec3::ZERO).all() && cell.cmplt(IVec3::splat(size)).all();
+                    cells.push(if inside {
+                        Some(chunk.get(cell.as_uvec3()))
+                    } else {
+                        world.get_voxel(origin + cell)
+                    });
+                }
+            }
+        }
+
+        let sculpted = (-1..=1).any(|z| {
+            (-1..=1).any(|y| {
+                (-1..=1).any(|x| {
+                    world
+                        .get_chunk(chunk_pos + IVec3::new(x, y, z))
+                        .is_some_and(|neighbor| neighbor.has_density())
+                })
+            })
+        });
+        let fills = sculpted.then(|| {
+            let mut fills = Vec::with_capacity(cells.len());
+            for z in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                for y in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                    for x in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                        fills.push(world.get_density(origin + IVec3::new(x, y, z)).unwrap_or(0));
+                    }
+                }
+            }
+            fills
+        });
+
+        let mut lights = Vec::with_capacity(cells.len());
+        for z in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+            for y in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                for x in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                    lights.push(world.get_light(origin + IVec3::new(x, y, z)).unwrap_or(FULL_SKY));
+                }
+            }
+        }
+
+        Some(Self { position: chunk_pos, scale, size, cells, fills, lights: Some(lights) })
+    }
+
+    /// Capture at level of detail `lod`, an index into `LOD_SCALES`
+    pub fn capture_lod(world: &VoxelWorld, chunk_pos: IVec3, lod: usize) -> Option<ChunkCapture> {
+        let scale = LOD_SCALES[lod.min(LOD_SCALES.len() - 1)];
+        if scale == 1 {
+            return Self::capture(world, chunk_pos).map(ChunkCapture::Full);
+        }
+        world.get_chunk(chunk_pos)?;
+
+        let mut chunks = Vec::with_capacity(27);
+        for z in -1..=1 {
+            for y in -1..=1 {
+                for x in -1..=1 {
+                    let neighbor = world.get_chunk(chunk_pos + IVec3::new(x, y, z));
+                    chunks.push(neighbor.map(|chunk| chunk.voxels().clone()));
+                }
+            }
+        }
+        Some(ChunkCapture::Downsampled(LodCapture { position: chunk_pos, scale, chunks }))
+    }
+
+    /// Snapshot of a chunk with each cell downsampled from `scale`³ voxels
+    fn downsampled(capture: &LodCapture) -> Self {
+        let scale = capture.scale;
+        let size = CHUNK_SIZE_I32 / scale;
+        let padded = size + 2 * SNAPSHOT_BORDER;
+        let origin = VoxelWorld::chunk_to_world(capture.position);
+        let mut cells = Vec::with_capacity((padded * padded * padded) as usize);
+        for z in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+            for y in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                for x in -SNAPSHOT_BORDER..size + SNAPSHOT_BORDER {
+                    cells.push(downsample(capture, origin + IVec3::new(x, y, z) * scale, scale));
+                }
+            }
+        }
+        Self { position: capture.position, scale, size, cells, fills: None, lights: None }
+    }
+
+    fn index(&self, local: IVec3) -> Option<usize> {
+        let padded_size = self.size + 2 * SNAPSHOT_BORDER;
+        let padded = local + IVec3::splat(SNAPSHOT_BORDER);
+        if padded.cmplt(IVec3::ZERO).any() || padded.cmpge(IVec3::splat(padded_size)).any() {
+            return None;
+        }
+        Some((padded.x + padded.y * padded_size + padded.z * padded_size * padded_size) as usize)
+    }
+
+    pub fn position(&self) -> IVec3 {
+        self.position
+    }
+
+    /// Voxels per cell edge
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    /// Cells per chunk edge
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    /// First cell of the chunk in cell coordinates (world voxel coordinates at full detail)
+    pub fn origin(&self) -> IVec3 {
+        self.position * self.size
+    }
+
+    /// Cell inside the chunk
+    pub fn get(&self, local: UVec3) -> VoxelType {
+        self.index(local.as_ivec3())
+            .and_then(|index| self.cells[index])
+            .unwrap_or(VoxelType::Air)
+    }
+
+    /// Cell at a position in cell coordinates, None outside the world or beyond the copied border
+    pub fn get_voxel(&self, cell_pos: IVec3) -> Option<VoxelType> {
+        self.index(cell_pos - self.origin()).and_then(|index| self.cells[index])
+    }
+
+    /// Fill level of a cell, 0 empty to 255 full; the water level for liquids. Cells that
+    /// were never sculpted or flowed into are full when solid or liquid.
+    pub fn get_fill(&self, cell_pos: IVec3) -> u8 {
+        let Some(index) = self.index(cell_pos - self.origin()) else {
+            return 0;
+        };
+        if let Some(fills) = &self.fills {
+            return fills[index];
+        }
+        match self.cells[index] {
+            Some(voxel) if voxel.is_solid() || voxel.is_liquid() => 255,
+            _ => 0,
+        }
+    }
+
+    /// Packed sky and block light of a cell; open sky where it is unknown
+    pub fn get_light(&self, cell_pos: IVec3) -> u8 {
+        match (&self.lights, self.index(cell_pos - self.origin())) {
+            (Some(lights), Some(index)) => lights[index],
+            _ => FULL_SKY,
+        }
+    }
+
+    /// Average sky and block light levels of the cells light can enter among `cells`.
+    /// Solid cells are dark inside, so they would darken the surface around them.
+    pub fn average_light(&self, cells: impl IntoIterator<Item = IVec3>) -> Option<Vec2> {
+        let mut sum = Vec2::ZERO;
+        let mut count = 0;
+        for cell in cells {
+            if !self.get_voxel(cell).is_some_and(lighting::lets_light_through) {
+                continue;
+            }
+            let light = self.get_light(cell);
+            sum += Vec2::new(LightChannel::Sky.level(light) as f32, LightChannel::Block.level(light) as f32);
+            count += 1;
+        }
+        (count > 0).then(|| sum / count as f32)
+    }
+}
+
+/// What `ChunkSnapshot::capture_lod` copies from the world. Downsampling reads every voxel
+/// of a cell, so it is left to the mesh task.
+pub enum ChunkCapture {
+    Full(ChunkSnapshot),
+    Downsampled(LodCapture),
+}
+
+impl ChunkCapture {
+    /// Snapshot to mesh, downsampling the copied voxels if needed
+    pub fn into_snapshot(self) -> ChunkSnapshot {
+        match self {
+            ChunkCapture::Full(snapshot) => snapshot,
+            ChunkCapture::Downsampled(capture) => ChunkSnapshot::downsampled(&capture),
+        }
+    }
+}
+
+/// Voxels of a distant chunk and the chunks around it. The padding of the coarsest level
+/// reaches `SNAPSHOT_BORDER * 8` voxels, so it stays within the neighbors.
+pub struct LodCapture {
+    position: IVec3,
+    scale: i32,
+    /// Voxels of the 3×3×3 chunks around `position` in x, y, z order; None where not loaded
+    chunks: Vec<Option<PalettedStorage>>,
+}
+
+impl LodCapture {
+    fn get_voxel(&self, world_pos: IVec3) -> Option<VoxelType> {
+        let offset = VoxelWorld::world_to_chunk(world_pos) - self.position + IVec3::ONE;
+        if offset.cmplt(IVec3::ZERO).any() || offset.cmpgt(IVec3::splat(2)).any() {
+            return None;
+        }
+        let voxels = self.chunks[(offset.x + offset.y * 3 + offset.z * 9) as usize].as_ref()?;
+        let local = VoxelWorld::world_to_local(world_pos);
+        Some(voxels.get(Chunk::index(local.x as usize, local.y as usize, local.z as usize)))
+    }
+}
+
+/// One cell of a downsampled grid: the most common solid voxel if at least half of the
+/// cell is solid, else the most common liquid if solids and liquids fill half of it
+fn downsample(capture: &LodCapture, min: IVec3, scale: i32) -> Option<VoxelType> {
+    let mut counts: Vec<(VoxelType, u32)> = Vec::new();
+    for z in 0..scale {
+        for y in 0..scale {
+            for x in 0..scale {
+                let Some(voxel) = capture.get_voxel(min + IVec3::new(x, y, z)) else {
+                    continue;
+                };
+                match counts.iter_mut().find(|(v, _)| *v == voxel) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((voxel, 1)),
+                }
+            }
+        }
+    }
+    if counts.is_empty() {
+        return None;
+    }
+
+    let half = (scale * scale * scale / 2) as u32;
+    let most_common = |wanted: fn(VoxelType) -> bool| {
+        counts
+            .iter()
+            .filter(|(voxel, _)| wanted(*voxel))
+            .max_by_key(|(_, count)| *count)
+            .map(|(voxel, _)| *voxel)
+    };
+    let total = |wanted: fn(VoxelType) -> bool| -> u32 {
+        counts.iter().filter(|(voxel, _)| wanted(*voxel)).map(|(_, count)| count).sum()
+    };
+
+    let solid = total(|voxel| voxel.is_solid());
+    let liquid = total(|voxel| voxel.is_liquid());
+    if solid >= half {
+        most_common(|voxel| voxel.is_solid())
+    } else if liquid > 0 && solid + liquid >= half {
+        most_common(|voxel| voxel.is_liquid())
+    } else {
+        Some(VoxelType::Air)
+    }
+}
+
 pub fn generate_chunk_mesh(
-    chunk: &Chunk,
-    world: &VoxelWorld,
+    chunk: &ChunkSnapshot,
 ) -> ChunkMeshResult {
     let mut solid_mesh = MeshData::new();
-    let mut water_mesh = MeshData::new();
     
-    for x in 0..16 {
-        for y in 0..16 {
-            for z in 0..16 {
+    let size = chunk.size() as u32;
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
                 let local = UVec3::new(x, y, z);
                 let voxel = chunk.get(local);
                 
-                if voxel == VoxelType::Air {
-                    continue;
-                }
-
-                if voxel.is_liquid() {
-                    // Generate water mesh faces (only visible against air)
-                    check_water_face(chunk, world, local, Face::Top, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::Bottom, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::North, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::South, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::East, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::West, &mut water_mesh, voxel);
-                } else if voxel.is_solid() {
+                if voxel.is_solid() {
                     // Solid blocks - render faces adjacent to air or water (transparent)
-                    check_face(chunk, world, local, Face::Top, &mut solid_mesh, voxel);
-                    check_face(chunk, world, local, Face::Bottom, &mut solid_mesh, voxel);
-                    check_face(chunk, world, local, Face::North, &mut solid_mesh, voxel);
-                    check_face(chunk, world, local, Face::South, &mut solid_mesh, voxel);
-                    check_face(chunk, world, local, Face::East, &mut solid_mesh, voxel);
-                    check_face(chunk, world, local, Face::West, &mut solid_mesh, voxel);
+                    check_face(chunk, local, Face::Top, &mut solid_mesh, voxel);
+                    check_face(chunk, local, Face::Bottom, &mut solid_mesh, voxel);
+                    check_face(chunk, local, Face::North, &mut solid_mesh, voxel);
+                    check_face(chunk, local, Face::South, &mut solid_mesh, voxel);
+                    check_face(chunk, local, Face::East, &mut solid_mesh, voxel);
+                    check_face(chunk, local, Face::West, &mut solid_mesh, voxel);
                 }
             }
         }
@@ -111,40 +417,127 @@ pub fn generate_chunk_mesh(
 
     ChunkMeshResult {
         solid: solid_mesh,
-        water: water_mesh,
+        water: generate_water_mesh(chunk),
     }
 }
 
-fn check_face(
-    chunk: &Chunk,
-    world: &VoxelWorld,
-    local: UVec3,
-    face: Face,
-    mesh_data: &mut MeshData,
+/// A visible solid face waiting to be merged with its neighbors in the same layer
+#[derive(Clone, Copy, PartialEq)]
+struct GreedyFace {
     voxel: VoxelType,
-) {
-    if is_face_visible(chunk, world, local, face) {
-        add_face_with_ao(mesh_data, chunk, world, local, face, voxel);
+    shading: FaceShading,
+}
+
+/// Blocky meshing that merges coplanar faces of the same voxel type, AO and light into larger quads.
+/// The atlas tile repeats across a merged quad, so it looks the same as one quad per face.
+pub fn generate_chunk_mesh_greedy(
+    chunk: &ChunkSnapshot,
+) -> ChunkMeshResult {
+    let mut solid_mesh = MeshData::new();
+
+    let size = chunk.size() as u32;
+    let cell_index = |local: UVec3| (local.x + local.y * size + local.z * size * size) as usize;
+
+    // Visible faces of every solid cell as bits in `Face::ALL` order, found in one pass so
+    // the face directions below only look at layers that have something to draw
+    let mut visible = vec![0u8; (size * size * size) as usize];
+    let mut layer_has_faces = vec![[false; 6]; size as usize];
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                let local = UVec3::new(x, y, z);
+                let voxel = chunk.get(local);
+                if voxel.is_solid() {
+                    for (bit, face) in Face::ALL.into_iter().enumerate() {
+                        if is_face_visible(chunk, local, face) {
+                            visible[cell_index(local)] |= 1 << bit;
+                            layer_has_faces[local[face.axes().0] as usize][bit] = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut mask: Vec<Option<GreedyFace>> = vec![None; (size * size) as usize];
+    let mask_index = |i: u32, j: u32| (i + j * size) as usize;
+
+    for (bit, face) in Face::ALL.into_iter().enumerate() {
+        let (normal_axis, u_axis, v_axis) = face.axes();
+        let cell = |layer: u32, i: u32, j: u32| {
+            let mut local = UVec3::ZERO;
+            local[normal_axis] = layer;
+            local[u_axis] = i;
+            local[v_axis] = j;
+            local
+        };
+
+        for layer in (0..size).filter(|&layer| layer_has_faces[layer as usize][bit]) {
+            for j in 0..size {
+                for i in 0..size {
+                    let local = cell(layer, i, j);
+                    mask[mask_index(i, j)] = (visible[cell_index(local)] & (1 << bit) != 0)
+                        .then(|| GreedyFace { voxel: chunk.get(local), shading: get_face_shading(chunk, local, face) });
+                }
+            }
+
+            // Grow each face along u, then the whole row along v, while the faces match
+            for j in 0..size {
+                let mut i = 0;
+                while i < size {
+                    let Some(current) = mask[mask_index(i, j)] else {
+                        i += 1;
+                        continue;
+                    };
+
+                    let mut width = 1;
+                    while i + width < size && mask[mask_index(i + width, j)] == Some(current) {
+                        width += 1;
+                    }
+                    let mut height = 1;
+                    while j + height < size
+                        && (0..width).all(|k| mask[mask_index(i + k, j + height)] == Some(current))
+                    {
+                        height += 1;
+                    }
+
+                    for dj in 0..height {
+                        for di in 0..width {
+                            mask[mask_index(i + di, j + dj)] = None;
+                        }
+                    }
+
+                    let mut span = UVec3::ONE;
+                    span[u_axis] = width;
+                    span[v_axis] = height;
+                    add_shaded_quad(&mut solid_mesh, chunk, cell(layer, i, j), face, current.voxel, current.shading, span);
+                    i += width;
+                }
+            }
+        }
+    }
+
+    ChunkMeshResult {
+        solid: solid_mesh,
+        water: generate_water_mesh(chunk),
     }
 }
 
-fn check_water_face(
-    chunk: &Chunk,
-    world: &VoxelWorld,
+fn check_face(
+    chunk: &ChunkSnapshot,
     local: UVec3,
     face: Face,
     mesh_data: &mut MeshData,
     voxel: VoxelType,
 ) {
-    if is_water_face_visible(chunk, world, local, face) {
-        // Water doesn't need AO - use full brightness
-        add_face_no_ao(mesh_data, local, face, voxel);
+    if is_face_visible(chunk, local, face) {
+        let shading = get_face_shading(chunk, local, face);
+        add_shaded_quad(mesh_data, chunk, local, face, voxel, shading, UVec3::ONE);
     }
 }
 
 fn is_face_visible(
-    chunk: &Chunk,
-    world: &VoxelWorld,
+    chunk: &ChunkSnapshot,
     local: UVec3,
     face: Face,
 ) -> bool {
@@ -162,20 +555,28 @@ fn is_face_visible(
     let neighbor_z = local.z as i32 + dz;
 
     // If neighbor is within chunk
-    if neighbor_x >= 0 && neighbor_x < 16 &&
-       neighbor_y >= 0 && neighbor_y < 16 &&
-       neighbor_z >= 0 && neighbor_z < 16 {
+    if neighbor_x >= 0 && neighbor_x < chunk.size() &&
+       neighbor_y >= 0 && neighbor_y < chunk.size() &&
+       neighbor_z >= 0 && neighbor_z < chunk.size() {
         let neighbor_voxel = chunk.get(UVec3::new(neighbor_x as u32, neighbor_y as u32, neighbor_z as u32));
         return neighbor_voxel.is_transparent(); // Visible if neighbor is transparent (air or water)
     }
 
     // If neighbor is outside chunk, check world
-    let chunk_pos = chunk.position();
-    let chunk_origin = VoxelWorld::chunk_to_world(chunk_pos);
+    let chunk_origin = chunk.origin();
     let current_world_pos = chunk_origin + IVec3::new(local.x as i32, local.y as i32, local.z as i32);
     let neighbor_world_pos = current_world_pos + IVec3::new(dx, dy, dz);
+
+    // Downsampled chunks do not line up with neighbors at another level of detail, so the
+    // border sides of surface cells are kept as a skirt over the gap
+    if chunk.scale() > 1
+        && dy == 0
+        && chunk.get_voxel(current_world_pos + IVec3::Y).is_some_and(|above| above.is_transparent())
+    {
+        return true;
+    }
     
-    if let Some(neighbor_voxel) = world.get_voxel(neighbor_world_pos) {
+    if let Some(neighbor_voxel) = chunk.get_voxel(neighbor_world_pos) {
         neighbor_voxel.is_transparent()
     } else {
         // Outside world bounds - never render faces into the void
@@ -183,48 +584,6 @@ fn is_face_visible(
     }
 }
 
-/// Water faces are only visible when adjacent to air (not other water)
-fn is_water_face_visible(
-    chunk: &Chunk,
-    world: &VoxelWorld,
-    local: UVec3,
-    face: Face,
-) -> bool {
-    let (dx, dy, dz) = match face {
-        Face::Top => (0, 1, 0),
-        Face::Bottom => (0, -1, 0),
-        Face::North => (0, 0, -1),
-        Face::South => (0, 0, 1),
-        Face::East => (1, 0, 0),
-        Face::West => (-1, 0, 0),
-    };
-
-    let neighbor_x = local.x as i32 + dx;
-    let neighbor_y = local.y as i32 + dy;
-    let neighbor_z = local.z as i32 + dz;
-
-    // If neighbor is within chunk
-    if neighbor_x >= 0 && neighbor_x < 16 &&
-       neighbor_y >= 0 && neighbor_y < 16 &&
-       neighbor_z >= 0 && neighbor_z < 16 {
-        let neighbor_voxel = chunk.get(UVec3::new(neighbor_x as u32, neighbor_y as u32, neighbor_z as u32));
-        return neighbor_voxel == VoxelType::Air; // Water only visible against air
-    }
-
-    // If neighbor is outside chunk, check world
-    let chunk_pos = chunk.position();
-    let chunk_origin = VoxelWorld::chunk_to_world(chunk_pos);
-    let current_world_pos = chunk_origin + IVec3::new(local.x as i32, local.y as i32, local.z as i32);
-    let neighbor_world_pos = current_world_pos + IVec3::new(dx, dy, dz);
-    
-    if let Some(neighbor_voxel) = world.get_voxel(neighbor_world_pos) {
-        neighbor_voxel == VoxelType::Air
-    } else {
-        // If outside world bounds, assume visible (edge of world)
-        true
-    }
-}
-
 /// Calculate vertex ambient occlusion (0-3 scale, 0 = fully occluded, 3 = not occluded)
 fn calculate_vertex_ao(side1: bool, side2: bool, corner: bool) -> f32 {
     let ao = if side1 && side2 {
@@ -237,35 +596,32 @@ fn calculate_vertex_ao(side1: bool, side2: bool, corner: bool) -> f32 {
 }
 
 /// Check if a world position contains a solid block (for AO calculation)
-fn is_solid_at_offset(chunk: &Chunk, world: &VoxelWorld, local: UVec3, offset: IVec3) -> bool {
+fn is_solid_at_offset(chunk: &ChunkSnapshot, local: UVec3, offset: IVec3) -> bool {
     let local_pos = IVec3::new(local.x as i32, local.y as i32, local.z as i32) + offset;
     
     // Check within chunk first
-    if local_pos.x >= 0 && local_pos.x < 16 &&
-       local_pos.y >= 0 && local_pos.y < 16 &&
-       local_pos.z >= 0 && local_pos.z < 16 {
+    if local_pos.x >= 0 && local_pos.x < chunk.size() &&
+       local_pos.y >= 0 && local_pos.y < chunk.size() &&
+       local_pos.z >= 0 && local_pos.z < chunk.size() {
         let v = chunk.get(UVec3::new(local_pos.x as u32, local_pos.y as u32, local_pos.z as u32));
         return v.is_solid();
     }
     
     // Check world
-    let chunk_pos = chunk.position();
-    let chunk_origin = VoxelWorld::chunk_to_world(chunk_pos);
+    let chunk_origin = chunk.origin();
     let world_pos = chunk_origin + local_pos;
     
-    if let Some(v) = world.get_voxel(world_pos) {
+    if let Some(v) = chunk.get_voxel(world_pos) {
         v.is_solid()
     } else {
         false
     }
 }
 
-/// Get AO values for the 4 vertices of a face
-fn get_face_ao(chunk: &Chunk, world: &VoxelWorld, local: UVec3, face: Face) -> [f32; 4] {
-    // For each face, we need to check the 8 neighbors in the plane of the face
-    // and calculate AO for each of the 4 vertices
-    
-    let offsets = match face {
+/// Offsets of the two side cells and the corner cell around each of the 4 vertices of a
+/// face, in the layer of cells in front of it
+fn face_vertex_offsets(face: Face) -> [(IVec3, IVec3, IVec3); 4] {
+    match face {
         Face::Top => {
             // Vertices: v0(0,1,1), v1(1,1,1), v2(1,1,0), v3(0,1,0)
             [
@@ -315,29 +671,67 @@ fn get_face_ao(chunk: &Chunk, world: &VoxelWorld, local: UVec3, face: Face) -> [
                 (IVec3::new(-1, 0, -1), IVec3::new(-1, 1, 0), IVec3::new(-1, 1, -1)),
             ]
         }
-    };
-    
+    }
+}
+
+/// Get AO values for the 4 vertices of a face
+fn get_face_ao(chunk: &ChunkSnapshot, local: UVec3, face: Face) -> [f32; 4] {
+    // For each face, we need to check the 8 neighbors in the plane of the face
+    // and calculate AO for each of the 4 vertices
+    let offsets = face_vertex_offsets(face);
+
     let mut ao = [1.0; 4];
     for (i, (side1_off, side2_off, corner_off)) in offsets.iter().enumerate() {
-        let side1 = is_solid_at_offset(chunk, world, local, *side1_off);
-        let side2 = is_solid_at_offset(chunk, world, local, *side2_off);
-        let corner = is_solid_at_offset(chunk, world, local, *corner_off);
+        let side1 = is_solid_at_offset(chunk, local, *side1_off);
+        let side2 = is_solid_at_offset(chunk, local, *side2_off);
+        let corner = is_solid_at_offset(chunk, local, *corner_off);
         ao[i] = calculate_vertex_ao(side1, side2, corner);
     }
     ao
 }
 
+/// Light color of the 4 vertices of a face, smoothed over the cells in front of it that
+/// share each vertex
+fn get_face_light(chunk: &ChunkSnapshot, local: UVec3, face: Face) -> [[f32; 3]; 4] {
+    let cell = chunk.origin() + local.as_ivec3();
+    let front = cell + face_normal(face);
+    face_vertex_offsets(face).map(|(side1, side2, corner)| {
+        let light = chunk
+            .average_light([front, cell + side1, cell + side2, cell + corner])
+            .unwrap_or(Vec2::ZERO);
+        lighting::light_color(light.x, light.y)
+    })
+}
+
+fn face_normal(face: Face) -> IVec3 {
+    match face {
+        Face::Top => IVec3::Y,
+        Face::Bottom => IVec3::NEG_Y,
+        Face::North => IVec3::NEG_Z,
+        Face::South => IVec3::Z,
+        Face::East => IVec3::X,
+        Face::West => IVec3::NEG_X,
+    }
+}
+
+/// Ambient occlusion and light of the 4 vertices of a blocky face
+#[derive(Clone, Copy, PartialEq)]
+struct FaceShading {
+    ao: [f32; 4],
+    light: [[f32; 3]; 4],
+}
+
+fn get_face_shading(chunk: &ChunkSnapshot, local: UVec3, face: Face) -> FaceShading {
+    FaceShading { ao: get_face_ao(chunk, local, face), light: get_face_light(chunk, local, face) }
+}
+
 /// Get the atlas index for a voxel face (supports face-specific textures)
 fn get_face_atlas_index(voxel: VoxelType, face: Face) -> u8 {
-    match voxel {
-        VoxelType::TopSoil => {
-            match face {
-                Face::Top => 0,    // Grass top texture
-                Face::Bottom => 1, // Dirt texture
-                _ => 7,            // Grass side texture (uses slot 7)
-            }
-        }
-        _ => voxel.atlas_index(),
+    let properties = registry::properties(voxel);
+    match face {
+        Face::Top => properties.atlas_top(),
+        Face::Bottom => properties.atlas_bottom(),
+        _ => properties.atlas_side(),
     }
 }
 
@@ -363,49 +757,50 @@ fn rotate_uvs(uvs: [[f32; 2]; 4], rotation: u8) -> [[f32; 2]; 4] {
     }
 }
 
-fn add_face_with_ao(
+/// Add a quad covering `span` cells starting at `local`, one cell deep along the face normal
+fn add_shaded_quad(
     mesh_data: &mut MeshData,
-    chunk: &Chunk,
-    world: &VoxelWorld,
+    chunk: &ChunkSnapshot,
     local: UVec3,
     face: Face,
     voxel: VoxelType,
+    shading: FaceShading,
+    span: UVec3,
 ) {
-    let x = local.x as f32 * VOXEL_SIZE;
-    let y = local.y as f32 * VOXEL_SIZE;
-    let z = local.z as f32 * VOXEL_SIZE;
-    let s = VOXEL_SIZE;
+    let FaceShading { ao, light } = shading;
+    let s = VOXEL_SIZE * chunk.scale() as f32;
+    let x = local.x as f32 * s;
+    let y = local.y as f32 * s;
+    let z = local.z as f32 * s;
+    let (sx, sy, sz) = (span.x as f32 * s, span.y as f32 * s, span.z as f32 * s);
 
     let (v0, v1, v2, v3, normal) = match face {
         Face::Top => (
-            [x, y + s, z + s], [x + s, y + s, z + s], [x + s, y + s, z], [x, y + s, z],
+            [x, y + sy, z + sz], [x + sx, y + sy, z + sz], [x + sx, y + sy, z], [x, y + sy, z],
             [0.0, 1.0, 0.0]
         ),
         Face::Bottom => (
-            [x, y, z], [x + s, y, z], [x + s, y, z + s], [x, y, z + s],
+            [x, y, z], [x + sx, y, z], [x + sx, y, z + sz], [x, y, z + sz],
             [0.0, -1.0, 0.0]
         ),
         Face::North => (
-            [x + s, y, z], [x, y, z], [x, y + s, z], [x + s, y + s, z],
+            [x + sx, y, z], [x, y, z], [x, y + sy, z], [x + sx, y + sy, z],
             [0.0, 0.0, -1.0]
         ),
         Face::South => (
-            [x, y, z + s], [x + s, y, z + s], [x + s, y + s, z + s], [x, y + s, z + s],
+            [x, y, z + sz], [x + sx, y, z + sz], [x + sx, y + sy, z + sz], [x, y + sy, z + sz],
             [0.0, 0.0, 1.0]
         ),
         Face::East => (
-            [x + s, y, z + s], [x + s, y, z], [x + s, y + s, z], [x + s, y + s, z + s],
+            [x + sx, y, z + sz], [x + sx, y, z], [x + sx, y + sy, z], [x + sx, y + sy, z + sz],
             [1.0, 0.0, 0.0]
         ),
         Face::West => (
-            [x, y, z], [x, y, z + s], [x, y + s, z + s], [x, y + s, z],
+            [x, y, z], [x, y, z + sz], [x, y + sy, z + sz], [x, y + sy, z],
             [-1.0, 0.0, 0.0]
         ),
     };
 
-    // Calculate AO for each vertex
-    let ao = get_face_ao(chunk, world, local, face);
-
     let start_idx = mesh_data.positions.len() as u32;
     
     mesh_data.positions.push(v0);
@@ -418,11 +813,10 @@ fn add_face_with_ao(
     mesh_data.normals.push(normal);
     mesh_data.normals.push(normal);
     
-    // Add vertex colors for AO (grayscale)
-    mesh_data.colors.push([ao[0], ao[0], ao[0], 1.0]);
-    mesh_data.colors.push([ao[1], ao[1], ao[1], 1.0]);
-    mesh_data.colors.push([ao[2], ao[2], ao[2], 1.0]);
-    mesh_data.colors.push([ao[3], ao[3], ao[3], 1.0]);
+    // Vertex colors carry AO times the voxel light
+    for (ao, [r, g, b]) in ao.into_iter().zip(light) {
+        mesh_data.colors.push([r * ao, g * ao, b * ao, 1.0]);
+    }
     
     // Face-specific texture
     let atlas_idx = get_face_atlas_index(voxel, face);
@@ -430,7 +824,7 @@ fn add_face_with_ao(
     if DEBUG_LOG_WATER_TILE_ON_SOLIDS && atlas_idx == VoxelType::Water.atlas_index() {
         let count = DEBUG_WATER_SOLID_LOGS.fetch_add(1, Ordering::Relaxed);
         if count < DEBUG_MAX_LOGS {
-            let chunk_origin = VoxelWorld::chunk_to_world(chunk.position());
+            let chunk_origin = chunk.origin();
             let world_pos = chunk_origin + IVec3::new(local.x as i32, local.y as i32, local.z as i32);
             info!(
                 "Solid face using water tile at {:?}, voxel {:?}, face {:?}",
@@ -438,24 +832,21 @@ fn add_face_with_ao(
             );
         }
     }
-    let cols = 4.0;
-    let rows = 4.0;
-    let col = (atlas_idx % 4) as f32;
-    let row = (atlas_idx / 4) as f32;
-    
-    // UV padding to prevent texture bleeding from adjacent tiles
-    let padding = 0.02;
-    
-    let u_min = col / cols + padding;
-    let u_max = (col + 1.0) / cols - padding;
-    let v_min = row / rows + padding;
-    let v_max = (row + 1.0) / rows - padding;
-    
-    mesh_data.uvs.push([u_min, v_max]);
-    mesh_data.uvs.push([u_max, v_max]);
-    mesh_data.uvs.push([u_max, v_min]);
-    mesh_data.uvs.push([u_min, v_min]);
-    
+    let tile = 1.0 / ATLAS_COLUMNS as f32;
+    let col = (atlas_idx as u32 % ATLAS_COLUMNS) as f32;
+    let row = (atlas_idx as u32 / ATLAS_COLUMNS) as f32;
+
+    // The shader wraps the UVs into the tile, padded to prevent bleeding from adjacent tiles
+    let tile_origin = [col * tile + ATLAS_UV_PADDING, row * tile + ATLAS_UV_PADDING];
+    let (_, u_axis, v_axis) = face.axes();
+    let (width, height) = (span[u_axis] as f32, span[v_axis] as f32);
+
+    mesh_data.uvs.push([0.0, height]);
+    mesh_data.uvs.push([width, height]);
+    mesh_data.uvs.push([width, 0.0]);
+    mesh_data.uvs.push([0.0, 0.0]);
+    mesh_data.tile_origins.extend([tile_origin; 4]);
+
     // Use flipped winding for proper AO interpolation when needed
     // Check if we should flip the quad diagonal based on AO values
     if ao[0] + ao[2] > ao[1] + ao[3] {
@@ -481,207 +872,63 @@ fn add_face_with_ao(
     }
 }
 
-fn add_face_no_ao(
-    mesh_data: &mut MeshData,
-    local: UVec3,
-    face: Face,
-    voxel: VoxelType,
-) {
-    let x = local.x as f32 * VOXEL_SIZE;
-    let y = local.y as f32 * VOXEL_SIZE;
-    let z = local.z as f32 * VOXEL_SIZE;
-    let s = VOXEL_SIZE;
-
-    // Inset water faces slightly to prevent them showing through terrain gaps
-    // The smooth terrain mesh may not perfectly align with blocky water mesh
-    let inset = 0.05;
-
-    let (v0, v1, v2, v3, normal) = match face {
-        Face::Top => (
-            [x + inset, y + s - inset, z + s - inset], [x + s - inset, y + s - inset, z + s - inset],
-            [x + s - inset, y + s - inset, z + inset], [x + inset, y + s - inset, z + inset],
-            [0.0, 1.0, 0.0]
-        ),
-        Face::Bottom => (
-            [x + inset, y + inset, z + inset], [x + s - inset, y + inset, z + inset],
-            [x + s - inset, y + inset, z + s - inset], [x + inset, y + inset, z + s - inset],
-            [0.0, -1.0, 0.0]
-        ),
-        Face::North => (
-            [x + s - inset, y + inset, z + inset], [x + inset, y + inset, z + inset],
-            [x + inset, y + s - inset, z + inset], [x + s - inset, y + s - inset, z + inset],
-            [0.0, 0.0, -1.0]
-        ),
-        Face::South => (
-            [x + inset, y + inset, z + s - inset], [x + s - inset, y + inset, z + s - inset],
-            [x + s - inset, y + s - inset, z + s - inset], [x + inset, y + s - inset, z + s - inset],
-            [0.0, 0.0, 1.0]
-        ),
-        Face::East => (
-            [x + s - inset, y + inset, z + s - inset], [x + s - inset, y + inset, z + inset],
-            [x + s - inset, y + s - inset, z + inset], [x + s - inset, y + s - inset, z + s - inset],
-            [1.0, 0.0, 0.0]
-        ),
-        Face::West => (
-            [x + inset, y + inset, z + inset], [x + inset, y + inset, z + s - inset],
-            [x + inset, y + s - inset, z + s - inset], [x + inset, y + s - inset, z + inset],
-            [-1.0, 0.0, 0.0]
-        ),
-    };
-
-    let start_idx = mesh_data.positions.len() as u32;
-    
-    mesh_data.positions.push(v0);
-    mesh_data.positions.push(v1);
-    mesh_data.positions.push(v2);
-    mesh_data.positions.push(v3);
-    
-    mesh_data.normals.push(normal);
-    mesh_data.normals.push(normal);
-    mesh_data.normals.push(normal);
-    mesh_data.normals.push(normal);
-    
-    // Full brightness for water
-    mesh_data.colors.push([1.0, 1.0, 1.0, 1.0]);
-    mesh_data.colors.push([1.0, 1.0, 1.0, 1.0]);
-    mesh_data.colors.push([1.0, 1.0, 1.0, 1.0]);
-    mesh_data.colors.push([1.0, 1.0, 1.0, 1.0]);
-    
-    let atlas_idx = voxel.atlas_index();
-    let cols = 4.0;
-    let rows = 4.0;
-    let col = (atlas_idx % 4) as f32;
-    let row = (atlas_idx / 4) as f32;
-    
-    // UV padding to prevent texture bleeding from adjacent tiles
-    let padding = 0.02;
-    
-    let u_min = col / cols + padding;
-    let u_max = (col + 1.0) / cols - padding;
-    let v_min = row / rows + padding;
-    let v_max = (row + 1.0) / rows - padding;
-    
-    mesh_data.uvs.push([u_min, v_max]);
-    mesh_data.uvs.push([u_max, v_max]);
-    mesh_data.uvs.push([u_max, v_min]);
-    mesh_data.uvs.push([u_min, v_min]);
-    
-    mesh_data.indices.push(start_idx);
-    mesh_data.indices.push(start_idx + 2);
-    mesh_data.indices.push(start_idx + 1);
-
-    mesh_data.indices.push(start_idx);
-    mesh_data.indices.push(start_idx + 3);
-    mesh_data.indices.push(start_idx + 2);
-}
-
 // =============================================================================
 // Surface Nets Smooth Meshing
 // =============================================================================
 
-/// Padded chunk shape for surface nets (18x18x18 for 16x16x16 chunk + 1 padding)
-type PaddedChunkShape = ConstShape3u32<18, 18, 18>;
-
-/// Sample voxel from world or chunk, returns true if solid OR water
-/// Water is treated as solid for SDF purposes to prevent surface nets from generating
-/// surfaces at solid-water boundaries (which would create visible seams with the blocky water mesh)
-fn sample_voxel_solid(chunk: &Chunk, world: &VoxelWorld, chunk_origin: IVec3, px: u32, py: u32, pz: u32) -> bool {
-    let world_pos = chunk_origin + IVec3::new(px as i32 - 1, py as i32 - 1, pz as i32 - 1);
+/// Padded grid shape for surface nets: the chunk's cells plus 1 padding on each side
+/// (18x18x18 at full detail)
+type PaddedChunkShape = RuntimeShape<u32, 3>;
 
-    let voxel = if px >= 1 && px <= 16 && py >= 1 && py <= 16 && pz >= 1 && pz <= 16 {
-        chunk.get(UVec3::new(px - 1, py - 1, pz - 1))
-    } else {
-        world.get_voxel(world_pos).unwrap_or(VoxelType::Air)
-    };
-
-    // Treat water as solid for SDF so we don't generate surfaces at solid-water boundaries
-    voxel.is_solid() || voxel.is_liquid()
-}
-
-/// Generate an SDF array from voxel data with 1-voxel padding for neighbor sampling
-/// Uses distance-based SDF for smoother surfaces at chunk boundaries
-fn generate_sdf(chunk: &Chunk, world: &VoxelWorld) -> [f32; 5832] { // 18^3 = 5832
-    let mut sdf = [1.0f32; PaddedChunkShape::USIZE];
-    let chunk_pos = chunk.position();
-    let chunk_origin = VoxelWorld::chunk_to_world(chunk_pos);
-
-    // First pass: set binary solid/air values
-    for i in 0..PaddedChunkShape::USIZE {
-        let [px, py, pz] = PaddedChunkShape::delinearize(i as u32);
-        let is_solid = sample_voxel_solid(chunk, world, chunk_origin, px, py, pz);
-        // SDF: negative inside solid, positive in air
-        sdf[i] = if is_solid { -1.0 } else { 1.0 };
-    }
-
-    // Second pass: smooth SDF values at boundaries by averaging with neighbors
-    // This creates smoother transitions and helps with chunk boundary alignment
-    let mut smoothed = sdf;
-    for i in 0..PaddedChunkShape::USIZE {
-        let [px, py, pz] = PaddedChunkShape::delinearize(i as u32);
-
-        // Only smooth interior cells (not at array edges)
-        if px > 0 && px < 17 && py > 0 && py < 17 && pz > 0 && pz < 17 {
-            let current = sdf[i];
-
-            // Check if this is a boundary cell (sign changes with any neighbor)
-            let neighbors = [
-                sdf[PaddedChunkShape::linearize([px - 1, py, pz]) as usize],
-                sdf[PaddedChunkShape::linearize([px + 1, py, pz]) as usize],
-                sdf[PaddedChunkShape::linearize([px, py - 1, pz]) as usize],
-                sdf[PaddedChunkShape::linearize([px, py + 1, pz]) as usize],
-                sdf[PaddedChunkShape::linearize([px, py, pz - 1]) as usize],
-                sdf[PaddedChunkShape::linearize([px, py, pz + 1]) as usize],
-            ];
-
-            let has_sign_change = neighbors.iter().any(|&n| (n > 0.0) != (current > 0.0));
+fn padded_shape(chunk: &ChunkSnapshot) -> PaddedChunkShape {
+    let padded = chunk.size() as u32 + 2;
+    RuntimeShape::<u32, 3>::new([padded; 3])
+}
 
-            if has_sign_change {
-                // At surface boundary, use a value between -0.5 and 0.5 for smoother interpolation
-                let neighbor_avg: f32 = neighbors.iter().sum::<f32>() / 6.0;
-                smoothed[i] = (current + neighbor_avg) * 0.5;
+/// Generate an SDF array over the chunk plus 1 cell of padding on each side.
+/// Every value is a function of the cells around its world position only, so neighboring
+/// chunks compute identical values where their grids overlap and their vertices meet exactly.
+fn generate_sdf(chunk: &ChunkSnapshot, shape: &PaddedChunkShape) -> Vec<f32> {
+    // Fill field one cell wider than the padded grid, so boundary smoothing of the padding
+    // cells sees the same neighbors as the chunk next door does. Water counts as empty: the
+    // water mesh draws its surface, and the terrain runs along the lake bed underneath.
+    let wide = chunk.size() + 4;
+    let wide_origin = chunk.origin() - IVec3::splat(2);
+    let mut field = Vec::with_capacity((wide * wide * wide) as usize);
+    for z in 0..wide {
+        for y in 0..wide {
+            for x in 0..wide {
+                // SDF: -1 inside full cells, 1 in empty ones, sculpted cells in between
+                let cell = wide_origin + IVec3::new(x, y, z);
+                let liquid = chunk.get_voxel(cell).is_some_and(|voxel| voxel.is_liquid());
+                let fill = if liquid { 0 } else { chunk.get_fill(cell) };
+                field.push(1.0 - 2.0 * fill as f32 / 255.0);
             }
         }
     }
-
-    smoothed
-}
-
-/// Sample the voxel type at a world position for texture lookup
-fn sample_voxel_for_texture(chunk: &Chunk, world: &VoxelWorld, local_pos: Vec3) -> VoxelType {
-    // Convert local position to world position for accurate sampling
-    // This handles positions outside the [0,15] range due to Surface Nets padding
-    let chunk_origin = VoxelWorld::chunk_to_world(chunk.position());
-    let world_pos = IVec3::new(
-        chunk_origin.x + local_pos.x.round() as i32,
-        chunk_origin.y + local_pos.y.round() as i32,
-        chunk_origin.z + local_pos.z.round() as i32,
-    );
-
-    // First try exact position
-    if let Some(voxel) = world.get_voxel(world_pos) {
-        if voxel.is_solid() {
-            return voxel;
-        }
+    let field_at = |p: IVec3| field[(p.x + p.y * wide + p.z * wide * wide) as usize];
+
+    // Smooth SDF values at boundaries by averaging with neighbors
+    // This creates smoother transitions for the surface vertices
+    let mut sdf = vec![1.0f32; shape.usize()];
+    for (i, value) in sdf.iter_mut().enumerate() {
+        let p = UVec3::from_array(shape.delinearize(i as u32)).as_ivec3() + IVec3::ONE;
+        let current = field_at(p);
+        let neighbors = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z]
+            .map(|offset| field_at(p + offset));
+
+        // Boundary cells (sign changes with a neighbor) are blended halfway toward their
+        // neighbors' average. Sculpted fills scale the blend by the largest step to a
+        // neighbor, so the surface moves continuously as a fill changes.
+        let boundary = neighbors
+            .iter()
+            .map(|&n| ((n - current).abs() * 0.5).min(1.0))
+            .fold(0.0f32, f32::max);
+        let neighbor_avg: f32 = neighbors.iter().sum::<f32>() / 6.0;
+        *value = current + (neighbor_avg - current) * boundary * 0.5;
     }
 
-    // If we hit air/water, search nearby for a solid voxel
-    // Prioritize searching upward first (more likely to find terrain surface)
-    for dy in [0i32, -1, 1, -2, 2] {
-        for dx in [-1i32, 0, 1] {
-            for dz in [-1i32, 0, 1] {
-                if dx == 0 && dy == 0 && dz == 0 {
-                    continue; // Already checked
-                }
-                let check_pos = world_pos + IVec3::new(dx, dy, dz);
-                if let Some(v) = world.get_voxel(check_pos) {
-                    if v.is_solid() {
-                        return v;
-                    }
-                }
-            }
-        }
-    }
-    VoxelType::TopSoil // Default fallback
+    sdf
 }
 
 /// Compute planar UV coordinates in world space that tile within an atlas tile
@@ -733,34 +980,178 @@ fn compute_triplanar_uv(world_pos: Vec3, normal: [f32; 3], atlas_idx: u8) -> [f3
     ]
 }
 
+/// Splat material weights of a surface nets vertex from the 8 cells around it
+fn vertex_splat_weights(chunk: &ChunkSnapshot, local_pos: Vec3) -> [f32; 4] {
+    let mut weights = [0.0f32; 4];
+    let mut total_weight = 0.0;
+    
+    // Check 8 neighbors of the cell containing the vertex
+    let base_x = local_pos.x.floor() as i32;
+    let base_y = local_pos.y.floor() as i32;
+    let base_z = local_pos.z.floor() as i32;
+    
+    let chunk_origin = chunk.origin();
+
+    for dz in 0..2 {
+        for dy in 0..2 {
+            for dx in 0..2 {
+                let lx = base_x + dx;
+                let ly = base_y + dy;
+                let lz = base_z + dz;
+                
+                let voxel = if lx >= 0 && lx < chunk.size() && ly >= 0 && ly < chunk.size() && lz >= 0 && lz < chunk.size() {
+                    chunk.get(UVec3::new(lx as u32, ly as u32, lz as u32))
+                } else {
+                    let wx = chunk_origin.x + lx;
+                    let wy = chunk_origin.y + ly;
+                    let wz = chunk_origin.z + lz;
+                    chunk.get_voxel(IVec3::new(wx, wy, wz)).unwrap_or(VoxelType::Air)
+                };
+
+                // Splat slot from the registry: 0 grass, 1 rock, 2 sand, 3 dirt
+                if let Some(mat_idx) = voxel.splat_material() {
+                    let mat_idx = mat_idx as usize;
+
+                    // Distance-based weighting (closer voxels have more influence)
+                    // This assumes local_pos is within the cell [base, base+1]
+                    // let dist_sq = (lx as f32 - local_pos.x).powi(2) + 
+                    //               (ly as f32 - local_pos.y).powi(2) + 
+                    //               (lz as f32 - local_pos.z).powi(2);
+                    // let weight = 1.0 / (dist_sq + 0.001);
+                    
+                    // Simple binary presence also works well for Surface Nets
+                    let weight = 1.0;
+                    
+                    weights[mat_idx] += weight;
+                    total_weight += weight;
+                }
+            }
+        }
+    }
+    
+    if total_weight > 0.0 {
+        [
+            weights[0] / total_weight,
+            weights[1] / total_weight,
+            weights[2] / total_weight,
+            weights[3] / total_weight,
+        ]
+    } else {
+        // Default to dirt if isolated (shouldn't happen for valid mesh)
+        [0.0, 0.0, 0.0, 1.0] 
+    }
+}
+
+/// Ore overlay of a surface nets vertex: the share of the solid cells around it whose voxel
+/// draws its atlas tile over the splat materials, and that tile
+fn vertex_overlay(chunk: &ChunkSnapshot, local_pos: Vec3) -> (f32, Option<u8>) {
+    let base = chunk.origin() + local_pos.floor().as_ivec3();
+    let mut tile = None;
+    let (mut overlaid, mut splatted) = (0, 0);
+    for i in 0..8 {
+        let voxel = chunk.get_voxel(base + IVec3::new(i & 1, (i >> 1) & 1, i >> 2)).unwrap_or(VoxelType::Air);
+        if voxel.splat_material().is_some() {
+            splatted += 1;
+        }
+        if voxel.splat_overlay() {
+            tile.get_or_insert(voxel.atlas_index());
+            overlaid += 1;
+        }
+    }
+    (overlaid as f32 / splatted.max(1) as f32, tile)
+}
+
+/// UV_1 of the vertices of one surface nets polygon. The tile is the same for all of them,
+/// so it does not interpolate between tiles; only the weight fades out.
+fn polygon_overlays<const N: usize>(overlays: [(f32, Option<u8>); N]) -> [[f32; 2]; N] {
+    let tile = overlays
+        .iter()
+        .filter(|(_, tile)| tile.is_some())
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .and_then(|&(_, tile)| tile);
+    overlays.map(|(weight, _)| match tile {
+        Some(tile) => [weight, tile as f32],
+        None => [0.0, 0.0],
+    })
+}
+
+/// Sky and block light of a surface nets vertex from the 8 cells around it, as fractions of
+/// full light. The triplanar shader reads them from UV_0, as the vertex colors hold the
+/// splat weights.
+fn vertex_light(chunk: &ChunkSnapshot, local_pos: Vec3) -> [f32; 2] {
+    let base = chunk.origin() + local_pos.floor().as_ivec3();
+    let cells = (0..8).map(|i| base + IVec3::new(i & 1, (i >> 1) & 1, i >> 2));
+    let light = chunk.average_light(cells).unwrap_or(Vec2::ZERO);
+    (light / lighting::MAX_LIGHT as f32).to_array()
+}
+
+/// Vertical strips one cell deep below every open edge of a surface nets mesh. Open edges
+/// only occur at the chunk border, where a neighbor at another level of detail may not line up.
+fn add_surface_skirts(
+    mesh: &mut MeshData,
+    chunk: &ChunkSnapshot,
+    buffer: &SurfaceNetsBuffer,
+    to_mesh_pos: impl Fn(Vec3) -> [f32; 3],
+    depth: f32,
+) {
+    let mut edge_uses: HashMap<(u32, u32), u32> = HashMap::new();
+    for triangle in buffer.indices.chunks_exact(3) {
+        for (a, b) in [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])] {
+            *edge_uses.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+
+    let local = |i: u32| Vec3::from_array(buffer.positions[i as usize]) - Vec3::ONE;
+    let normal = |i: u32| Vec3::from_array(buffer.normals[i as usize]).normalize_or(Vec3::Y).to_array();
+    for (&(a, b), &uses) in &edge_uses {
+        let (local_a, local_b) = (local(a), local(b));
+        if uses != 1 || !local_a.is_finite() || !local_b.is_finite() {
+            continue;
+        }
+
+        let top = [to_mesh_pos(local_a), to_mesh_pos(local_b)];
+        let bottom = top.map(|[x, y, z]| [x, y - depth, z]);
+        let weights = [vertex_splat_weights(chunk, local_a), vertex_splat_weights(chunk, local_b)];
+        let lights = [vertex_light(chunk, local_a), vertex_light(chunk, local_b)];
+        let overlays = polygon_overlays([vertex_overlay(chunk, local_a), vertex_overlay(chunk, local_b)]);
+        let normals = [normal(a), normal(b)];
+
+        let base = mesh.positions.len() as u32;
+        mesh.positions.extend([top[0], top[1], bottom[1], bottom[0]]);
+        mesh.normals.extend([normals[0], normals[1], normals[1], normals[0]]);
+        mesh.uvs.extend([lights[0], lights[1], lights[1], lights[0]]);
+        mesh.colors.extend([weights[0], weights[1], weights[1], weights[0]]);
+        mesh.overlays.extend([overlays[0], overlays[1], overlays[1], overlays[0]]);
+        // Which side of the edge faces outwards is unknown, so the strip is two-sided
+        mesh.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        mesh.indices.extend([base, base + 2, base + 1, base, base + 3, base + 2]);
+    }
+}
+
 /// Generate mesh using Surface Nets algorithm for smooth terrain
 pub fn generate_chunk_mesh_surface_nets(
-    chunk: &Chunk,
-    world: &VoxelWorld,
+    chunk: &ChunkSnapshot,
 ) -> ChunkMeshResult {
     let mut solid_mesh = MeshData::new();
-    let mut water_mesh = MeshData::new();
-    let chunk_origin = VoxelWorld::chunk_to_world(chunk.position());
 
-    // Scale factor to slightly enlarge chunks so they overlap at boundaries
-    // This prevents gaps (sky showing through) at chunk seams caused by
-    // independent SDF smoothing per chunk producing slightly different vertex positions
-    const CHUNK_SCALE: f32 = 1.01; // 1.0% larger overlap
-    let chunk_center = Vec3::new(8.0, 8.0, 8.0) * VOXEL_SIZE; // Center of 16x16x16 chunk
+    // Lower levels of detail have larger cells
+    let cell_size = VOXEL_SIZE * chunk.scale() as f32;
+    let to_mesh_pos = |local: Vec3| -> [f32; 3] { (local * cell_size).to_array() };
 
     // Generate SDF from voxel data
-    let sdf = generate_sdf(chunk, world);
+    let shape = padded_shape(chunk);
+    let sdf = generate_sdf(chunk, &shape);
 
     // Run surface nets on the SDF
-    // Extract the full padded region [0,0,0] to [17,17,17)
-    // Including the padding lets the mesh extend half a voxel past each edge,
-    // so neighboring chunks meet without leaving a one-voxel gap.
+    // Extract the full padded region [0,0,0] to [17,17,17] at full detail
+    // Surface nets only makes quads for the edges this chunk owns, so together with the
+    // shared SDF values neighboring chunks tile without gaps or overlap.
     let mut buffer = SurfaceNetsBuffer::default();
     surface_nets(
         &sdf,
-        &PaddedChunkShape {},
+        &shape,
         [0; 3],  // Start at 0 (include negative padding)
-        [17; 3], // End at 17 (include positive padding)
+        [chunk.size() as u32 + 1; 3], // End past the last cell (include positive padding)
         &mut buffer,
     );
 
@@ -832,110 +1223,38 @@ pub fn generate_chunk_mesh_surface_nets(
                 [0.0, 1.0, 0.0]
             };
 
-            // Calculate material weights for each vertex
-            let compute_vertex_weights = |local_pos: Vec3| -> [f32; 4] {
-                let mut weights = [0.0f32; 4];
-                let mut total_weight = 0.0;
-                
-                // Check 8 neighbors of the cell containing the vertex
-                let base_x = local_pos.x.floor() as i32;
-                let base_y = local_pos.y.floor() as i32;
-                let base_z = local_pos.z.floor() as i32;
-                
-                let chunk_pos = chunk.position();
-                let chunk_origin = VoxelWorld::chunk_to_world(chunk_pos);
-
-                for dz in 0..2 {
-                    for dy in 0..2 {
-                        for dx in 0..2 {
-                            let lx = base_x + dx;
-                            let ly = base_y + dy;
-                            let lz = base_z + dz;
-                            
-                            let voxel = if lx >= 0 && lx < 16 && ly >= 0 && ly < 16 && lz >= 0 && lz < 16 {
-                                chunk.get(UVec3::new(lx as u32, ly as u32, lz as u32))
-                            } else {
-                                let wx = chunk_origin.x + lx;
-                                let wy = chunk_origin.y + ly;
-                                let wz = chunk_origin.z + lz;
-                                world.get_voxel(IVec3::new(wx, wy, wz)).unwrap_or(VoxelType::Air)
-                            };
-
-                            if voxel != VoxelType::Air && voxel != VoxelType::Water {
-                                let mat_idx = match voxel {
-                                    VoxelType::TopSoil => 0, // Grass
-                                    
-                                    VoxelType::Rock | VoxelType::Bedrock | 
-                                    VoxelType::DungeonWall | VoxelType::DungeonFloor => 1, // Rock
-                                    
-                                    VoxelType::Sand => 2, // Sand
-                                    
-                                    // Everything else maps to Dirt
-                                    VoxelType::SubSoil | VoxelType::Clay | 
-                                    VoxelType::Wood | VoxelType::Leaves | _ => 3, 
-                                };
-                                
-                                // Distance-based weighting (closer voxels have more influence)
-                                // This assumes local_pos is within the cell [base, base+1]
-                                // let dist_sq = (lx as f32 - local_pos.x).powi(2) + 
-                                //               (ly as f32 - local_pos.y).powi(2) + 
-                                //               (lz as f32 - local_pos.z).powi(2);
-                                // let weight = 1.0 / (dist_sq + 0.001);
-                                
-                                // Simple binary presence also works well for Surface Nets
-                                let weight = 1.0;
-                                
-                                weights[mat_idx] += weight;
-                                total_weight += weight;
-                            }
-                        }
-                    }
-                }
-                
-                if total_weight > 0.0 {
-                    [
-                        weights[0] / total_weight,
-                        weights[1] / total_weight,
-                        weights[2] / total_weight,
-                        weights[3] / total_weight,
-                    ]
-                } else {
-                    // Default to dirt if isolated (shouldn't happen for valid mesh)
-                    [0.0, 0.0, 0.0, 1.0] 
-                }
-            };
-
-            let weights0 = compute_vertex_weights(local0);
-            let weights1 = compute_vertex_weights(local1);
-            let weights2 = compute_vertex_weights(local2);
+            let weights0 = vertex_splat_weights(chunk, local0);
+            let weights1 = vertex_splat_weights(chunk, local1);
+            let weights2 = vertex_splat_weights(chunk, local2);
+            let overlays = polygon_overlays([
+                vertex_overlay(chunk, local0),
+                vertex_overlay(chunk, local1),
+                vertex_overlay(chunk, local2),
+            ]);
 
             // Add all 3 vertices for this triangle (not shared)
             let base_idx = solid_mesh.positions.len() as u32;
 
-            // Helper to scale vertex position outward from chunk center to close seams
-            let scale_vertex = |local: Vec3| -> [f32; 3] {
-                let pos = Vec3::new(local.x * VOXEL_SIZE, local.y * VOXEL_SIZE, local.z * VOXEL_SIZE);
-                let scaled = chunk_center + (pos - chunk_center) * CHUNK_SCALE;
-                [scaled.x, scaled.y, scaled.z]
-            };
-
             // Vertex 0
-            solid_mesh.positions.push(scale_vertex(local0));
+            solid_mesh.positions.push(to_mesh_pos(local0));
             solid_mesh.normals.push(normal0);
-            solid_mesh.uvs.push([0.0, 0.0]); // UVs not used for splatting logic
+            solid_mesh.uvs.push(vertex_light(chunk, local0)); // Light, the texture comes from world space
             solid_mesh.colors.push(weights0);
+            solid_mesh.overlays.push(overlays[0]);
 
             // Vertex 1
-            solid_mesh.positions.push(scale_vertex(local1));
+            solid_mesh.positions.push(to_mesh_pos(local1));
             solid_mesh.normals.push(normal1);
-            solid_mesh.uvs.push([0.0, 0.0]);
+            solid_mesh.uvs.push(vertex_light(chunk, local1));
             solid_mesh.colors.push(weights1);
+            solid_mesh.overlays.push(overlays[1]);
 
             // Vertex 2
-            solid_mesh.positions.push(scale_vertex(local2));
+            solid_mesh.positions.push(to_mesh_pos(local2));
             solid_mesh.normals.push(normal2);
-            solid_mesh.uvs.push([0.0, 0.0]);
+            solid_mesh.uvs.push(vertex_light(chunk, local2));
             solid_mesh.colors.push(weights2);
+            solid_mesh.overlays.push(overlays[2]);
 
             // Add triangle indices (sequential since vertices are not shared)
             solid_mesh.indices.push(base_idx);
@@ -944,33 +1263,21 @@ pub fn generate_chunk_mesh_surface_nets(
         }
     }
 
-    // Water still uses blocky meshing for now
-    for x in 0..16 {
-        for y in 0..16 {
-            for z in 0..16 {
-                let local = UVec3::new(x, y, z);
-                let voxel = chunk.get(local);
-
-                if voxel.is_liquid() {
-                    check_water_face(chunk, world, local, Face::Top, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::Bottom, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::North, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::South, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::East, &mut water_mesh, voxel);
-                    check_water_face(chunk, world, local, Face::West, &mut water_mesh, voxel);
-                }
-            }
-        }
+    // Lower levels of detail hang skirts from the open edges of the surface to hide cracks
+    // against neighbors meshed at another level
+    if chunk.scale() > 1 {
+        add_surface_skirts(&mut solid_mesh, chunk, &buffer, to_mesh_pos, cell_size);
     }
 
     ChunkMeshResult {
         solid: solid_mesh,
-        water: water_mesh,
+        water: generate_water_mesh(chunk),
     }
 }
 
 /// Mesh generation mode
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MeshMode {
     /// Traditional blocky voxel meshing (Minecraft-style)
     #[default]
@@ -993,15 +1300,16 @@ impl Default for MeshSettings {
     }
 }
 
-/// Generate chunk mesh using the specified mode
+/// Generate chunk mesh using the specified mode; `greedy` merges blocky faces
 pub fn generate_chunk_mesh_with_mode(
-    chunk: &Chunk,
-    world: &VoxelWorld,
+    chunk: &ChunkSnapshot,
     mode: MeshMode,
+    greedy: bool,
 ) -> ChunkMeshResult {
     match mode {
-        MeshMode::Blocky => generate_chunk_mesh(chunk, world),
-        MeshMode::SurfaceNets => generate_chunk_mesh_surface_nets(chunk, world),
+        MeshMode::Blocky if greedy => generate_chunk_mesh_greedy(chunk),
+        MeshMode::Blocky => generate_chunk_mesh(chunk),
+        MeshMode::SurfaceNets => generate_chunk_mesh_surface_nets(chunk),
     }
 }
 
@@ -1,6 +1,14 @@
 pub mod types;
+pub mod registry;
+pub mod palette;
 pub mod chunk;
 pub mod world;
+pub mod generation;
 pub mod meshing;
+pub mod water_mesh;
+pub mod fluid;
+pub mod lighting;
+pub mod mesh_tasks;
 pub mod persistence;
+pub mod streaming;
 pub mod plugin;
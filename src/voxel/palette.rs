@@ -0,0 +1,219 @@
+use crate::constants::CHUNK_VOLUME;
+use crate::voxel::types::VoxelType;
+use serde::{Serialize, Deserialize};
+
+/// Palette-compressed voxel storage for one chunk.
+///
+/// Homogeneous chunks store only their single value. Otherwise every voxel is an index
+/// into the palette, bit-packed into u64 words (indices never straddle a word boundary).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PalettedStorage {
+    palette: Vec<VoxelType>,
+    /// Bits per index: 0 (single value), 1, 2, 4 or 8
+    bits: u8,
+    words: Vec<u64>,
+    /// Number of voxels using each palette entry, rebuilt after deserializing
+    #[serde(skip)]
+    counts: Vec<u32>,
+}
+
+impl PalettedStorage {
+    /// Storage where every voxel has the same value
+    pub fn filled(voxel: VoxelType) -> Self {
+        Self {
+            palette: vec![voxel],
+            bits: 0,
+            words: Vec::new(),
+            counts: vec![CHUNK_VOLUME as u32],
+        }
+    }
+
+    /// Build compact storage from voxels in index order (missing voxels are air)
+    pub fn from_voxels(voxels: impl IntoIterator<Item = VoxelType>) -> Self {
+        let mut palette: Vec<VoxelType> = Vec::new();
+        let mut counts: Vec<u32> = Vec::new();
+        let mut indices = Vec::with_capacity(CHUNK_VOLUME);
+
+        let padded = voxels.into_iter().chain(std::iter::repeat(VoxelType::Air));
+        for voxel in padded.take(CHUNK_VOLUME) {
+            let slot = match palette.iter().position(|v| *v == voxel) {
+                Some(slot) => slot,
+                None => {
+                    palette.push(voxel);
+                    counts.push(0);
+                    palette.len() - 1
+                }
+            };
+            counts[slot] += 1;
+            indices.push(slot as u8);
+        }
+
+        if palette.len() == 1 {
+            return Self::filled(palette[0]);
+        }
+
+        let bits = bits_for(palette.len());
+        let mut storage = Self {
+            palette,
+            bits,
+            words: vec![0; word_count(bits)],
+            counts,
+        };
+        for (index, slot) in indices.into_iter().enumerate() {
+            storage.write_slot(index, slot as usize);
+        }
+        storage
+    }
+
+    pub fn get(&self, index: usize) -> VoxelType {
+        self.palette[self.read_slot(index)]
+    }
+
+    /// Set a voxel, returning true if the value changed
+    pub fn set(&mut self, index: usize, voxel: VoxelType) -> bool {
+        let old_slot = self.read_slot(index);
+        if self.palette[old_slot] == voxel {
+            return false;
+        }
+
+        let new_slot = match self.palette.iter().position(|v| *v == voxel) {
+            Some(slot) => slot,
+            None => self.add_entry(voxel),
+        };
+
+        self.write_slot(index, new_slot);
+        self.counts[old_slot] -= 1;
+        self.counts[new_slot] += 1;
+
+        // Collapse back to the single-value fast path once one type fills the chunk
+        if self.counts[new_slot] == CHUNK_VOLUME as u32 {
+            *self = Self::filled(voxel);
+        }
+        true
+    }
+
+    /// Replace every voxel value through `map` (e.g. when saved ids are renumbered)
+    pub fn remap(&mut self, map: impl Fn(VoxelType) -> VoxelType) {
+        for voxel in &mut self.palette {
+            *voxel = map(*voxel);
+        }
+        // Two entries may now hold the same value
+        *self = self.compacted();
+    }
+
+    /// Value of a homogeneous chunk
+    pub fn single_value(&self) -> Option<VoxelType> {
+        (self.bits == 0).then(|| self.palette[0])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = VoxelType> + '_ {
+        (0..CHUNK_VOLUME).map(|index| self.get(index))
+    }
+
+    /// Copy without unused palette entries, at the smallest index width
+    pub fn compacted(&self) -> Self {
+        Self::from_voxels(self.iter())
+    }
+
+    /// Number of palette entries still in use
+    pub fn palette_len(&self) -> usize {
+        self.counts.iter().filter(|count| **count > 0).count()
+    }
+
+    pub fn bits_per_voxel(&self) -> u8 {
+        self.bits
+    }
+
+    /// Approximate heap + inline size in bytes
+    pub fn memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.palette.capacity() * std::mem::size_of::<VoxelType>()
+            + self.words.capacity() * std::mem::size_of::<u64>()
+            + self.counts.capacity() * std::mem::size_of::<u32>()
+    }
+
+    /// Rebuild the per-entry counts after deserializing. Returns false if the data is
+    /// inconsistent (wrong word count or indices outside the palette).
+    pub fn rebuild_counts(&mut self) -> bool {
+        let valid_bits = matches!(self.bits, 0 | 1 | 2 | 4 | 8);
+        if self.palette.is_empty() || !valid_bits || self.words.len() != word_count(self.bits) {
+            return false;
+        }
+
+        let mut counts = vec![0u32; self.palette.len()];
+        for index in 0..CHUNK_VOLUME {
+            let slot = self.read_slot(index);
+            if slot >= counts.len() {
+                return false;
+            }
+            counts[slot] += 1;
+        }
+        self.counts = counts;
+        true
+    }
+
+    /// Find a free slot for a new palette entry, widening the indices if needed
+    fn add_entry(&mut self, voxel: VoxelType) -> usize {
+        if let Some(slot) = self.counts.iter().position(|count| *count == 0) {
+            self.palette[slot] = voxel;
+            return slot;
+        }
+
+        self.palette.push(voxel);
+        self.counts.push(0);
+
+        let needed = bits_for(self.palette.len());
+        if needed > self.bits {
+            self.repack(needed);
+        }
+        self.palette.len() - 1
+    }
+
+    fn repack(&mut self, bits: u8) {
+        let slots: Vec<usize> = (0..CHUNK_VOLUME).map(|index| self.read_slot(index)).collect();
+        self.bits = bits;
+        self.words = vec![0; word_count(bits)];
+        for (index, slot) in slots.into_iter().enumerate() {
+            self.write_slot(index, slot);
+        }
+    }
+
+    fn read_slot(&self, index: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let per_word = 64 / self.bits as usize;
+        let shift = (index % per_word) * self.bits as usize;
+        let mask = (1u64 << self.bits) - 1;
+        ((self.words[index / per_word] >> shift) & mask) as usize
+    }
+
+    fn write_slot(&mut self, index: usize, slot: usize) {
+        let per_word = 64 / self.bits as usize;
+        let shift = (index % per_word) * self.bits as usize;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let word = &mut self.words[index / per_word];
+        *word = (*word & !mask) | ((slot as u64) << shift);
+    }
+}
+
+impl Default for PalettedStorage {
+    fn default() -> Self {
+        Self::filled(VoxelType::Air)
+    }
+}
+
+/// Smallest supported index width for a palette of this size
+fn bits_for(palette_len: usize) -> u8 {
+    match palette_len {
+        0..=1 => 0,
+        2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        _ => 8,
+    }
+}
+
+fn word_count(bits: u8) -> usize {
+    if bits == 0 { 0 } else { CHUNK_VOLUME.div_ceil(64 / bits as usize) }
+}
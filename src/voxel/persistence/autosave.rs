@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy::tasks::{block_on, futures::check_ready, IoTaskPool, Task};
+use crate::voxel::persistence::{self, PersistenceError, SaveHeader, WorldPersistence, WorldStorage};
+use crate::voxel::persistence::slots::{ActiveWorld, WorldMetadata};
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::world::{VoxelWorld, WorldSeed};
+
+/// Key that saves edited chunks immediately
+pub const SAVE_KEY: KeyCode = KeyCode::F5;
+
+/// Result of a finished save
+#[derive(Clone, Debug)]
+pub enum SaveOutcome {
+    Saved { chunks: usize },
+    Failed(String),
+}
+
+/// Autosave timer, the in-flight background save and the last outcome for the overlay
+#[derive(Resource)]
+pub struct SaveState {
+    timer: Timer,
+    task: Option<Task<Result<usize, PersistenceError>>>,
+    /// Save on the next autosave check instead of waiting for the timer
+    requested: bool,
+    /// Elapsed time when the last save finished, with its outcome
+    pub last: Option<(f32, SaveOutcome)>,
+}
+
+impl SaveState {
+    pub fn new(interval_secs: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(interval_secs.max(0.0), TimerMode::Repeating),
+            task: None,
+            requested: false,
+            last: None,
+        }
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Save in the background as soon as the previous save is done
+    pub fn request_save(&mut self) {
+        self.requested = true;
+    }
+}
+
+/// Snapshot edited chunks and the slot metadata and write them on the IO task pool, along
+/// with unloaded chunks still queued in `WorldStorage`. The metadata (player position, play
+/// time) is saved even when no chunk changed.
+fn start_save(
+    world: &mut VoxelWorld,
+    header: SaveHeader,
+    metadata: WorldMetadata,
+    storage: &WorldStorage,
+    state: &mut SaveState,
+) -> bool {
+    // Edits made meanwhile stay flagged and go out with the next save
+    if state.task.is_some() {
+        return false;
+    }
+
+    // Queued snapshots are read instead of the disk until written, so a chunk unloaded and
+    // loaded again meanwhile keeps its edits
+    storage.queue_chunks(world.take_unsaved_chunks());
+    let storage = storage.clone();
+    state.task = Some(IoTaskPool::get().spawn(async move {
+        storage.write_pending(|store, chunks| persistence::save_snapshot(store, &header, &metadata, chunks))
+    }));
+    true
+}
+
+/// A failed save leaves its chunks queued for the next one
+fn finish_save(state: &mut SaveState, result: Result<usize, PersistenceError>, now: f32) {
+    let outcome = match result {
+        Ok(chunks) => {
+            info!("Saved {} edited chunks", chunks);
+            SaveOutcome::Saved { chunks }
+        }
+        Err(e) => {
+            warn!("Failed to save world: {}", e);
+            SaveOutcome::Failed(e.to_string())
+        }
+    };
+    state.last = Some((now, outcome));
+}
+
+/// Periodically save edited chunks in the background, or right away when requested
+#[allow(clippy::too_many_arguments)]
+pub fn autosave_system(
+    time: Res<Time>,
+    settings: Res<WorldPersistence>,
+    storage: Res<WorldStorage>,
+    registry: Res<VoxelRegistry>,
+    seed: Res<WorldSeed>,
+    active: Res<ActiveWorld>,
+    mut world: ResMut<VoxelWorld>,
+    mut state: ResMut<SaveState>,
+) {
+    let due = settings.autosave_interval_secs > 0.0 && state.timer.tick(time.delta()).just_finished();
+    if !due && !state.requested {
+        return;
+    }
+
+    let header = SaveHeader::new(world.to_data(), seed.0, active.generator_version, &registry);
+    if start_save(&mut world, header, active.snapshot(), &storage, &mut state) {
+        state.requested = false;
+    }
+}
+
+/// Save edited chunks and the player state right away on the save key
+pub fn manual_save_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    storage: Res<WorldStorage>,
+    registry: Res<VoxelRegistry>,
+    seed: Res<WorldSeed>,
+    active: Res<ActiveWorld>,
+    mut world: ResMut<VoxelWorld>,
+    mut state: ResMut<SaveState>,
+) {
+    if !keyboard.just_pressed(SAVE_KEY) {
+        return;
+    }
+
+    let header = SaveHeader::new(world.to_data(), seed.0, active.generator_version, &registry);
+    if start_save(&mut world, header, active.snapshot(), &storage, &mut state) {
+        info!("Saving world...");
+        // Restart the autosave countdown after a manual save
+        state.timer.reset();
+    } else {
+        info!("Save already in progress");
+    }
+}
+
+/// Collect finished background saves
+pub fn poll_save_task(time: Res<Time>, mut state: ResMut<SaveState>) {
+    let Some(task) = state.task.as_mut() else {
+        return;
+    };
+    if let Some(result) = check_ready(task) {
+        state.task = None;
+        finish_save(&mut state, result, time.elapsed_secs());
+    }
+}
+
+/// Flush all remaining edits, including unloaded chunks, before the app closes
+#[allow(clippy::too_many_arguments)]
+pub fn save_on_exit(
+    mut exit: MessageReader<AppExit>,
+    time: Res<Time>,
+    storage: Res<WorldStorage>,
+    registry: Res<VoxelRegistry>,
+    seed: Res<WorldSeed>,
+    active: Res<ActiveWorld>,
+    mut world: ResMut<VoxelWorld>,
+    mut state: ResMut<SaveState>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+
+    if let Some(task) = state.task.take() {
+        let result = block_on(task);
+        finish_save(&mut state, result, time.elapsed_secs());
+    }
+
+    storage.queue_chunks(world.take_unsaved_chunks());
+    info!("Saving edited chunks before exit...");
+    let header = SaveHeader::new(world.to_data(), seed.0, active.generator_version, &registry);
+    let metadata = active.snapshot();
+    let result = storage.write_pending(|store, chunks| persistence::save_snapshot(store, &header, &metadata, chunks));
+    if let Err(e) = result {
+        error!("Failed to save world on exit: {}", e);
+    }
+}
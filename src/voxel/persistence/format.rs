@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::voxel::chunk::ChunkData;
+use crate::voxel::palette::PalettedStorage;
+use crate::voxel::persistence::{PersistenceError, RegionStore, WorldData};
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::types::{VoxelType, BUILTIN_VOXEL_NAMES};
+
+/// First bytes of every world header file
+pub const SAVE_MAGIC: [u8; 8] = *b"VOXWORLD";
+
+/// Save format history:
+/// 0 - single `world_data.bin` with every chunk as a raw voxel array
+/// 1 - region files plus a headerless `world.bin` holding only the world size
+/// 2 - `world.bin` starts with this header
+/// 3 - chunks carry an optional density channel
+pub const SAVE_FORMAT_VERSION: u32 = 3;
+
+/// Bumped whenever terrain generation changes, since saves only hold edited/generated
+/// chunks and the rest is regenerated on load:
+/// 1 - unseeded noise
+/// 2 - all noise derived from the world seed
+/// 3 - caves carved with 3D Perlin noise
+/// 4 - biomes from climate noise
+/// 5 - ore veins
+/// 6 - procedural dungeon layouts per cell
+/// 7 - trees, boulders and ruins from structure templates
+/// 8 - no dungeon entrance shafts on the edge of a corridor
+pub const GENERATOR_VERSION: u32 = 8;
+
+/// Subdirectory of a save where rewritten regions are staged before they replace the saved ones
+const STAGING_DIR: &str = "rewrite";
+/// Header written after the last staged region, which the staged regions belong to
+const STAGED_HEADER_FILE: &str = "world.bin";
+
+/// Self-describing header stored at the start of `world.bin`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveHeader {
+    pub magic: [u8; 8],
+    pub format_version: u32,
+    pub seed: u64,
+    pub generator_version: u32,
+    /// Registry name of each voxel id used in the region files
+    pub voxel_names: Vec<String>,
+    pub world: WorldData,
+}
+
+impl SaveHeader {
+    /// Header in the current format. `generator_version` is the one the save was created
+    /// with, `GENERATOR_VERSION` for a new world.
+    pub fn new(world: WorldData, seed: u64, generator_version: u32, registry: &VoxelRegistry) -> Self {
+        Self {
+            magic: SAVE_MAGIC,
+            format_version: SAVE_FORMAT_VERSION,
+            seed,
+            generator_version,
+            voxel_names: registry.iter().map(|(_, info)| info.name.clone()).collect(),
+            world,
+        }
+    }
+
+    /// Map saved voxel ids to the current registry. None if the ids already match.
+    fn id_remap(&self, registry: &VoxelRegistry) -> Result<Option<Vec<VoxelType>>, PersistenceError> {
+        let mut remap = Vec::with_capacity(self.voxel_names.len());
+        for name in &self.voxel_names {
+            let id = registry
+                .id(name)
+                .ok_or_else(|| PersistenceError::UnknownVoxelType(name.clone()))?;
+            remap.push(id);
+        }
+
+        let identity = remap.iter().enumerate().all(|(old, new)| new.0 as usize == old);
+        Ok((!identity).then_some(remap))
+    }
+}
+
+/// Write the header through a temp file so a crash never leaves it half written
+pub fn write_header(path: &Path, header: &SaveHeader) -> Result<(), PersistenceError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let tmp_path = path.with_extension("bin.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    bincode::serialize_into(&mut writer, header)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Read a header of any known version, without migrating it
+fn read_header(path: &Path) -> Result<SaveHeader, PersistenceError> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+
+    if bytes.starts_with(&SAVE_MAGIC) {
+        return Ok(bincode::deserialize(&bytes)?);
+    }
+
+    // Version 1 had no header, just the world size
+    let world: WorldData = bincode::deserialize(&bytes).map_err(|_| PersistenceError::BadMagic)?;
+    Ok(SaveHeader {
+        magic: SAVE_MAGIC,
+        format_version: 1,
+        seed: 0,
+        generator_version: 1,
+        voxel_names: Vec::new(),
+        world,
+    })
+}
+
+/// Read the header at `path`, upgrading older saves in place. Chunks are rewritten in the
+/// current layout, with voxel ids from the registry if it assigns different ids to the saved names.
+pub fn open_save(path: &Path, store: &mut RegionStore, registry: &VoxelRegistry) -> Result<SaveHeader, PersistenceError> {
+    let mut header = read_header(path)?;
+    let loaded_version = header.format_version;
+    recover_rewrite(store, &header)?;
+
+    if header.format_version > SAVE_FORMAT_VERSION {
+        return Err(PersistenceError::UnsupportedVersion {
+            found: header.format_version,
+            supported: SAVE_FORMAT_VERSION,
+        });
+    }
+    store.set_format_version(header.format_version);
+
+    while header.format_version < SAVE_FORMAT_VERSION {
+        match header.format_version {
+            1 => migrate_v1_to_v2(&mut header, registry),
+            // The density channel is added when the chunks are rewritten
+            2 => header.format_version = 3,
+            version => return Err(PersistenceError::NoMigration(version)),
+        }
+    }
+
+    if header.generator_version != GENERATOR_VERSION {
+        warn!(
+            "Save was made with generator version {}, current is {}; unsaved chunks will not match",
+            header.generator_version, GENERATOR_VERSION
+        );
+    }
+
+    let remap = header.id_remap(registry)?;
+    if loaded_version != SAVE_FORMAT_VERSION || remap.is_some() {
+        // Keep the generator version: unsaved chunks are still regenerated from the old one
+        let upgraded = SaveHeader::new(header.world, header.seed, header.generator_version, registry);
+
+        // The new header commits the rewrite, so a crash before it leaves the old regions
+        // in place and a crash after it is finished by `recover_rewrite`
+        let staging = store.dir().join(STAGING_DIR);
+        let count = stage_rewritten_regions(store, remap.as_deref(), &staging, &upgraded)?;
+        write_header(path, &upgraded)?;
+        finish_rewrite(store, &staging)?;
+        store.set_format_version(SAVE_FORMAT_VERSION);
+
+        header = upgraded;
+        info!(
+            "Upgraded save {} from format version {}, rewrote {} saved chunks",
+            path.display(),
+            loaded_version,
+            count
+        );
+    }
+
+    Ok(header)
+}
+
+/// Version 2 only added header fields. Version 1 used the registry's ids directly.
+fn migrate_v1_to_v2(header: &mut SaveHeader, registry: &VoxelRegistry) {
+    header.voxel_names = registry.iter().map(|(_, info)| info.name.clone()).collect();
+    header.format_version = 2;
+}
+
+/// Write every saved chunk in the current layout into `staging`, with voxel ids mapped
+/// through `remap` if given, followed by the header they belong to. Returns the number of
+/// chunks written.
+fn stage_rewritten_regions(
+    store: &mut RegionStore,
+    remap: Option<&[VoxelType]>,
+    staging: &Path,
+    header: &SaveHeader,
+) -> Result<usize, PersistenceError> {
+    if staging.exists() {
+        fs::remove_dir_all(staging)?;
+    }
+
+    let positions = store.saved_chunk_positions()?;
+    let mut chunks = Vec::with_capacity(positions.len());
+    for pos in positions {
+        if let Some(mut data) = store.load_chunk_data(pos)? {
+            if let Some(remap) = remap {
+                data.voxels.remap(|voxel| remap.get(voxel.0 as usize).copied().unwrap_or(VoxelType::Air));
+            }
+            chunks.push(data);
+        }
+    }
+    let count = RegionStore::new(staging).save_chunks(chunks)?;
+    write_header(&staging.join(STAGED_HEADER_FILE), header)?;
+    Ok(count)
+}
+
+/// Move staged regions over the saved ones
+fn finish_rewrite(store: &mut RegionStore, staging: &Path) -> Result<(), PersistenceError> {
+    store.close();
+    for entry in fs::read_dir(staging)? {
+        let entry = entry?;
+        if entry.path().extension().is_some_and(|ext| ext == "region") {
+            fs::rename(entry.path(), store.dir().join(entry.file_name()))?;
+        }
+    }
+    fs::remove_dir_all(staging)?;
+    Ok(())
+}
+
+/// Deal with a rewrite interrupted by a crash. Staged regions are moved in if the header
+/// saved next to them was already written over `header`, and discarded otherwise.
+fn recover_rewrite(store: &mut RegionStore, header: &SaveHeader) -> Result<(), PersistenceError> {
+    let staging = store.dir().join(STAGING_DIR);
+    if !staging.exists() {
+        return Ok(());
+    }
+
+    let committed = read_header(&staging.join(STAGED_HEADER_FILE)).is_ok_and(|staged| {
+        staged.format_version == header.format_version && staged.voxel_names == header.voxel_names
+    });
+    if committed {
+        warn!("Finishing a save upgrade interrupted by a crash");
+        finish_rewrite(store, &staging)
+    } else {
+        fs::remove_dir_all(&staging)?;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Version 0: single-file saves
+// =============================================================================
+
+/// Where version 0 saves were written
+pub const LEGACY_SAVE_PATH: &str = "world_data.bin";
+
+#[derive(Deserialize)]
+struct LegacyWorldData {
+    world_size_chunks: IVec3,
+    chunks: Vec<LegacyChunkData>,
+}
+
+#[derive(Deserialize)]
+struct LegacyChunkData {
+    /// VoxelType was an enum, which bincode stores as its u32 variant index
+    voxels: Vec<u32>,
+    position: IVec3,
+}
+
+/// Move a version 0 save into region files and write a current header at `header_path`
+pub fn migrate_legacy_save(
+    legacy_path: &Path,
+    header_path: &Path,
+    store: &mut RegionStore,
+    registry: &VoxelRegistry,
+) -> Result<SaveHeader, PersistenceError> {
+    let legacy: LegacyWorldData = bincode::deserialize_from(BufReader::new(File::open(legacy_path)?))?;
+
+    // The enum variants were the built-in types in registry order
+    let remap: Vec<VoxelType> = BUILTIN_VOXEL_NAMES
+        .iter()
+        .map(|name| registry.id(name).ok_or_else(|| PersistenceError::UnknownVoxelType(name.to_string())))
+        .collect::<Result<_, _>>()?;
+
+    let chunk_count = legacy.chunks.len();
+    let chunks = legacy.chunks.into_iter().map(|chunk| ChunkData {
+        voxels: PalettedStorage::from_voxels(
+            chunk.voxels.into_iter().map(|id| remap.get(id as usize).copied().unwrap_or(VoxelType::Air)),
+        ),
+        position: chunk.position,
+        density: None,
+    });
+    store.save_chunks(chunks)?;
+
+    let world = WorldData { world_size_chunks: legacy.world_size_chunks };
+    // Single-file saves predate the header, like format version 1
+    let header = SaveHeader::new(world, 0, 1, registry);
+    write_header(header_path, &header)?;
+
+    info!("Migrated {} chunks from {} (format version 0)", chunk_count, legacy_path.display());
+    Ok(header)
+}
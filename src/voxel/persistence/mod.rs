@@ -0,0 +1,261 @@
+pub mod autosave;
+pub mod format;
+pub mod region;
+pub mod slots;
+
+use crate::voxel::chunk::{Chunk, ChunkData};
+use crate::voxel::registry::VoxelRegistry;
+use crate::voxel::world::VoxelWorld;
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+
+pub use format::SaveHeader;
+pub use region::RegionStore;
+
+/// Pre-slot save directory, adopted as the default slot
+const LEGACY_SAVE_DIR: &str = "world_data";
+const WORLD_META_FILE: &str = "world.bin";
+
+#[derive(Error, Debug)]
+pub enum PersistenceError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+    #[error("No saved world found")]
+    NotFound,
+    #[error("Not a world save (bad magic number)")]
+    BadMagic,
+    #[error("Save format version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("No migration from save format version {0}")]
+    NoMigration(u32),
+    #[error("Save uses voxel type '{0}' which is not in the registry")]
+    UnknownVoxelType(String),
+    #[error("Metadata error: {0}")]
+    Metadata(#[from] serde_yaml::Error),
+    #[error("Invalid world name '{0}' (use letters, digits, spaces, '-' or '_')")]
+    InvalidSlotName(String),
+    #[error("A world named '{0}' already exists")]
+    SlotExists(String),
+    #[error("No world named '{0}'")]
+    SlotNotFound(String),
+}
+
+/// Serializable world-level data (chunks live in region files)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorldData {
+    pub world_size_chunks: IVec3,
+}
+
+/// Region files shared between the main thread and background save tasks, along with the
+/// chunk snapshots queued for saving that are not on disk yet
+#[derive(Resource, Clone)]
+pub struct WorldStorage {
+    store: Arc<Mutex<RegionStore>>,
+    pending: Arc<Mutex<PendingChunks>>,
+}
+
+/// Latest unwritten snapshot of each chunk, numbered so a write only forgets the snapshots
+/// that were not replaced while it ran
+#[derive(Default)]
+struct PendingChunks {
+    next_serial: u64,
+    chunks: HashMap<IVec3, (u64, ChunkData)>,
+}
+
+impl WorldStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(RegionStore::new(dir))),
+            pending: Arc::default(),
+        }
+    }
+
+    /// Blocks while a background save is writing
+    pub fn lock(&self) -> MutexGuard<'_, RegionStore> {
+        // A panicked save leaves the committed tables on disk intact, so keep going
+        self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn pending(&self) -> MutexGuard<'_, PendingChunks> {
+        self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Queue chunk snapshots for the next write, replacing older snapshots of the same chunks
+    pub fn queue_chunks(&self, chunks: impl IntoIterator<Item = ChunkData>) {
+        let mut pending = self.pending();
+        for data in chunks {
+            pending.next_serial += 1;
+            let serial = pending.next_serial;
+            pending.chunks.insert(data.position, (serial, data));
+        }
+    }
+
+    /// Latest queued snapshot of a chunk that is not written yet
+    pub fn pending_chunk(&self, chunk_pos: IVec3) -> Option<ChunkData> {
+        self.pending().chunks.get(&chunk_pos).map(|(_, data)| data.clone())
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending().chunks.is_empty()
+    }
+
+    /// Load a chunk, preferring a queued snapshot over the older copy on disk
+    pub fn load_chunk(&self, chunk_pos: IVec3) -> io::Result<Option<Chunk>> {
+        let mut store = self.lock();
+        if let Some(data) = self.pending_chunk(chunk_pos) {
+            return Ok(Some(Chunk::from_data(data)));
+        }
+        store.load_chunk(chunk_pos)
+    }
+
+    /// Hand every queued snapshot to `write` with the store locked. Snapshots stay queued
+    /// until a write succeeds, so a failed one goes out with the next write, and a later
+    /// write always carries the newest snapshot whichever task gets the lock first.
+    pub fn write_pending<T, E>(&self, write: impl FnOnce(&mut RegionStore, Vec<ChunkData>) -> Result<T, E>) -> Result<T, E> {
+        let mut store = self.lock();
+        let (serials, chunks): (Vec<_>, Vec<_>) = self
+            .pending()
+            .chunks
+            .iter()
+            .map(|(pos, (serial, data))| ((*pos, *serial), data.clone()))
+            .unzip();
+
+        let result = write(&mut store, chunks);
+        if result.is_ok() {
+            let mut pending = self.pending();
+            for (pos, serial) in serials {
+                if pending.chunks.get(&pos).is_some_and(|(queued, _)| *queued == serial) {
+                    pending.chunks.remove(&pos);
+                }
+            }
+        }
+        result
+    }
+}
+
+fn header_path(store: &RegionStore) -> PathBuf {
+    store.dir().join(WORLD_META_FILE)
+}
+
+/// Save the header, slot metadata and already snapshotted chunks, rewriting only their regions
+pub fn save_snapshot(
+    store: &mut RegionStore,
+    header: &SaveHeader,
+    metadata: &slots::WorldMetadata,
+    chunks: Vec<ChunkData>,
+) -> Result<usize, PersistenceError> {
+    format::write_header(&header_path(store), header)?;
+    slots::write_metadata(store.dir(), metadata)?;
+    let saved = store.save_chunks(chunks)?;
+
+    info!("World saved to {} ({} chunks)", store.dir().display(), saved);
+    Ok(saved)
+}
+
+/// Open the saved world, migrating older formats. Chunks are loaded on demand through
+/// the `RegionStore`.
+pub fn load_world(store: &mut RegionStore, registry: &VoxelRegistry) -> Result<(VoxelWorld, SaveHeader), PersistenceError> {
+    let path = header_path(store);
+    let legacy_path = Path::new(format::LEGACY_SAVE_PATH);
+
+    let header = if path.exists() {
+        format::open_save(&path, store, registry)?
+    } else if is_default_slot(store) && legacy_path.exists() {
+        format::migrate_legacy_save(legacy_path, &path, store, registry)?
+    } else {
+        return Err(PersistenceError::NotFound);
+    };
+
+    info!("World header loaded from {} (seed {})", path.display(), header.seed);
+
+    Ok((VoxelWorld::from_data(header.world.clone()), header))
+}
+
+/// Older saves (a single file or the `world_data` directory) belong to the default slot
+fn is_default_slot(store: &RegionStore) -> bool {
+    store.dir() == slots::slot_dir(slots::DEFAULT_SLOT)
+}
+
+/// Move a pre-slot `world_data` directory into the default slot
+pub fn adopt_legacy_save_dir(store: &mut RegionStore) -> Result<(), PersistenceError> {
+    let legacy_dir = Path::new(LEGACY_SAVE_DIR);
+    if !is_default_slot(store) || header_path(store).exists() || !legacy_dir.join(WORLD_META_FILE).exists() {
+        return Ok(());
+    }
+
+    store.close();
+    if let Some(parent) = store.dir().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(legacy_dir, store.dir())?;
+    info!("Moved {} to {}", legacy_dir.display(), store.dir().display());
+    Ok(())
+}
+
+/// Check if a saved world exists (including a pre-region single-file save)
+pub fn saved_world_exists(store: &RegionStore) -> bool {
+    header_path(store).exists()
+        || (is_default_slot(store) && Path::new(format::LEGACY_SAVE_PATH).exists())
+}
+
+/// Rename an unreadable save directory so a new world does not overwrite it
+pub fn set_aside_saved_world(store: &mut RegionStore) -> Result<PathBuf, PersistenceError> {
+    store.close();
+    let dir = store.dir();
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let backup = dir.with_file_name(format!(
+        "{}.unreadable-{}",
+        dir.file_name().and_then(|n| n.to_str()).unwrap_or("world"),
+        stamp
+    ));
+    if dir.exists() {
+        fs::rename(dir, &backup)?;
+    }
+    Ok(backup)
+}
+
+/// Delete the saved world directory
+pub fn delete_saved_world(store: &mut RegionStore) -> Result<(), PersistenceError> {
+    store.close();
+    let dir = store.dir();
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+        info!("Deleted saved world at {}", dir.display());
+    }
+    Ok(())
+}
+
+/// Resource to control world persistence behavior
+#[derive(Resource, Clone, Debug)]
+pub struct WorldPersistence {
+    /// Force regeneration even if saved world exists
+    pub force_regenerate: bool,
+    /// Auto-save world after generation
+    pub auto_save: bool,
+    /// Seconds between background saves of edited chunks (0 disables)
+    pub autosave_interval_secs: f32,
+    /// Named world under `saves/` to load or create at startup
+    pub slot: String,
+}
+
+impl Default for WorldPersistence {
+    fn default() -> Self {
+        Self {
+            force_regenerate: true, // Force regeneration to ensure fresh terrain
+            auto_save: true,
+            autosave_interval_secs: 60.0,
+            slot: slots::DEFAULT_SLOT.to_string(),
+        }
+    }
+}
@@ -0,0 +1,428 @@
+// Region files: each file holds a 32×8×32 block of chunks.
+//
+// Layout: header (magic + version), two offset tables, then compressed chunk blobs.
+// Saving appends new blobs to the end of the file and then commits them by writing the
+// inactive offset table with a higher generation, so a crash mid-save leaves the previous
+// table (and every other region file) intact. Dead space from overwritten blobs is
+// reclaimed by rewriting the region through a temp file once it outgrows the live data.
+
+use crate::voxel::chunk::{Chunk, ChunkData};
+use crate::voxel::palette::PalettedStorage;
+use crate::voxel::persistence::format::SAVE_FORMAT_VERSION;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Chunks per region along x and z
+pub const REGION_SIZE: i32 = 32;
+/// Chunks per region along y
+pub const REGION_HEIGHT: i32 = 8;
+
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE * REGION_HEIGHT) as usize;
+const REGION_MAGIC: [u8; 4] = *b"VXRG";
+const REGION_VERSION: u32 = 1;
+
+const HEADER_BYTES: u64 = 8;
+/// Offset (u64) + length (u32) per chunk
+const ENTRY_BYTES: usize = 12;
+/// Generation (u64) + checksum (u64) + entries
+const TABLE_BYTES: u64 = 16 + (CHUNKS_PER_REGION * ENTRY_BYTES) as u64;
+const DATA_START: u64 = HEADER_BYTES + 2 * TABLE_BYTES;
+
+/// Regions are compacted once dead blobs exceed both the live data and this size
+const COMPACT_MIN_DEAD_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Region containing a chunk
+pub fn region_of(chunk_pos: IVec3) -> IVec3 {
+    IVec3::new(
+        chunk_pos.x.div_euclid(REGION_SIZE),
+        chunk_pos.y.div_euclid(REGION_HEIGHT),
+        chunk_pos.z.div_euclid(REGION_SIZE),
+    )
+}
+
+/// Index of a chunk inside its region's offset table
+fn slot_of(chunk_pos: IVec3) -> usize {
+    let x = chunk_pos.x.rem_euclid(REGION_SIZE);
+    let y = chunk_pos.y.rem_euclid(REGION_HEIGHT);
+    let z = chunk_pos.z.rem_euclid(REGION_SIZE);
+    (x + z * REGION_SIZE + y * REGION_SIZE * REGION_SIZE) as usize
+}
+
+fn region_file_name(region_pos: IVec3) -> String {
+    format!("r.{}.{}.{}.region", region_pos.x, region_pos.y, region_pos.z)
+}
+
+fn parse_region_file_name(name: &str) -> Option<IVec3> {
+    let coords = name.strip_prefix("r.")?.strip_suffix(".region")?;
+    let mut parts = coords.split('.').map(|part| part.parse::<i32>().ok());
+    let pos = IVec3::new(parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(pos)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Compress a chunk for storage in a region file
+pub fn encode_chunk(data: &ChunkData) -> io::Result<Vec<u8>> {
+    let raw = bincode::serialize(data).map_err(|e| invalid_data(e.to_string()))?;
+    Ok(lz4_flex::compress_prepend_size(&raw))
+}
+
+/// Chunk layout of format version 2 and older, before the density channel
+#[derive(Deserialize)]
+struct ChunkDataV2 {
+    voxels: PalettedStorage,
+    position: IVec3,
+}
+
+/// Decompress a chunk blob written by save format `format_version` and check its palette
+pub fn decode_chunk(blob: &[u8], format_version: u32) -> io::Result<ChunkData> {
+    let raw = lz4_flex::decompress_size_prepended(blob).map_err(|e| invalid_data(e.to_string()))?;
+    let mut data = match format_version {
+        1 | 2 => {
+            let ChunkDataV2 { voxels, position } =
+                bincode::deserialize(&raw).map_err(|e| invalid_data(e.to_string()))?;
+            ChunkData { voxels, position, density: None }
+        }
+        SAVE_FORMAT_VERSION => bincode::deserialize(&raw).map_err(|e| invalid_data(e.to_string()))?,
+        version => return Err(invalid_data(format!("no chunk layout for save format version {}", version))),
+    };
+    // A blob can pass the checksum and still hold indices the palette does not have
+    if !data.voxels.rebuild_counts() {
+        return Err(invalid_data(format!("inconsistent voxel palette in chunk {:?}", data.position)));
+    }
+    Ok(data)
+}
+
+/// Location of a chunk blob; a zero length means the chunk was never saved
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    offset: u64,
+    len: u32,
+}
+
+struct OffsetTable {
+    generation: u64,
+    entries: Vec<Entry>,
+}
+
+impl OffsetTable {
+    fn empty() -> Self {
+        Self {
+            generation: 0,
+            entries: vec![Entry::default(); CHUNKS_PER_REGION],
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut entries = Vec::with_capacity(CHUNKS_PER_REGION * ENTRY_BYTES);
+        for entry in &self.entries {
+            entries.extend_from_slice(&entry.offset.to_le_bytes());
+            entries.extend_from_slice(&entry.len.to_le_bytes());
+        }
+
+        let mut bytes = Vec::with_capacity(TABLE_BYTES as usize);
+        bytes.extend_from_slice(&self.generation.to_le_bytes());
+        bytes.extend_from_slice(&checksum(self.generation, &entries).to_le_bytes());
+        bytes.extend_from_slice(&entries);
+        bytes
+    }
+
+    /// None if the table is torn or was never written
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let generation = u64::from_le_bytes(bytes[0..8].try_into().ok()?);
+        let stored = u64::from_le_bytes(bytes[8..16].try_into().ok()?);
+        let entry_bytes = &bytes[16..];
+        if generation == 0 || stored != checksum(generation, entry_bytes) {
+            return None;
+        }
+
+        let entries = entry_bytes
+            .chunks_exact(ENTRY_BYTES)
+            .map(|e| Entry {
+                offset: u64::from_le_bytes(e[0..8].try_into().unwrap()),
+                len: u32::from_le_bytes(e[8..12].try_into().unwrap()),
+            })
+            .collect();
+        Some(Self { generation, entries })
+    }
+
+    fn live_bytes(&self) -> u64 {
+        self.entries.iter().map(|e| e.len as u64).sum()
+    }
+}
+
+/// FNV-1a over the generation and table entries
+fn checksum(generation: u64, entries: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in generation.to_le_bytes().iter().chain(entries) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+struct RegionFile {
+    path: PathBuf,
+    file: File,
+    table: OffsetTable,
+    /// Which of the two table slots holds the committed table
+    active_slot: u64,
+    /// Append position for new blobs
+    end: u64,
+}
+
+impl RegionFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        let mut header = [0u8; HEADER_BYTES as usize];
+        file.read_exact(&mut header)?;
+        if header[0..4] != REGION_MAGIC {
+            return Err(invalid_data(format!("{} is not a region file", path.display())));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != REGION_VERSION {
+            return Err(invalid_data(format!("unsupported region version {}", version)));
+        }
+
+        let mut tables = vec![0u8; 2 * TABLE_BYTES as usize];
+        file.read_exact(&mut tables)?;
+        let (a, b) = tables.split_at(TABLE_BYTES as usize);
+
+        // Use the newest table that survived intact
+        let (table, active_slot) = match (OffsetTable::from_bytes(a), OffsetTable::from_bytes(b)) {
+            (Some(a), Some(b)) if b.generation > a.generation => (b, 1),
+            (Some(a), _) => (a, 0),
+            (None, Some(b)) => (b, 1),
+            (None, None) => return Err(invalid_data(format!("{} has no valid offset table", path.display()))),
+        };
+
+        let end = file.metadata()?.len().max(DATA_START);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            table,
+            active_slot,
+            end,
+        })
+    }
+
+    /// Write an empty region through a temp file so a half-created file is never seen
+    fn create(path: &Path) -> io::Result<Self> {
+        let table = OffsetTable { generation: 1, ..OffsetTable::empty() };
+        write_region_atomic(path, &table, &[])?;
+        Self::open(path)
+    }
+
+    fn read_chunk(&mut self, slot: usize) -> io::Result<Option<Vec<u8>>> {
+        let entry = self.table.entries[slot];
+        if entry.len == 0 {
+            return Ok(None);
+        }
+
+        let mut blob = vec![0u8; entry.len as usize];
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        self.file.read_exact(&mut blob)?;
+        Ok(Some(blob))
+    }
+
+    /// Append blobs and commit them with a new offset table
+    fn write_chunks(&mut self, blobs: Vec<(usize, Vec<u8>)>) -> io::Result<()> {
+        let mut entries = self.table.entries.clone();
+        let mut end = self.end;
+
+        self.file.seek(SeekFrom::Start(end))?;
+        for (slot, blob) in &blobs {
+            self.file.write_all(blob)?;
+            entries[*slot] = Entry { offset: end, len: blob.len() as u32 };
+            end += blob.len() as u64;
+        }
+        // Blobs must be on disk before the table that points at them
+        self.file.sync_data()?;
+
+        let table = OffsetTable { generation: self.table.generation + 1, entries };
+        let slot = 1 - self.active_slot;
+        self.file.seek(SeekFrom::Start(HEADER_BYTES + slot * TABLE_BYTES))?;
+        self.file.write_all(&table.to_bytes())?;
+        self.file.sync_data()?;
+
+        self.table = table;
+        self.active_slot = slot;
+        self.end = end;
+
+        let dead = self.end - DATA_START - self.table.live_bytes();
+        if dead > COMPACT_MIN_DEAD_BYTES && dead > self.table.live_bytes() {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the region with only live blobs
+    fn compact(&mut self) -> io::Result<()> {
+        let mut blobs = Vec::new();
+        for slot in 0..CHUNKS_PER_REGION {
+            if let Some(blob) = self.read_chunk(slot)? {
+                blobs.push((slot, blob));
+            }
+        }
+
+        let mut table = OffsetTable { generation: self.table.generation + 1, ..OffsetTable::empty() };
+        let mut offset = DATA_START;
+        for (slot, blob) in &blobs {
+            table.entries[*slot] = Entry { offset, len: blob.len() as u32 };
+            offset += blob.len() as u64;
+        }
+
+        write_region_atomic(&self.path, &table, &blobs)?;
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Write a complete region file next to `path` and rename it into place
+fn write_region_atomic(path: &Path, table: &OffsetTable, blobs: &[(usize, Vec<u8>)]) -> io::Result<()> {
+    let tmp_path = path.with_extension("region.tmp");
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&REGION_MAGIC)?;
+        file.write_all(&REGION_VERSION.to_le_bytes())?;
+        file.write_all(&table.to_bytes())?;
+        // Second slot stays invalid until the next commit
+        file.write_all(&vec![0u8; TABLE_BYTES as usize])?;
+        for (_, blob) in blobs {
+            file.write_all(blob)?;
+        }
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+/// Lazily opened region files of one saved world
+pub struct RegionStore {
+    dir: PathBuf,
+    regions: HashMap<IVec3, RegionFile>,
+    /// Save format the chunk blobs were written in
+    format_version: u32,
+}
+
+impl RegionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            regions: HashMap::new(),
+            format_version: SAVE_FORMAT_VERSION,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read chunks of an older save format until they are rewritten
+    pub fn set_format_version(&mut self, version: u32) {
+        self.format_version = version;
+    }
+
+    /// Drop open file handles (e.g. before the directory is deleted)
+    pub fn close(&mut self) {
+        self.regions.clear();
+    }
+
+    fn region(&mut self, region_pos: IVec3, create: bool) -> io::Result<Option<&mut RegionFile>> {
+        if !self.regions.contains_key(&region_pos) {
+            let path = self.dir.join(region_file_name(region_pos));
+            let region = if path.exists() {
+                RegionFile::open(&path)?
+            } else if create {
+                fs::create_dir_all(&self.dir)?;
+                RegionFile::create(&path)?
+            } else {
+                return Ok(None);
+            };
+            self.regions.insert(region_pos, region);
+        }
+        Ok(self.regions.get_mut(&region_pos))
+    }
+
+    /// Load a single chunk, None if it was never saved
+    pub fn load_chunk(&mut self, chunk_pos: IVec3) -> io::Result<Option<Chunk>> {
+        Ok(self.load_chunk_data(chunk_pos)?.map(Chunk::from_data))
+    }
+
+    pub fn load_chunk_data(&mut self, chunk_pos: IVec3) -> io::Result<Option<ChunkData>> {
+        let Some(region) = self.region(region_of(chunk_pos), false)? else {
+            return Ok(None);
+        };
+        let Some(blob) = region.read_chunk(slot_of(chunk_pos))? else {
+            return Ok(None);
+        };
+
+        let data = decode_chunk(&blob, self.format_version)?;
+        if data.position != chunk_pos {
+            return Err(invalid_data(format!(
+                "region slot for {:?} holds chunk {:?}",
+                chunk_pos, data.position
+            )));
+        }
+        Ok(Some(data))
+    }
+
+    /// Positions of every chunk stored in this world's region files
+    pub fn saved_chunk_positions(&mut self) -> io::Result<Vec<IVec3>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut positions = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let Some(region_pos) = name.to_str().and_then(parse_region_file_name) else {
+                continue;
+            };
+            let Some(region) = self.region(region_pos, false)? else {
+                continue;
+            };
+
+            let origin = region_pos * IVec3::new(REGION_SIZE, REGION_HEIGHT, REGION_SIZE);
+            for (slot, entry) in region.table.entries.iter().enumerate() {
+                if entry.len > 0 {
+                    let slot = slot as i32;
+                    let local = IVec3::new(
+                        slot % REGION_SIZE,
+                        slot / (REGION_SIZE * REGION_SIZE),
+                        (slot / REGION_SIZE) % REGION_SIZE,
+                    );
+                    positions.push(origin + local);
+                }
+            }
+        }
+        Ok(positions)
+    }
+
+    /// Write chunks, touching only the regions they belong to. Returns the number saved.
+    pub fn save_chunks(&mut self, chunks: impl IntoIterator<Item = ChunkData>) -> io::Result<usize> {
+        let mut by_region: HashMap<IVec3, Vec<(usize, Vec<u8>)>> = HashMap::new();
+        for data in chunks {
+            let blob = encode_chunk(&data)?;
+            by_region
+                .entry(region_of(data.position))
+                .or_default()
+                .push((slot_of(data.position), blob));
+        }
+
+        let mut saved = 0;
+        for (region_pos, blobs) in by_region {
+            saved += blobs.len();
+            if let Some(region) = self.region(region_pos, true)? {
+                region.write_chunks(blobs)?;
+            }
+        }
+        Ok(saved)
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::camera::controller::{CameraMode, PlayerCamera};
+use crate::config::biomes::BiomesConfigFile;
+use crate::config::ores::OresConfigFile;
+use crate::config::structures::StructuresConfigFile;
+use crate::config::world::GeneratorSection;
+use crate::entity::{Inventory, ItemType};
+use crate::voxel::persistence::PersistenceError;
+
+/// Directory holding one subdirectory per named world
+pub const SAVES_DIR: &str = "saves";
+/// Slot used when none is chosen
+pub const DEFAULT_SLOT: &str = "world";
+const METADATA_FILE: &str = "metadata.yaml";
+
+/// Where the player was when the world was last saved
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerState {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub camera_mode: CameraMode,
+}
+
+/// Human-readable description of a saved world, stored next to its region files
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorldMetadata {
+    pub name: String,
+    pub seed: u64,
+    /// Unix timestamps in seconds
+    pub created: u64,
+    pub last_played: u64,
+    pub play_time_secs: f64,
+    /// Generator the world was created with, so unsaved chunks regenerate the same way
+    #[serde(default)]
+    pub generator: Option<GeneratorSection>,
+    /// Biomes the world was created with
+    #[serde(default)]
+    pub biomes: Option<BiomesConfigFile>,
+    /// Ore deposits the world was created with
+    #[serde(default)]
+    pub ores: Option<OresConfigFile>,
+    /// Structures the world was created with
+    #[serde(default)]
+    pub structures: Option<StructuresConfigFile>,
+    #[serde(default)]
+    pub player: Option<PlayerState>,
+    #[serde(default)]
+    pub inventory: HashMap<ItemType, u32>,
+}
+
+impl WorldMetadata {
+    pub fn new(name: &str, seed: u64) -> Self {
+        let now = unix_now();
+        Self {
+            name: name.to_string(),
+            seed,
+            created: now,
+            last_played: now,
+            play_time_secs: 0.0,
+            generator: None,
+            biomes: None,
+            ores: None,
+            structures: None,
+            player: None,
+            inventory: HashMap::new(),
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Slot names double as directory names, so keep them to a safe character set
+pub fn validate_slot_name(name: &str) -> Result<(), PersistenceError> {
+    let valid = !name.trim().is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(PersistenceError::InvalidSlotName(name.to_string()))
+    }
+}
+
+/// Directory of a named world
+pub fn slot_dir(name: &str) -> PathBuf {
+    Path::new(SAVES_DIR).join(name)
+}
+
+pub fn slot_exists(name: &str) -> bool {
+    slot_dir(name).join(METADATA_FILE).exists()
+}
+
+pub fn read_metadata(dir: &Path) -> Result<WorldMetadata, PersistenceError> {
+    let file = File::open(dir.join(METADATA_FILE))?;
+    Ok(serde_yaml::from_reader(file)?)
+}
+
+/// Write metadata through a temp file so a crash never leaves it half written
+pub fn write_metadata(dir: &Path, metadata: &WorldMetadata) -> Result<(), PersistenceError> {
+    fs::create_dir_all(dir)?;
+
+    let path = dir.join(METADATA_FILE);
+    let tmp_path = path.with_extension("yaml.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    serde_yaml::to_writer(&mut writer, metadata)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// All saved worlds, most recently played first
+pub fn list_slots() -> Result<Vec<WorldMetadata>, PersistenceError> {
+    let saves = Path::new(SAVES_DIR);
+    if !saves.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut slots = Vec::new();
+    for entry in fs::read_dir(saves)? {
+        let dir = entry?.path();
+        if !dir.join(METADATA_FILE).exists() {
+            continue;
+        }
+        match read_metadata(&dir) {
+            Ok(metadata) => slots.push(metadata),
+            Err(e) => warn!("Skipping world {}: {}", dir.display(), e),
+        }
+    }
+    slots.sort_by_key(|slot| std::cmp::Reverse(slot.last_played));
+    Ok(slots)
+}
+
+/// Create an empty named world. Terrain is generated when it is first loaded.
+pub fn create_slot(name: &str, seed: u64) -> Result<WorldMetadata, PersistenceError> {
+    validate_slot_name(name)?;
+    if slot_dir(name).exists() {
+        return Err(PersistenceError::SlotExists(name.to_string()));
+    }
+
+    let metadata = WorldMetadata::new(name, seed);
+    write_metadata(&slot_dir(name), &metadata)?;
+    info!("Created world '{}' (seed {})", name, seed);
+    Ok(metadata)
+}
+
+pub fn delete_slot(name: &str) -> Result<(), PersistenceError> {
+    validate_slot_name(name)?;
+    let dir = slot_dir(name);
+    if !dir.exists() {
+        return Err(PersistenceError::SlotNotFound(name.to_string()));
+    }
+
+    fs::remove_dir_all(&dir)?;
+    info!("Deleted world '{}'", name);
+    Ok(())
+}
+
+pub fn rename_slot(old_name: &str, new_name: &str) -> Result<(), PersistenceError> {
+    validate_slot_name(old_name)?;
+    validate_slot_name(new_name)?;
+    let old_dir = slot_dir(old_name);
+    let new_dir = slot_dir(new_name);
+    if !old_dir.exists() {
+        return Err(PersistenceError::SlotNotFound(old_name.to_string()));
+    }
+    if new_dir.exists() {
+        return Err(PersistenceError::SlotExists(new_name.to_string()));
+    }
+
+    fs::rename(&old_dir, &new_dir)?;
+    let mut metadata = read_metadata(&new_dir)?;
+    metadata.name = new_name.to_string();
+    write_metadata(&new_dir, &metadata)?;
+    info!("Renamed world '{}' to '{}'", old_name, new_name);
+    Ok(())
+}
+
+// =============================================================================
+// Active world session
+// =============================================================================
+
+/// Metadata of the world being played, kept current for the next save
+#[derive(Resource, Clone, Debug)]
+pub struct ActiveWorld {
+    pub metadata: WorldMetadata,
+    /// Generator version from the loaded save header, written back with every save so an
+    /// older world keeps warning that its unsaved chunks will not match
+    pub generator_version: u32,
+}
+
+impl ActiveWorld {
+    /// Metadata with the last-played time set to now, ready to be written
+    pub fn snapshot(&self) -> WorldMetadata {
+        WorldMetadata { last_played: unix_now(), ..self.metadata.clone() }
+    }
+}
+
+/// Track play time, player position and inventory for the slot metadata
+pub fn track_world_session(
+    time: Res<Time>,
+    mut active: ResMut<ActiveWorld>,
+    camera_query: Query<(&Transform, &PlayerCamera)>,
+    inventory: Option<Res<Inventory>>,
+) {
+    let metadata = &mut active.metadata;
+    metadata.play_time_secs += time.delta_secs_f64();
+
+    if let Ok((transform, camera)) = camera_query.single() {
+        metadata.player = Some(PlayerState {
+            position: transform.translation,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+            camera_mode: camera.mode,
+        });
+    }
+
+    if let Some(inventory) = inventory
+        && inventory.is_changed()
+    {
+        metadata.inventory = inventory.items.clone();
+    }
+}
+
+/// Put the player back where they were when the world was saved
+pub fn restore_player_state(
+    active: Res<ActiveWorld>,
+    mut camera_query: Query<(&mut Transform, &mut PlayerCamera)>,
+    inventory: Option<ResMut<Inventory>>,
+) {
+    if let Some(player) = &active.metadata.player
+        && let Ok((mut transform, mut camera)) = camera_query.single_mut()
+    {
+        transform.translation = player.position;
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, player.yaw, player.pitch, 0.0);
+        camera.yaw = player.yaw;
+        camera.pitch = player.pitch;
+        camera.mode = player.camera_mode;
+    }
+
+    if let Some(mut inventory) = inventory {
+        inventory.items = active.metadata.inventory.clone();
+    }
+}
@@ -1,12 +1,25 @@
+use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
 use bevy::prelude::*;
-use crate::constants::{CHUNK_SIZE, CHUNK_SIZE_I32};
-use crate::voxel::chunk::Chunk;
-use crate::voxel::meshing::{generate_chunk_mesh_with_mode, MeshSettings, MeshMode};
-use crate::voxel::types::VoxelType;
-use crate::voxel::world::VoxelWorld;
-use crate::voxel::persistence::{self, WorldPersistence};
-use crate::rendering::materials::VoxelMaterial;
-use crate::rendering::triplanar_material::TriplanarMaterialHandle;
+use crate::camera::controller::PlayerCamera;
+use crate::config::biomes::{load_biomes_config, BiomesConfigFile, BIOMES_CONFIG_PATH};
+use crate::config::ores::{load_ores_config, OresConfigFile, ORES_CONFIG_PATH};
+use crate::config::structures::{load_structures_config, StructuresConfigFile, STRUCTURES_CONFIG_PATH};
+use crate::config::hot_reload::{ConfigReloadPlugin, ConfigReloaded, ConfigWatcher};
+use crate::config::world::{load_world_config, FluidSection, GeneratorSection, InteractionSection, LodSection, StreamingSection, WorldConfigFile, WORLD_CONFIG_PATH};
+use crate::constants::CHUNK_SIZE_I32;
+use crate::voxel::fluid::{self, FluidSimulation, VoxelsEdited};
+use crate::voxel::generation::{BiomeMap, GeneratorContext, NoiseGenerator, TerrainGenerators, WorldGenerator};
+use crate::voxel::generation::tasks::{self, ChunkGenerationQueue, GenerationProgress};
+use crate::voxel::lighting;
+use crate::voxel::mesh_tasks::{self, ChunkMeshTasks};
+use crate::voxel::meshing::MeshSettings;
+use crate::voxel::registry::{VoxelRegistry, VOXEL_TYPES_CONFIG_PATH};
+use crate::voxel::streaming::{self, ChunkStreaming, ChunkUnloaded};
+use crate::voxel::world::{BoundsPolicy, VoxelWorld, WorldSeed};
+use crate::voxel::persistence::{self, RegionStore, SaveHeader, WorldPersistence, WorldStorage};
+use crate::voxel::persistence::slots::{self, ActiveWorld, WorldMetadata};
+use crate::voxel::persistence::autosave::{self, SaveState};
+use crate::voxel::persistence::format::GENERATOR_VERSION;
 
 pub struct VoxelPlugin;
 
@@ -15,601 +28,493 @@ pub struct WorldConfig {
     pub size_chunks: IVec3,
     pub chunk_size: i32,
     pub greedy_meshing: bool,
+    pub meshing_enabled: bool,
+    /// Finished chunk meshes uploaded per frame
+    pub mesh_upload_budget: usize,
+    /// Downsampled meshes for distant chunks
+    pub lod: LodSection,
+    /// Generator for new worlds
+    pub generator: GeneratorSection,
+    /// Biomes for new worlds
+    pub biomes: BiomesConfigFile,
+    /// Ore deposits for new worlds
+    pub ores: OresConfigFile,
+    /// Trees, boulders and ruins for new worlds
+    pub structures: StructuresConfigFile,
+    pub streaming: StreamingSection,
+    /// Flowing water simulation
+    pub fluids: FluidSection,
+    pub interaction: InteractionSection,
 }
 
-impl Plugin for VoxelPlugin {
-    fn build(&self, app: &mut App) {
-        app
-            .insert_resource(WorldConfig {
-                size_chunks: IVec3::new(32, 4, 32),
-                chunk_size: 16,
-                greedy_meshing: true,
-            })
-            .insert_resource(VoxelWorld::new(IVec3::new(32, 4, 32)))
-            // Use SurfaceNets for smooth terrain meshing (change to Blocky for Minecraft-style)
-            .insert_resource(MeshSettings { mode: MeshMode::SurfaceNets })
-            // World persistence settings (set force_regenerate to true to regenerate)
-            .insert_resource(WorldPersistence { force_regenerate: false, ..default() })
-            .add_systems(Startup, setup_voxel_world)
-            .add_systems(Update, mesh_dirty_chunks_system);
-    }
-}
-
-// Simple pseudo-random noise functions for terrain generation
-fn hash(x: i32, z: i32) -> f32 {
-    let n = x.wrapping_mul(374761393).wrapping_add(z.wrapping_mul(668265263));
-    let n = (n ^ (n >> 13)).wrapping_mul(1274126177);
-    ((n ^ (n >> 16)) as u32 as f32) / u32::MAX as f32
-}
-
-fn smoothstep(t: f32) -> f32 {
-    t * t * (3.0 - 2.0 * t)
+/// Debug rendering toggles from the `debug` section of world.yaml
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct VoxelDebugSettings {
+    pub wireframe: bool,
+    pub chunk_borders: bool,
 }
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + t * (b - a)
-}
-
-fn value_noise(x: f32, z: f32) -> f32 {
-    let xi = x.floor() as i32;
-    let zi = z.floor() as i32;
-    let xf = x - x.floor();
-    let zf = z - z.floor();
-
-    let v00 = hash(xi, zi);
-    let v10 = hash(xi + 1, zi);
-    let v01 = hash(xi, zi + 1);
-    let v11 = hash(xi + 1, zi + 1);
-
-    let u = smoothstep(xf);
-    let v = smoothstep(zf);
-
-    lerp(lerp(v00, v10, u), lerp(v01, v11, u), v)
-}
-
-fn fbm(x: f32, z: f32, octaves: u32) -> f32 {
-    let mut value = 0.0;
-    let mut amplitude = 1.0;
-    let mut frequency = 1.0;
-    let mut max_value = 0.0;
-
-    for _ in 0..octaves {
-        value += amplitude * value_noise(x * frequency, z * frequency);
-        max_value += amplitude;
-        amplitude *= 0.5;
-        frequency *= 2.0;
-    }
-
-    value / max_value
-}
-
-fn get_terrain_height(world_x: i32, world_z: i32) -> i32 {
-    let x = world_x as f32;
-    let z = world_z as f32;
-
-    // Base terrain with multiple noise layers
-    // Base ranges from 16-36, keeping most land above water level (18)
-    let base = fbm(x * 0.008, z * 0.008, 4) * 20.0 + 16.0;
-
-    // Hills - larger features
-    let hills = fbm(x * 0.02, z * 0.02, 3) * 10.0;
-
-    // Mountains - occasional tall peaks
-    let mountain_mask = fbm(x * 0.005, z * 0.005, 2);
-    let mountains = if mountain_mask > 0.65 {
-        (mountain_mask - 0.65) * 50.0
-    } else {
-        0.0
-    };
+impl Plugin for VoxelPlugin {
+    fn build(&self, app: &mut App) {
+        let config_file = match load_world_config(WORLD_CONFIG_PATH) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Invalid world config {}: {}. Using defaults", WORLD_CONFIG_PATH, e);
+                WorldConfigFile::default()
+            }
+        };
+        let size_chunks = config_file.size_chunks();
 
-    // River valleys - carve into terrain (wider rivers)
-    let river_noise = (fbm(x * 0.015, z * 0.015, 2) * 6.28).sin();
-    let river_factor = if river_noise.abs() < 0.2 {
-        -10.0 * (1.0 - river_noise.abs() / 0.2)
-    } else {
-        0.0
-    };
+        let biomes = match load_biomes_config(BIOMES_CONFIG_PATH) {
+            Ok(biomes) => biomes,
+            Err(e) => {
+                error!("Invalid biomes {}: {}. Using built-in biomes", BIOMES_CONFIG_PATH, e);
+                BiomesConfigFile::default()
+            }
+        };
 
-    (base + hills + mountains + river_factor).max(1.0).min(58.0) as i32
-}
+        let ores = match load_ores_config(ORES_CONFIG_PATH) {
+            Ok(ores) => ores,
+            Err(e) => {
+                error!("Invalid ore deposits {}: {}. Using built-in deposits", ORES_CONFIG_PATH, e);
+                OresConfigFile::default()
+            }
+        };
 
-fn get_biome(world_x: i32, world_z: i32) -> u8 {
-    // 0 = normal, 1 = sandy/beach, 2 = rocky, 3 = clay deposits
-    let x = world_x as f32;
-    let z = world_z as f32;
+        let structures = match load_structures_config(STRUCTURES_CONFIG_PATH) {
+            Ok(structures) => structures,
+            Err(e) => {
+                error!("Invalid structures {}: {}. Using built-in structures", STRUCTURES_CONFIG_PATH, e);
+                StructuresConfigFile::default()
+            }
+        };
 
-    let biome_noise = fbm(x * 0.01, z * 0.01, 2);
-    let detail_noise = fbm(x * 0.05, z * 0.05, 2);
+        let registry = match VoxelRegistry::load(VOXEL_TYPES_CONFIG_PATH) {
+            Ok(registry) => registry,
+            Err(e) => {
+                error!("Invalid voxel types {}: {}. Using built-in types", VOXEL_TYPES_CONFIG_PATH, e);
+                VoxelRegistry::builtin()
+            }
+        };
+        // Meshing and physics query voxel properties through the Voxel trait
+        registry.install();
+        info!("Registered {} voxel types", registry.len());
+
+        // World persistence settings (set force_regenerate to true to regenerate). An app that
+        // inserted its own WorldPersistence keeps it; otherwise the slot is --world on the
+        // command line, then world.yaml, then the default slot.
+        let persistence_settings = app.world().get_resource::<WorldPersistence>().cloned().unwrap_or_else(|| {
+            let slot = slot_from_args(std::env::args())
+                .or_else(|| config_file.world.slot.clone())
+                .filter(|slot| match slots::validate_slot_name(slot) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("{}. Using the '{}' world", e, slots::DEFAULT_SLOT);
+                        false
+                    }
+                })
+                .unwrap_or_else(|| slots::DEFAULT_SLOT.to_string());
+            WorldPersistence { force_regenerate: false, slot, ..default() }
+        });
+
+        // Seed for a new world: --seed on the command line, then world.yaml, then random.
+        // An existing slot replaces it with its own seed when loaded.
+        let seed = seed_from_args(std::env::args())
+            .or(config_file.world.seed)
+            .map(WorldSeed)
+            .unwrap_or_else(WorldSeed::random);
 
-    if biome_noise < 0.25 {
-        1 // Sandy areas
-    } else if biome_noise > 0.75 && detail_noise > 0.5 {
-        2 // Rocky outcrops
-    } else if biome_noise > 0.4 && biome_noise < 0.5 && detail_noise > 0.6 {
-        3 // Clay deposits
-    } else {
-        0 // Normal terrain
+        app
+            // Edits to world.yaml / voxel_types.yaml are picked up while running
+            .add_plugins(ConfigReloadPlugin)
+            .insert_resource(ConfigWatcher::new(config_file.clone(), registry.clone()))
+            .insert_resource(WorldConfig {
+                size_chunks,
+                chunk_size: config_file.world.chunk_size,
+                greedy_meshing: config_file.meshing.greedy,
+                meshing_enabled: config_file.meshing.enabled,
+                mesh_upload_budget: config_file.meshing.upload_budget,
+                lod: config_file.meshing.lod,
+                generator: config_file.generator.clone(),
+                biomes,
+                ores,
+                structures,
+                streaming: config_file.streaming,
+                fluids: config_file.fluids,
+                interaction: config_file.interaction,
+            })
+            .init_resource::<TerrainGenerators>()
+            .init_resource::<ChunkGenerationQueue>()
+            .init_resource::<GenerationProgress>()
+            .init_resource::<ChunkMeshTasks>()
+            .init_resource::<ChunkStreaming>()
+            .init_resource::<FluidSimulation>()
+            .add_message::<ChunkUnloaded>()
+            .add_message::<VoxelsEdited>()
+            .insert_resource(VoxelWorld::new(size_chunks))
+            .insert_resource(seed)
+            .insert_resource(registry)
+            // Mesh mode comes from world.yaml (surface_nets for smooth terrain, blocky for Minecraft-style)
+            .insert_resource(MeshSettings { mode: config_file.meshing.mode })
+            .insert_resource(VoxelDebugSettings {
+                wireframe: config_file.debug.wireframe,
+                chunk_borders: config_file.debug.chunk_borders,
+            })
+            .insert_resource(SaveState::new(persistence_settings.autosave_interval_secs))
+            .insert_resource(WorldStorage::new(slots::slot_dir(&persistence_settings.slot)))
+            .insert_resource(ActiveWorld {
+                metadata: WorldMetadata::new(&persistence_settings.slot, seed.0),
+                generator_version: GENERATOR_VERSION,
+            })
+            .insert_resource(persistence_settings)
+            .add_systems(Startup, setup_voxel_world)
+            // The camera is spawned in Startup, so restore the saved player afterwards
+            .add_systems(PostStartup, (
+                slots::restore_player_state,
+                // Queue the first streamed chunks around the restored position
+                streaming::request_chunks_around_player.run_if(streaming::streaming_enabled),
+            ).chain())
+            .add_systems(Update, (
+                apply_config_reloads,
+                // Streamed worlds load and unload chunks as the player moves
+                (
+                    streaming::request_chunks_around_player,
+                    streaming::unload_distant_chunks,
+                    streaming::poll_evicted_saves,
+                ).chain().run_if(streaming::streaming_enabled),
+                // Chunks are generated on the async compute pool and inserted as they finish
+                tasks::dispatch_generation_tasks,
+                tasks::collect_generated_chunks,
+                // Water flows toward edits a few voxels per step; changed chunks are remeshed below
+                (fluid::schedule_edited_fluids, fluid::step_fluids).chain().run_if(fluid::fluids_enabled),
+                // New chunks and edits are lit before meshing reads their light
+                lighting::update_lighting,
+                // Dirty chunks are meshed in the background and uploaded a few per frame
+                (mesh_tasks::update_chunk_lods, mesh_tasks::dispatch_mesh_tasks, mesh_tasks::upload_chunk_meshes)
+                    .chain()
+                    .run_if(|config: Res<WorldConfig>| config.meshing_enabled),
+                draw_chunk_borders.run_if(|debug: Res<VoxelDebugSettings>| debug.chunk_borders),
+            ).chain())
+            // Player edits: periodic background saves, F5 to save now, flush on exit
+            .add_systems(Update, (
+                slots::track_world_session,
+                autosave::manual_save_system,
+                autosave::autosave_system,
+                autosave::poll_save_task,
+            ).chain())
+            .add_systems(Last, autosave::save_on_exit);
+
+        // Wireframe rendering needs line polygon mode support, so only pull it in when asked for
+        if config_file.debug.wireframe {
+            app
+                .add_plugins(WireframePlugin::default())
+                .insert_resource(WireframeConfig { global: true, ..default() });
+        }
     }
 }
 
-fn is_cave(world_x: i32, world_y: i32, world_z: i32) -> bool {
-    let x = world_x as f32;
-    let y = world_y as f32;
-    let z = world_z as f32;
-
-    // 3D noise for caves
-    let cave_noise = fbm(x * 0.05 + y * 0.03, z * 0.05 + y * 0.02, 3);
-    let cave_threshold = 0.65 + (y / 64.0) * 0.1; // Caves more common at lower depths
-
-    cave_noise > cave_threshold && world_y > 2 && world_y < 45
-}
-
-fn is_dungeon_wall(world_x: i32, world_y: i32, world_z: i32) -> Option<VoxelType> {
-    // Create dungeon structures at specific locations
-    let dungeon_spacing = 96;  // Closer spacing for more dungeons
-    let dungeon_size = 20;
-    let dungeon_floor_y = 3;  // Dungeon floor level
-    let dungeon_height = 12; // Dungeon interior height
 
-    let dx = ((world_x % dungeon_spacing) + dungeon_spacing) % dungeon_spacing;
-    let dz = ((world_z % dungeon_spacing) + dungeon_spacing) % dungeon_spacing;
-
-    // Dungeon entrance staircase - visible from surface
-    // Located at corner of each dungeon (position 2-4, 2-4 in dungeon local coords)
-    let entrance_x = 2;
-    let entrance_z = 2;
-    let entrance_size = 3;
-
-    if dx >= entrance_x && dx < entrance_x + entrance_size &&
-       dz >= entrance_z && dz < entrance_z + entrance_size {
-        // Staircase from surface down to dungeon
-        // Stairs go from Y=dungeon_floor_y+1 up to Y=50 (well above terrain)
-        if world_y > dungeon_floor_y && world_y <= 50 {
-            let stair_local_x = dx - entrance_x;
-            let stair_local_z = dz - entrance_z;
-
-            // Create spiral/straight staircase walls
-            let is_stair_wall = stair_local_x == 0 || stair_local_x == entrance_size - 1 ||
-                               stair_local_z == 0 || stair_local_z == entrance_size - 1;
-
-            // Interior is air (the stairwell)
-            if is_stair_wall && stair_local_x != 1 && stair_local_z != 1 {
-                return Some(VoxelType::DungeonWall);
-            } else {
-                // Stairwell interior - just air for the shaft
-                return Some(VoxelType::Air);
-            }
+/// Values passed as `<flag> <value>` or `<flag>=<value>`, None for a flag without a value
+fn flag_values(args: impl Iterator<Item = String>, flag: &str) -> Vec<Option<String>> {
+    let prefix = format!("{flag}=");
+    let mut values = Vec::new();
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            values.push(args.next());
+        } else if let Some(value) = arg.strip_prefix(&prefix) {
+            values.push(Some(value.to_string()));
         }
     }
+    values
+}
 
-    // Check if we're in a dungeon area
-    if dx < dungeon_size && dz < dungeon_size && world_y >= dungeon_floor_y && world_y <= dungeon_floor_y + dungeon_height + 3 {
-        let local_x = dx;
-        let local_z = dz;
-        let local_y = world_y - dungeon_floor_y;
-
-        // Only generate dungeon structure within height bounds
-        if local_y > dungeon_height {
-            return None; // Above dungeon ceiling
-        }
-
-        // Create room walls
-        let is_outer_wall = local_x == 0 || local_x == dungeon_size - 1 ||
-                           local_z == 0 || local_z == dungeon_size - 1;
-
-        // Create inner walls forming corridors
-        let wall_at_x = (local_x % 8 == 0 || local_x % 8 == 1) && local_x > 0 && local_x < dungeon_size - 1;
-        let wall_at_z = (local_z % 8 == 0 || local_z % 8 == 1) && local_z > 0 && local_z < dungeon_size - 1;
-
-        // Doorways in inner walls
-        let doorway_x = local_z >= 3 && local_z <= 5 || local_z >= 11 && local_z <= 13 || local_z >= 17 && local_z <= 19;
-        let doorway_z = local_x >= 3 && local_x <= 5 || local_x >= 11 && local_x <= 13 || local_x >= 17 && local_x <= 19;
-
-        let is_inner_wall = (wall_at_x && !doorway_x) || (wall_at_z && !doorway_z);
-
-        // Floor and ceiling
-        let is_floor = local_y == 0;
-        let is_ceiling = local_y == dungeon_height;
-
-        // Pillars at intersections
-        let is_pillar = (local_x % 8 <= 1) && (local_z % 8 <= 1) &&
-                       local_x > 0 && local_x < dungeon_size - 1 &&
-                       local_z > 0 && local_z < dungeon_size - 1;
-
-        // Don't place ceiling over entrance
-        let over_entrance = dx >= entrance_x && dx < entrance_x + entrance_size &&
-                           dz >= entrance_z && dz < entrance_z + entrance_size;
-
-        if is_floor {
-            return Some(VoxelType::DungeonFloor);
-        } else if is_ceiling && !over_entrance {
-            return Some(VoxelType::DungeonFloor);
-        } else if is_outer_wall || is_inner_wall || is_pillar {
-            return Some(VoxelType::DungeonWall);
-        } else {
-            // Interior dungeon space - return Air so terrain doesn't fill it
-            return Some(VoxelType::Air);
+/// Seed passed as `--seed <n>` or `--seed=<n>`
+fn seed_from_args(args: impl Iterator<Item = String>) -> Option<u64> {
+    for value in flag_values(args, "--seed") {
+        match value.as_deref().map(str::parse::<u64>) {
+            Some(Ok(seed)) => return Some(seed),
+            _ => warn!("Ignoring invalid --seed {:?}, expected a number", value.unwrap_or_default()),
         }
     }
-
     None
 }
 
-/// Check if a tree should spawn at this location
-fn should_spawn_tree(world_x: i32, world_z: i32, terrain_height: i32) -> bool {
-    // Trees only spawn above water level on grass
-    if terrain_height <= WATER_LEVEL + 2 {
-        return false;
+/// Saved world passed as `--world <name>` or `--world=<name>`
+fn slot_from_args(args: impl Iterator<Item = String>) -> Option<String> {
+    for value in flag_values(args, "--world") {
+        match value {
+            Some(slot) => return Some(slot),
+            None => warn!("Ignoring --world without a world name"),
+        }
     }
-
-    // Use hash to determine tree placement - sparse distribution
-    let tree_noise = hash(world_x.wrapping_mul(7), world_z.wrapping_mul(13));
-
-    // About 2% chance per block
-    tree_noise > 0.98
-}
-
-/// Get tree height at this location (for consistent tree generation)
-fn get_tree_height(world_x: i32, world_z: i32) -> i32 {
-    let h = hash(world_x.wrapping_add(1000), world_z.wrapping_add(2000));
-    3 + (h * 3.0) as i32 // Height between 3 and 5
+    None
 }
 
-/// Check if a position is part of a tree trunk
-fn is_tree_trunk(world_x: i32, world_y: i32, world_z: i32, terrain_height: i32) -> bool {
-    if !should_spawn_tree(world_x, world_z, terrain_height) {
-        return false;
+#[allow(clippy::too_many_arguments)]
+fn setup_voxel_world(
+    mut commands: Commands,
+    mut world: ResMut<VoxelWorld>,
+    mut seed: ResMut<WorldSeed>,
+    mut active: ResMut<ActiveWorld>,
+    registry: Res<VoxelRegistry>,
+    storage: Res<WorldStorage>,
+    persistence_settings: Res<WorldPersistence>,
+    world_config: Res<WorldConfig>,
+    generators: Res<TerrainGenerators>,
+) {
+    let mut store = storage.lock();
+    if let Err(e) = persistence::adopt_legacy_save_dir(&mut store) {
+        warn!("Failed to move old save into slot '{}': {}", persistence_settings.slot, e);
     }
-    
-    let trunk_height = get_tree_height(world_x, world_z);
-    let trunk_bottom = terrain_height + 1;
-    let trunk_top = trunk_bottom + trunk_height;
-    
-    world_y >= trunk_bottom && world_y < trunk_top
-}
 
-/// Check if a position is part of tree leaves
-fn is_tree_leaves(world_x: i32, world_y: i32, world_z: i32) -> bool {
-    // Check nearby positions for tree trunks
-    let radius = 3;
-    
-    for dx in -radius..=radius {
-        for dz in -radius..=radius {
-            let check_x = world_x + dx;
-            let check_z = world_z + dz;
-            
-            let check_height = get_terrain_height(check_x, check_z);
-            
-            if should_spawn_tree(check_x, check_z, check_height) {
-                let trunk_height = get_tree_height(check_x, check_z);
-                let trunk_top = check_height + 1 + trunk_height;
-                let leaf_center_y = trunk_top - 1;
-                
-                // Spherical leaf shape
-                let dx_f = dx as f32;
-                let dz_f = dz as f32;
-                let dy_f = (world_y - leaf_center_y) as f32;
-                
-                let dist_sq = dx_f * dx_f + dy_f * dy_f * 1.5 + dz_f * dz_f;
-                let leaf_radius = 2.5;
-                
-                if dist_sq < leaf_radius * leaf_radius {
-                    // Don't place leaves where trunk is
-                    if !(dx == 0 && dz == 0 && world_y < trunk_top) {
-                        return true;
-                    }
-                }
+    // Slot metadata: player position, inventory and play time
+    // A slot made with `slots::create_slot` has metadata but no chunks yet, and keeps its seed
+    match slots::read_metadata(store.dir()) {
+        Ok(metadata) => {
+            if metadata.seed != seed.0 {
+                info!("Using world '{}' seed {} instead of {}", metadata.name, metadata.seed, seed.0);
             }
+            *seed = WorldSeed(metadata.seed);
+            active.metadata = metadata;
         }
+        Err(_) => info!("Starting new world '{}'", persistence_settings.slot),
     }
-    
-    false
-}
-
-// Water level constant - areas below this height will be filled with water
-const WATER_LEVEL: i32 = 18;
-
-// Debug flat world toggle (disabled by default)
-const DEBUG_FLAT_WORLD: bool = false;
 
-fn setup_voxel_world(
-    mut world: ResMut<VoxelWorld>,
-    persistence_settings: Res<WorldPersistence>,
-) {
     // Try to load saved world unless force_regenerate is set
-    if !persistence_settings.force_regenerate && persistence::saved_world_exists() {
+    let mut loaded_from_disk = false;
+    if !persistence_settings.force_regenerate && persistence::saved_world_exists(&store) {
         info!("Loading saved world from disk...");
-        match persistence::load_world() {
-            Ok(loaded_world) => {
+        match persistence::load_world(&mut store, &registry) {
+            Ok((loaded_world, header)) => {
+                if header.seed != seed.0 {
+                    info!("Using the saved world's seed {} instead of {}", header.seed, seed.0);
+                }
                 *world = loaded_world;
-                info!("World loaded successfully!");
-                return;
+                *seed = WorldSeed(header.seed);
+                active.metadata.seed = header.seed;
+                active.generator_version = header.generator_version;
+                loaded_from_disk = true;
             }
             Err(e) => {
-                warn!("Failed to load saved world: {}. Generating new world...", e);
+                error!("Failed to load saved world: {}. Generating new world...", e);
+                // Keep the unreadable save instead of overwriting it with the new world
+                match persistence::set_aside_saved_world(&mut store) {
+                    Ok(path) => warn!("Moved unreadable save to {}", path.display()),
+                    Err(e) => error!("Failed to move unreadable save aside: {}", e),
+                }
             }
         }
     }
 
-    info!("Generating new world...");
-    let start_time = std::time::Instant::now();
-
-    // Generate extensive procedural terrain
-    let chunk_positions: Vec<IVec3> = world.all_chunk_positions().collect();
-    let mut total_sand = 0u32;
-    let mut total_dungeon_wall = 0u32;
-    let mut total_dungeon_floor = 0u32;
-
-    for chunk_pos in chunk_positions {
-        let mut chunk = Chunk::new(chunk_pos);
-        let chunk_world_x = chunk_pos.x * CHUNK_SIZE_I32;
-        let chunk_world_z = chunk_pos.z * CHUNK_SIZE_I32;
-        let chunk_world_y = chunk_pos.y * CHUNK_SIZE_I32;
-        let mut water_count = 0u32;
-        let mut sand_count = 0u32;
-        let mut dungeon_wall_count = 0u32;
-        let mut dungeon_floor_count = 0u32;
-
-        for x in 0..CHUNK_SIZE {
-            for z in 0..CHUNK_SIZE {
-                let world_x = chunk_world_x + x as i32;
-                let world_z = chunk_world_z + z as i32;
-
-                let terrain_height = get_terrain_height(world_x, world_z);
-                let biome = get_biome(world_x, world_z);
-
-                for y in 0..CHUNK_SIZE {
-                    let world_y = chunk_world_y + y as i32;
-
-                    // Check for dungeon structures first
-                    if let Some(dungeon_voxel) = is_dungeon_wall(world_x, world_y, world_z) {
-                        match dungeon_voxel {
-                            VoxelType::DungeonWall => dungeon_wall_count += 1,
-                            VoxelType::DungeonFloor => dungeon_floor_count += 1,
-                            _ => {}
-                        }
-                        chunk.set(UVec3::new(x as u32, y as u32, z as u32), dungeon_voxel);
-                        continue;
-                    }
-
-                    // Check for caves
-                    // Caves disabled for debugging blue holes
-                    // if is_cave(world_x, world_y, world_z) && world_y < terrain_height - 3 {
-                    //     // Fill caves below water level with water
-                    //     let voxel = if world_y <= WATER_LEVEL {
-                    //         VoxelType::Water
-                    //     } else {
-                    //         VoxelType::Air
-                    //     };
-                    //     if voxel == VoxelType::Water {
-                    //         water_count += 1;
-                    //     }
-                    //     chunk.set(UVec3::new(x as u32, y as u32, z as u32), voxel);
-                    //     continue;
-                    // }
-
-                    // Check for tree trunks
-                    if is_tree_trunk(world_x, world_y, world_z, terrain_height) {
-                        chunk.set(UVec3::new(x as u32, y as u32, z as u32), VoxelType::Wood);
-                        continue;
-                    }
-
-                    // Check for tree leaves
-                    if world_y > terrain_height && is_tree_leaves(world_x, world_y, world_z) {
-                        chunk.set(UVec3::new(x as u32, y as u32, z as u32), VoxelType::Leaves);
-                        continue;
-                    }
-
-                    let voxel = if DEBUG_FLAT_WORLD {
-                        if world_y <= 12 { VoxelType::TopSoil } else { VoxelType::Air }
-                    } else if world_y > terrain_height {
-                        // Above terrain - check if below water level (lakes/rivers)
-                        if world_y <= WATER_LEVEL {
-                            VoxelType::Water
-                        } else {
-                            VoxelType::Air
-                        }
-                    } else if world_y == 0 {
-                        VoxelType::Bedrock
-                    } else if world_y <= 3 {
-                        // Deep bedrock layer with some rock
-                        if hash(world_x, world_z + world_y * 1000) > 0.3 {
-                            VoxelType::Bedrock
-                        } else {
-                            VoxelType::Rock
-                        }
-                    } else {
-                        // Determine block based on depth from surface and biome
-                        let depth = terrain_height - world_y;
-
-                        // Near water, use sand instead of topsoil (beaches and shorelines)
-                        // Beach area: terrain within 2 blocks above water level only
-                        let near_water = terrain_height <= WATER_LEVEL + 2;
+    // A saved world keeps the generator, biomes, ore deposits and structures it was created with
+    let settings = match &active.metadata.generator {
+        Some(saved) if loaded_from_disk => saved.clone(),
+        _ => world_config.generator.clone(),
+    };
+    let biomes = match &active.metadata.biomes {
+        Some(saved) if loaded_from_disk => saved.clone(),
+        _ => world_config.biomes.clone(),
+    };
+    let ores = match &active.metadata.ores {
+        Some(saved) if loaded_from_disk => saved.clone(),
+        _ => world_config.ores.clone(),
+    };
+    let structures = match &active.metadata.structures {
+        Some(saved) if loaded_from_disk => saved.clone(),
+        _ => world_config.structures.clone(),
+    };
+    let ctx = GeneratorContext {
+        seed: *seed,
+        settings: &settings,
+        registry: &registry,
+        biomes: &biomes,
+        ores: &ores,
+        structures: &structures,
+    };
+    let generator = generators.create(&ctx).unwrap_or_else(|e| {
+        error!("{}. Using the noise generator", e);
+        WorldGenerator::new(NoiseGenerator::new(*seed))
+    });
+    commands.insert_resource(generator.clone());
+    active.metadata.generator = Some(settings.clone());
+    active.metadata.ores = Some(ores);
+    active.metadata.structures = Some(structures);
+
+    // Grass and creature spawning look up biomes too, whatever the generator
+    let biome_map = BiomeMap::new(*seed, &biomes, &registry).unwrap_or_else(|e| {
+        error!("{}. Using the built-in biomes", e);
+        BiomeMap::builtin(*seed)
+    });
+    commands.insert_resource(biome_map);
+    active.metadata.biomes = Some(biomes);
+
+    // A streamed world loads saved chunks and generates the rest around the player as it moves
+    if world_config.streaming.enabled {
+        world.set_bounds(BoundsPolicy::Columns);
+        let storage = (!persistence_settings.force_regenerate).then(|| storage.clone());
+        info!(
+            "Streaming chunks within {} chunks of the player with the '{}' generator (seed {})",
+            world_config.streaming.horizontal_radius, settings.kind, seed.0
+        );
+        commands.insert_resource(GenerationProgress::default());
+        commands.insert_resource(ChunkGenerationQueue::streamed(storage));
+    } else {
+        queue_fixed_world(&mut commands, &mut world, &mut store, loaded_from_disk, &settings.kind, seed.0);
+    }
 
-                        match biome {
-                            1 => {
-                                // Sandy biome
-                                if depth <= 4 {
-                                    VoxelType::Sand
-                                } else if depth <= 8 {
-                                    VoxelType::SubSoil
-                                } else {
-                                    VoxelType::Rock
-                                }
-                            }
-                            2 => {
-                                // Rocky biome
-                                if depth <= 1 {
-                                    VoxelType::Rock
-                                } else if depth <= 3 {
-                                    VoxelType::SubSoil
-                                } else {
-                                    VoxelType::Rock
-                                }
-                            }
-                            3 => {
-                                // Clay deposits
-                                if depth <= 2 {
-                                    VoxelType::TopSoil
-                                } else if depth <= 6 {
-                                    VoxelType::Clay
-                                } else if depth <= 10 {
-                                    VoxelType::SubSoil
-                                } else {
-                                    VoxelType::Rock
-                                }
-                            }
-                            _ => {
-                                // Normal terrain - use sand near water (beaches)
-                                if near_water {
-                                    if depth <= 2 {
-                                        VoxelType::Sand
-                                    } else if depth <= 5 {
-                                        VoxelType::SubSoil
-                                    } else {
-                                        VoxelType::Rock
-                                    }
-                                } else if depth == 0 {
-                                    VoxelType::TopSoil
-                                } else if depth <= 4 {
-                                    VoxelType::SubSoil
-                                } else {
-                                    VoxelType::Rock
-                                }
-                            }
-                        }
-                    };
+    // Write the header and metadata now so a new world shows up in the slot list.
+    // Generated chunks are saved once generation finishes.
+    active.metadata.seed = seed.0;
+    if persistence_settings.auto_save {
+        let header = SaveHeader::new(world.to_data(), seed.0, active.generator_version, &registry);
+        if let Err(e) = persistence::save_snapshot(&mut store, &header, &active.snapshot(), Vec::new()) {
+            warn!("Failed to save world: {}", e);
+        }
+    }
+}
 
-                    match voxel {
-                        VoxelType::Water => water_count += 1,
-                        VoxelType::Sand => sand_count += 1,
-                        _ => {}
-                    }
-                    chunk.set(UVec3::new(x as u32, y as u32, z as u32), voxel);
+/// Read the saved chunks of a fixed-size world right away and queue the missing ones for
+/// generation in the background, nearest to the player first
+fn queue_fixed_world(
+    commands: &mut Commands,
+    world: &mut VoxelWorld,
+    store: &mut RegionStore,
+    loaded_from_disk: bool,
+    generator_name: &str,
+    seed: u64,
+) {
+    let mut to_generate: Vec<IVec3> = Vec::new();
+    let mut loaded_count = 0;
+    for chunk_pos in world.all_chunk_positions().collect::<Vec<_>>() {
+        if loaded_from_disk {
+            match store.load_chunk(chunk_pos) {
+                Ok(Some(chunk)) => {
+                    world.insert_chunk(chunk);
+                    loaded_count += 1;
+                    continue;
                 }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to load chunk {:?}: {}. Regenerating it...", chunk_pos, e),
             }
         }
-
-        chunk.mark_dirty();
-        world.insert_chunk(chunk);
-
-        total_sand += sand_count;
-        total_dungeon_wall += dungeon_wall_count;
-        total_dungeon_floor += dungeon_floor_count;
-
-        if dungeon_wall_count > 0 || dungeon_floor_count > 0 {
-            info!("Chunk {:?} (world pos {:?}) has {} dungeon walls, {} dungeon floors",
-                  chunk_pos,
-                  IVec3::new(chunk_world_x, chunk_world_y, chunk_world_z),
-                  dungeon_wall_count, dungeon_floor_count);
-        }
+        to_generate.push(chunk_pos);
     }
 
-    let generation_time = start_time.elapsed();
-    info!("=== WORLD GENERATION SUMMARY ===");
-    info!("Generation time: {:.2}s", generation_time.as_secs_f32());
-    info!("Total sand blocks: {}", total_sand);
-    info!("Total dungeon wall blocks: {}", total_dungeon_wall);
-    info!("Total dungeon floor blocks: {}", total_dungeon_floor);
-    info!("Dungeons should be at positions like (0-19, 3-18, 0-19), (96-115, 3-18, 96-115), etc.");
-    info!("Sand appears near water (terrain height <= 24) and in sandy biomes");
+    info!(
+        "Loaded {} chunks, generating {} with the '{}' generator (seed {})...",
+        loaded_count, to_generate.len(), generator_name, seed
+    );
+    commands.insert_resource(GenerationProgress {
+        loaded: loaded_count,
+        to_generate: to_generate.len(),
+        ..default()
+    });
+    commands.insert_resource(ChunkGenerationQueue::new(to_generate));
+}
 
-    // Save world to disk if auto_save is enabled
-    if persistence_settings.auto_save {
-        info!("Saving world to disk...");
-        match persistence::save_world(&world) {
-            Ok(()) => info!("World saved successfully!"),
-            Err(e) => warn!("Failed to save world: {}", e),
+/// Apply hot-reloaded config files to the running world
+fn apply_config_reloads(
+    mut reloaded: MessageReader<ConfigReloaded>,
+    mut world: ResMut<VoxelWorld>,
+    mut registry: ResMut<VoxelRegistry>,
+    mut world_config: ResMut<WorldConfig>,
+    mut mesh_settings: ResMut<MeshSettings>,
+    mut debug: ResMut<VoxelDebugSettings>,
+    mut wireframe: Option<ResMut<WireframeConfig>>,
+) {
+    for message in reloaded.read() {
+        match message {
+            ConfigReloaded::VoxelTypes { registry: new_registry, diff } => {
+                // Hardness and tool changes apply on the next break attempt through the resource
+                new_registry.install();
+                *registry = new_registry.clone();
+
+                if diff.requires_remesh() {
+                    // Opacity and emission changes move light around, so chunks are relit
+                    // and remeshed as they finish
+                    info!("Voxel visuals changed for {:?}, relighting and remeshing all chunks", diff.visual_changed);
+                    world.relight_all();
+                }
+            }
+            ConfigReloaded::World { config, diff } => {
+                if diff.requires_restart {
+                    warn!(
+                        "World size {:?} / chunk size {} / seed {:?} changes only apply after a restart",
+                        config.size_chunks(),
+                        config.world.chunk_size,
+                        config.world.seed
+                    );
+                }
+                if let Some(enabled) = diff.meshing_enabled {
+                    world_config.meshing_enabled = enabled;
+                }
+                if let Some(greedy) = diff.greedy {
+                    world_config.greedy_meshing = greedy;
+                    world.mark_all_dirty();
+                }
+                if let Some(budget) = diff.upload_budget {
+                    world_config.mesh_upload_budget = budget;
+                }
+                if let Some(fluids) = diff.fluids {
+                    world_config.fluids = fluids;
+                }
+                if let Some(interaction) = diff.interaction {
+                    world_config.interaction = interaction;
+                }
+                if let Some(lod) = diff.lod {
+                    // Chunks move to their new level of detail on the next frame
+                    world_config.lod = lod;
+                }
+                if let Some(mode) = diff.mesh_mode {
+                    mesh_settings.mode = mode;
+                    world.mark_all_dirty();
+                }
+                if let Some(chunk_borders) = diff.chunk_borders {
+                    debug.chunk_borders = chunk_borders;
+                }
+                if let Some(enabled) = diff.wireframe {
+                    debug.wireframe = enabled;
+                    match wireframe.as_mut() {
+                        Some(wireframe) => wireframe.global = enabled,
+                        // WireframePlugin is only added at startup when it was enabled
+                        None if enabled => warn!("Wireframe needs a restart with debug.wireframe enabled"),
+                        None => {}
+                    }
+                }
+            }
         }
     }
 }
 
-fn mesh_dirty_chunks_system(
-    mut commands: Commands,
-    mut world: ResMut<VoxelWorld>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    blocky_material: Res<VoxelMaterial>,
-    triplanar_material: Res<TriplanarMaterialHandle>,
-    water_material: Res<crate::rendering::materials::WaterMaterial>,
-    mesh_settings: Res<MeshSettings>,
+/// Draw chunk boundary boxes around the camera (debug.chunk_borders)
+fn draw_chunk_borders(
+    world: Res<VoxelWorld>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+    mut gizmos: Gizmos,
 ) {
-    // Collect dirty chunks first to avoid borrowing issues
-    let dirty_chunks: Vec<IVec3> = world.dirty_chunks().collect();
-
-    for chunk_pos in dirty_chunks {
-        // Step 1: Generate mesh data using immutable borrow
-        let mesh_result = if let Some(chunk) = world.get_chunk(chunk_pos) {
-            generate_chunk_mesh_with_mode(chunk, &world, mesh_settings.mode)
-        } else {
-            continue;
-        };
-
-        // Step 2: Update chunk state using mutable borrow
-        if let Some(chunk) = world.get_chunk_mut(chunk_pos) {
-            // Clear dirty flag
-            chunk.clear_dirty();
-
-            let world_pos = VoxelWorld::chunk_to_world(chunk_pos);
+    let Ok(camera) = camera_query.single() else {
+        return;
+    };
 
-            // Handle solid mesh
-            if mesh_result.solid.is_empty() {
-                if let Some(entity) = chunk.mesh_entity() {
-                    commands.entity(entity).despawn();
-                    chunk.clear_mesh_entity();
-                }
-            } else {
-                let mesh = mesh_result.solid.into_mesh();
-                let mesh_handle = meshes.add(mesh);
+    let center = VoxelWorld::world_to_chunk(camera.translation.floor().as_ivec3());
+    let size = CHUNK_SIZE_I32 as f32;
 
-                if let Some(entity) = chunk.mesh_entity() {
-                    commands.entity(entity).insert(Mesh3d(mesh_handle));
-                } else {
-                    // Spawn with appropriate material based on mesh mode
-                    let entity = match mesh_settings.mode {
-                        MeshMode::Blocky => {
-                            commands.spawn((
-                                Mesh3d(mesh_handle),
-                                MeshMaterial3d(blocky_material.handle.clone()),
-                                Transform::from_xyz(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32),
-                                crate::voxel::meshing::ChunkMesh { chunk_position: chunk_pos },
-                            )).id()
-                        }
-                        MeshMode::SurfaceNets => {
-                            commands.spawn((
-                                Mesh3d(mesh_handle),
-                                MeshMaterial3d(triplanar_material.handle.clone()),
-                                Transform::from_xyz(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32),
-                                crate::voxel::meshing::ChunkMesh { chunk_position: chunk_pos },
-                            )).id()
-                        }
-                    };
-                    chunk.set_mesh_entity(entity);
-                }
-            }
-            
-            // Handle water mesh
-            if mesh_result.water.is_empty() {
-                if let Some(entity) = chunk.water_mesh_entity() {
-                    commands.entity(entity).despawn();
-                    chunk.clear_water_mesh_entity();
+    // Only the chunks close to the camera, drawing the whole world every frame is too slow
+    for dx in -2..=2 {
+        for dy in -1..=1 {
+            for dz in -2..=2 {
+                let chunk_pos = center + IVec3::new(dx, dy, dz);
+                if !world.chunk_exists(chunk_pos) {
+                    continue;
                 }
-            } else {
-                let water_mesh = mesh_result.water.into_mesh();
-                let water_mesh_handle = meshes.add(water_mesh);
 
-                if let Some(entity) = chunk.water_mesh_entity() {
-                    commands.entity(entity).insert(Mesh3d(water_mesh_handle));
-                } else {
-                    let entity = commands.spawn((
-                        Mesh3d(water_mesh_handle),
-                        MeshMaterial3d(water_material.handle.clone()),
-                        Transform::from_xyz(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32),
-                        crate::voxel::meshing::ChunkMesh { chunk_position: chunk_pos },
-                    )).id();
-                    chunk.set_water_mesh_entity(entity);
-                }
+                let origin = VoxelWorld::chunk_to_world(chunk_pos).as_vec3();
+                gizmos.cuboid(
+                    Transform::from_translation(origin + Vec3::splat(size * 0.5)).with_scale(Vec3::splat(size)),
+                    Color::srgba(1.0, 0.6, 0.1, 0.6),
+                );
             }
         }
     }
 }
-
@@ -0,0 +1,359 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::config::loader::{load_config, ConfigError};
+use crate::entity::ItemType;
+use crate::voxel::lighting::MAX_LIGHT;
+use crate::voxel::types::{ToolType, VoxelType, VoxelTypeInfo, BUILTIN_VOXEL_NAMES};
+
+/// Default location of the voxel type definitions
+pub const VOXEL_TYPES_CONFIG_PATH: &str = "assets/config/voxel_types.yaml";
+
+/// Voxel ids are stored as u8
+pub const MAX_VOXEL_TYPES: usize = 256;
+
+/// Number of material slots in the triplanar terrain shader (grass, rock, sand, dirt)
+pub const SPLAT_MATERIAL_COUNT: u8 = 4;
+
+/// Typed contents of `voxel_types.yaml`
+#[derive(Deserialize, Clone, Debug)]
+pub struct VoxelTypesFile {
+    pub voxel_types: Vec<VoxelTypeDef>,
+}
+
+/// A single entry in `voxel_types.yaml`
+#[derive(Deserialize, Clone, Debug)]
+pub struct VoxelTypeDef {
+    pub id: String,
+    #[serde(default)]
+    pub solid: bool,
+    /// Defaults to `!solid`
+    #[serde(default)]
+    pub transparent: Option<bool>,
+    #[serde(default)]
+    pub liquid: bool,
+    #[serde(default)]
+    pub hardness: f32,
+    #[serde(default)]
+    pub tool_required: ToolType,
+    #[serde(default)]
+    pub atlas_index: u8,
+    #[serde(default)]
+    pub atlas_top: Option<u8>,
+    #[serde(default)]
+    pub atlas_bottom: Option<u8>,
+    #[serde(default)]
+    pub atlas_side: Option<u8>,
+    #[serde(default)]
+    pub splat_material: Option<u8>,
+    #[serde(default)]
+    pub splat_overlay: bool,
+    #[serde(default)]
+    pub light_emission: u8,
+    #[serde(default)]
+    pub drop: Option<ItemType>,
+}
+
+impl VoxelTypeDef {
+    fn to_info(&self) -> VoxelTypeInfo {
+        VoxelTypeInfo {
+            name: self.id.clone(),
+            solid: self.solid,
+            transparent: self.transparent.unwrap_or(!self.solid),
+            liquid: self.liquid,
+            hardness: self.hardness,
+            tool_required: self.tool_required,
+            atlas_index: self.atlas_index,
+            atlas_top: self.atlas_top.unwrap_or(self.atlas_index),
+            atlas_bottom: self.atlas_bottom.unwrap_or(self.atlas_index),
+            atlas_side: self.atlas_side.unwrap_or(self.atlas_index),
+            splat_material: self.splat_material,
+            splat_overlay: self.splat_overlay,
+            light_emission: self.light_emission,
+            drop: self.drop,
+        }
+    }
+}
+
+/// All known voxel types, indexed by numeric id
+#[derive(Resource, Clone, Debug)]
+pub struct VoxelRegistry {
+    types: Vec<VoxelTypeInfo>,
+    ids: HashMap<String, VoxelType>,
+}
+
+impl VoxelRegistry {
+    /// Registry containing only the built-in types with their default properties
+    pub fn builtin() -> Self {
+        let builtin = |name: &str, solid: bool, hardness: f32, tool_required: ToolType, atlas_index: u8, splat_material: Option<u8>| {
+            VoxelTypeInfo {
+                name: name.to_string(),
+                solid,
+                transparent: !solid,
+                liquid: false,
+                hardness,
+                tool_required,
+                atlas_index,
+                atlas_top: atlas_index,
+                atlas_bottom: atlas_index,
+                atlas_side: atlas_index,
+                splat_material,
+                splat_overlay: false,
+                light_emission: 0,
+                drop: None,
+            }
+        };
+
+        let types = vec![
+            builtin("air", false, 0.0, ToolType::None, 0, None),
+            VoxelTypeInfo {
+                // Grass top, dirt bottom, grass side texture in slot 7
+                atlas_bottom: 1,
+                atlas_side: 7,
+                ..builtin("topsoil", true, 1.0, ToolType::Shovel, 0, Some(0))
+            },
+            builtin("subsoil", true, 1.5, ToolType::Shovel, 1, Some(3)),
+            builtin("rock", true, 4.0, ToolType::Pickaxe, 2, Some(1)),
+            builtin("bedrock", true, -1.0, ToolType::None, 3, Some(1)),
+            builtin("sand", true, 0.8, ToolType::Shovel, 4, Some(2)),
+            builtin("clay", true, 2.0, ToolType::Shovel, 5, Some(3)),
+            VoxelTypeInfo {
+                liquid: true,
+                ..builtin("water", false, 0.0, ToolType::None, 6, None)
+            },
+            builtin("wood", true, 2.0, ToolType::None, 8, Some(3)),
+            VoxelTypeInfo {
+                transparent: true,
+                ..builtin("leaves", true, 0.2, ToolType::None, 9, Some(3))
+            },
+            builtin("dungeon_wall", true, 6.0, ToolType::Pickaxe, 10, Some(1)),
+            builtin("dungeon_floor", true, 6.0, ToolType::Pickaxe, 11, Some(1)),
+            VoxelTypeInfo {
+                liquid: true,
+                ..builtin("flowing_water", false, 0.0, ToolType::None, 6, None)
+            },
+            VoxelTypeInfo {
+                // No tile of its own yet, drawn with the wood texture
+                light_emission: MAX_LIGHT,
+                ..builtin("torch", true, 0.1, ToolType::None, 8, Some(3))
+            },
+            // Ores sit in rock, so surface nets draw their tile over the rock splat
+            VoxelTypeInfo {
+                splat_overlay: true,
+                drop: Some(ItemType::Coal),
+                ..builtin("coal_ore", true, 4.5, ToolType::Pickaxe, 12, Some(1))
+            },
+            VoxelTypeInfo {
+                splat_overlay: true,
+                drop: Some(ItemType::IronOre),
+                ..builtin("iron_ore", true, 5.5, ToolType::Pickaxe, 13, Some(1))
+            },
+            VoxelTypeInfo {
+                splat_overlay: true,
+                drop: Some(ItemType::GoldOre),
+                ..builtin("gold_ore", true, 6.0, ToolType::Pickaxe, 14, Some(1))
+            },
+        ];
+
+        let ids = BUILTIN_VOXEL_NAMES
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.to_string(), VoxelType(id as u8)))
+            .collect();
+
+        Self { types, ids }
+    }
+
+    /// Build a registry from parsed YAML. Built-in ids keep their numeric ids and are
+    /// overridden in place; new ids are appended in file order.
+    pub fn from_file(file: &VoxelTypesFile) -> Result<Self, ConfigError> {
+        let mut registry = Self::builtin();
+        let mut seen = HashSet::new();
+
+        for def in &file.voxel_types {
+            if !seen.insert(def.id.as_str()) {
+                return Err(ConfigError::DuplicateVoxelId(def.id.clone()));
+            }
+            if let Some(slot) = def.splat_material
+                && slot >= SPLAT_MATERIAL_COUNT
+            {
+                return Err(ConfigError::InvalidSplatMaterial { id: def.id.clone(), slot });
+            }
+            if def.light_emission > MAX_LIGHT {
+                return Err(ConfigError::InvalidLightEmission { id: def.id.clone(), level: def.light_emission });
+            }
+
+            let info = def.to_info();
+            match registry.ids.get(&def.id) {
+                Some(voxel) => registry.types[voxel.0 as usize] = info,
+                None => {
+                    if registry.types.len() >= MAX_VOXEL_TYPES {
+                        return Err(ConfigError::TooManyVoxelTypes(MAX_VOXEL_TYPES));
+                    }
+                    let voxel = VoxelType(registry.types.len() as u8);
+                    registry.ids.insert(def.id.clone(), voxel);
+                    registry.types.push(info);
+                }
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Load the registry from YAML, falling back to the built-in types if the file is missing
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            info!("No voxel type config at {}, using built-in types", path.display());
+            return Ok(Self::builtin());
+        }
+
+        let file: VoxelTypesFile = load_config(path)?;
+        Self::from_file(&file)
+    }
+
+    /// Properties of a voxel type (unknown ids behave like air)
+    pub fn get(&self, voxel: VoxelType) -> &VoxelTypeInfo {
+        self.types.get(voxel.0 as usize).unwrap_or(&self.types[0])
+    }
+
+    /// Look up the numeric id of a YAML type id
+    pub fn id(&self, name: &str) -> Option<VoxelType> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, voxel: VoxelType) -> &str {
+        &self.get(voxel).name
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (VoxelType, &VoxelTypeInfo)> + '_ {
+        self.types
+            .iter()
+            .enumerate()
+            .map(|(id, info)| (VoxelType(id as u8), info))
+    }
+
+    /// Publish this registry to the lookup table behind the `Voxel` trait
+    pub fn install(&self) {
+        let air = VoxelProperties::pack(&self.types[0]);
+        for (id, slot) in LOOKUP.iter().enumerate() {
+            let packed = self.types.get(id).map(VoxelProperties::pack).unwrap_or(air);
+            slot.store(packed.0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for VoxelRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+// =============================================================================
+// Hot-path lookup table
+// =============================================================================
+
+// The Voxel trait is called per voxel from meshing and physics, which have no access to
+// the ECS resource, so the installed registry is mirrored into a packed lock-free table.
+static LOOKUP: LazyLock<[AtomicU64; MAX_VOXEL_TYPES]> = LazyLock::new(|| {
+    let registry = VoxelRegistry::builtin();
+    let air = VoxelProperties::pack(&registry.types[0]);
+    std::array::from_fn(|id| {
+        let packed = registry.types.get(id).map(VoxelProperties::pack).unwrap_or(air);
+        AtomicU64::new(packed.0)
+    })
+});
+
+const SOLID_BIT: u64 = 1 << 32;
+const TRANSPARENT_BIT: u64 = 1 << 33;
+const LIQUID_BIT: u64 = 1 << 34;
+const OVERLAY_BIT: u64 = 1 << 35;
+const SPLAT_SHIFT: u64 = 40;
+const NO_SPLAT: u64 = 0xFF;
+const LIGHT_SHIFT: u64 = 48;
+
+/// Packed copy of the properties meshing and lighting need: atlas indices, flags, splat slot
+/// and overlay, and light emission
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoxelProperties(u64);
+
+impl VoxelProperties {
+    fn pack(info: &VoxelTypeInfo) -> Self {
+        let mut packed = info.atlas_index as u64
+            | (info.atlas_top as u64) << 8
+            | (info.atlas_bottom as u64) << 16
+            | (info.atlas_side as u64) << 24;
+        if info.solid {
+            packed |= SOLID_BIT;
+        }
+        if info.transparent {
+            packed |= TRANSPARENT_BIT;
+        }
+        if info.liquid {
+            packed |= LIQUID_BIT;
+        }
+        if info.splat_overlay {
+            packed |= OVERLAY_BIT;
+        }
+        packed |= info.splat_material.map(|s| s as u64).unwrap_or(NO_SPLAT) << SPLAT_SHIFT;
+        packed |= (info.light_emission as u64) << LIGHT_SHIFT;
+        Self(packed)
+    }
+
+    pub fn solid(self) -> bool {
+        self.0 & SOLID_BIT != 0
+    }
+
+    pub fn transparent(self) -> bool {
+        self.0 & TRANSPARENT_BIT != 0
+    }
+
+    pub fn liquid(self) -> bool {
+        self.0 & LIQUID_BIT != 0
+    }
+
+    pub fn atlas_index(self) -> u8 {
+        self.0 as u8
+    }
+
+    pub fn atlas_top(self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    pub fn atlas_bottom(self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    pub fn atlas_side(self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    pub fn splat_material(self) -> Option<u8> {
+        let slot = (self.0 >> SPLAT_SHIFT) & 0xFF;
+        if slot == NO_SPLAT { None } else { Some(slot as u8) }
+    }
+
+    pub fn splat_overlay(self) -> bool {
+        self.0 & OVERLAY_BIT != 0
+    }
+
+    pub fn light_emission(self) -> u8 {
+        (self.0 >> LIGHT_SHIFT) as u8 & 0x0F
+    }
+}
+
+/// Properties of a voxel type from the installed registry
+pub fn properties(voxel: VoxelType) -> VoxelProperties {
+    VoxelProperties(LOOKUP[voxel.0 as usize].load(Ordering::Relaxed))
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy::tasks::{futures::check_ready, IoTaskPool, Task};
+use std::io;
+use crate::camera::controller::PlayerCamera;
+use crate::config::world::StreamingSection;
+use crate::voxel::chunk::Chunk;
+use crate::voxel::generation::tasks::{ChunkGenerationQueue, GenerationProgress};
+use crate::voxel::persistence::WorldStorage;
+use crate::voxel::plugin::WorldConfig;
+use crate::voxel::world::VoxelWorld;
+
+/// Sent after a chunk left the streaming radius and was removed from the world
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ChunkUnloaded(pub IVec3);
+
+/// Streaming state: the area loaded around the player and the background save of unloaded chunks
+#[derive(Resource, Default)]
+pub struct ChunkStreaming {
+    /// Player chunk the loaded area was last built around
+    center: Option<IVec3>,
+    save_task: Option<Task<io::Result<usize>>>,
+    /// Edited chunks were unloaded since the last save started. A failed save is retried
+    /// once more chunks are unloaded.
+    unsaved: bool,
+}
+
+/// Run condition: the world is streamed around the player
+pub fn streaming_enabled(world_config: Res<WorldConfig>) -> bool {
+    world_config.streaming.enabled
+}
+
+/// Whether `pos` is within the streaming radius of `center`, widened by `margin` chunks
+pub fn in_radius(settings: &StreamingSection, center: IVec3, pos: IVec3, margin: i32) -> bool {
+    let offset = pos - center;
+    let radius = settings.horizontal_radius + margin;
+    offset.x * offset.x + offset.z * offset.z <= radius * radius
+        && offset.y.abs() <= settings.vertical_radius + margin
+}
+
+/// Queue the chunks within the radius of the player once it enters a new chunk, nearest
+/// first. Chunks still waiting to be saved are put back directly.
+pub fn request_chunks_around_player(
+    mut streaming: ResMut<ChunkStreaming>,
+    mut queue: ResMut<ChunkGenerationQueue>,
+    mut progress: ResMut<GenerationProgress>,
+    mut world: ResMut<VoxelWorld>,
+    storage: Res<WorldStorage>,
+    world_config: Res<WorldConfig>,
+    camera_query: Query<&Transform, With<PlayerCamera>>,
+) {
+    let Ok(camera) = camera_query.single() else {
+        return;
+    };
+    let center = VoxelWorld::world_to_chunk(camera.translation.floor().as_ivec3());
+    if streaming.center == Some(center) {
+        return;
+    }
+    streaming.center = Some(center);
+
+    let settings = &world_config.streaming;
+    let (h, v) = (settings.horizontal_radius, settings.vertical_radius);
+    let mut requested = 0;
+    for x in -h..=h {
+        for z in -h..=h {
+            for y in -v..=v {
+                let pos = center + IVec3::new(x, y, z);
+                if !in_radius(settings, center, pos, 0) || !world.chunk_in_bounds(pos) || world.chunk_exists(pos) {
+                    continue;
+                }
+                // Still queued for saving, which writes this same snapshot
+                if let Some(data) = storage.pending_chunk(pos) {
+                    world.insert_chunk(Chunk::from_data(data));
+                } else if queue.request(pos) {
+                    requested += 1;
+                }
+            }
+        }
+    }
+
+    // Chunks the player walked away from before they were started
+    let dropped = queue.retain_pending(|pos| in_radius(settings, center, pos, settings.unload_margin));
+    progress.to_generate = (progress.to_generate + requested).saturating_sub(dropped);
+}
+
+/// Remove chunks beyond the radius plus the unload margin, despawn their meshes and write
+/// the edited ones to disk in the background
+pub fn unload_distant_chunks(
+    mut commands: Commands,
+    mut streaming: ResMut<ChunkStreaming>,
+    mut world: ResMut<VoxelWorld>,
+    mut unloaded: MessageWriter<ChunkUnloaded>,
+    storage: Res<WorldStorage>,
+    world_config: Res<WorldConfig>,
+) {
+    let Some(center) = streaming.center else {
+        return;
+    };
+    let settings = &world_config.streaming;
+
+    let distant: Vec<IVec3> = world
+        .loaded_chunk_positions()
+        .filter(|&pos| !in_radius(settings, center, pos, settings.unload_margin))
+        .collect();
+    for pos in distant {
+        let Some(chunk) = world.remove_chunk(pos) else {
+            continue;
+        };
+        for entity in [chunk.mesh_entity(), chunk.water_mesh_entity()].into_iter().flatten() {
+            commands.entity(entity).despawn();
+        }
+        if chunk.needs_save() {
+            storage.queue_chunks([chunk.to_data()]);
+            streaming.unsaved = true;
+        }
+        unloaded.write(ChunkUnloaded(pos));
+    }
+
+    if streaming.save_task.is_none() && streaming.unsaved {
+        streaming.unsaved = false;
+        let storage = storage.clone();
+        streaming.save_task = Some(IoTaskPool::get().spawn(async move {
+            storage.write_pending(|store, chunks| store.save_chunks(chunks))
+        }));
+    }
+}
+
+/// Collect the finished save of unloaded chunks. Unloaded chunks stay queued in
+/// `WorldStorage` until a save writes them.
+pub fn poll_evicted_saves(mut streaming: ResMut<ChunkStreaming>) {
+    let Some(task) = streaming.save_task.as_mut() else {
+        return;
+    };
+    let Some(result) = check_ready(task) else {
+        return;
+    };
+    streaming.save_task = None;
+    match result {
+        Ok(count) => debug!("Saved {} unloaded chunks", count),
+        Err(e) => warn!("Failed to save unloaded chunks: {}", e),
+    }
+}
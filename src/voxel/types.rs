@@ -1,34 +1,111 @@
+use std::fmt;
 use std::hash::Hash;
 use serde::{Serialize, Deserialize};
+use crate::entity::ItemType;
+use crate::voxel::registry;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug, Serialize, Deserialize)]
-#[repr(u8)]
-pub enum VoxelType {
-    #[default]
-    Air = 0,
-    TopSoil = 1,
-    SubSoil = 2,
-    Rock = 3,
-    Bedrock = 4,
-    Sand = 5,
-    Clay = 6,
-    Water = 7,
-    Wood = 8,
-    Leaves = 9,
-    DungeonWall = 10,
-    DungeonFloor = 11,
+/// Numeric voxel id. The built-in types are associated constants so generation code can
+/// refer to them by name; further types are assigned ids by the `VoxelRegistry`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
+pub struct VoxelType(pub u8);
+
+#[allow(non_upper_case_globals)]
+impl VoxelType {
+    pub const Air: VoxelType = VoxelType(0);
+    pub const TopSoil: VoxelType = VoxelType(1);
+    pub const SubSoil: VoxelType = VoxelType(2);
+    pub const Rock: VoxelType = VoxelType(3);
+    pub const Bedrock: VoxelType = VoxelType(4);
+    pub const Sand: VoxelType = VoxelType(5);
+    pub const Clay: VoxelType = VoxelType(6);
+    pub const Water: VoxelType = VoxelType(7);
+    pub const Wood: VoxelType = VoxelType(8);
+    pub const Leaves: VoxelType = VoxelType(9);
+    pub const DungeonWall: VoxelType = VoxelType(10);
+    pub const DungeonFloor: VoxelType = VoxelType(11);
+    /// Water spreading from a source, with its level in the density channel
+    pub const FlowingWater: VoxelType = VoxelType(12);
+    pub const Torch: VoxelType = VoxelType(13);
+    pub const CoalOre: VoxelType = VoxelType(14);
+    pub const IronOre: VoxelType = VoxelType(15);
+    pub const GoldOre: VoxelType = VoxelType(16);
 }
 
-#[derive(Clone, Debug)]
+/// YAML ids of the built-in types, indexed by numeric id
+pub const BUILTIN_VOXEL_NAMES: [&str; 17] = [
+    "air",
+    "topsoil",
+    "subsoil",
+    "rock",
+    "bedrock",
+    "sand",
+    "clay",
+    "water",
+    "wood",
+    "leaves",
+    "dungeon_wall",
+    "dungeon_floor",
+    "flowing_water",
+    "torch",
+    "coal_ore",
+    "iron_ore",
+    "gold_ore",
+];
+
+impl fmt::Debug for VoxelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            VoxelType::Air => "Air",
+            VoxelType::TopSoil => "TopSoil",
+            VoxelType::SubSoil => "SubSoil",
+            VoxelType::Rock => "Rock",
+            VoxelType::Bedrock => "Bedrock",
+            VoxelType::Sand => "Sand",
+            VoxelType::Clay => "Clay",
+            VoxelType::Water => "Water",
+            VoxelType::Wood => "Wood",
+            VoxelType::Leaves => "Leaves",
+            VoxelType::DungeonWall => "DungeonWall",
+            VoxelType::DungeonFloor => "DungeonFloor",
+            VoxelType::FlowingWater => "FlowingWater",
+            VoxelType::Torch => "Torch",
+            VoxelType::CoalOre => "CoalOre",
+            VoxelType::IronOre => "IronOre",
+            VoxelType::GoldOre => "GoldOre",
+            VoxelType(id) => return write!(f, "VoxelType({})", id),
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct VoxelTypeInfo {
+    pub name: String,
     pub solid: bool,
+    pub transparent: bool,
+    pub liquid: bool,
+    /// How long the block takes to break; negative means unbreakable
     pub hardness: f32,
     pub tool_required: ToolType,
     pub atlas_index: u8,
+    /// Per-face atlas overrides (default to `atlas_index`)
+    pub atlas_top: u8,
+    pub atlas_bottom: u8,
+    pub atlas_side: u8,
+    /// Triplanar splat slot used by surface nets (None for air/liquids)
+    pub splat_material: Option<u8>,
+    /// Surface nets draw the atlas tile over the splat material, for ores in rock
+    pub splat_overlay: bool,
+    /// Block light level the voxel gives off, 0 to `MAX_LIGHT`
+    pub light_emission: u8,
+    /// Item added to the inventory when the voxel is broken
+    pub drop: Option<ItemType>,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ToolType {
+    #[default]
     None,
     Shovel,
     Pickaxe,
@@ -40,39 +117,38 @@ pub trait Voxel {
     fn is_transparent(&self) -> bool;
     fn is_liquid(&self) -> bool;
     fn atlas_index(&self) -> u8;
+    fn splat_material(&self) -> Option<u8>;
+    fn splat_overlay(&self) -> bool;
+    fn light_emission(&self) -> u8;
 }
 
+// Backed by the installed VoxelRegistry so data-driven types behave like built-in ones
 impl Voxel for VoxelType {
     fn is_solid(&self) -> bool {
-        match self {
-            VoxelType::Air | VoxelType::Water => false,
-            _ => true,
-        }
+        registry::properties(*self).solid()
     }
 
     fn is_transparent(&self) -> bool {
-        matches!(self, VoxelType::Air | VoxelType::Water | VoxelType::Leaves)
+        registry::properties(*self).transparent()
     }
 
     fn is_liquid(&self) -> bool {
-        matches!(self, VoxelType::Water)
+        registry::properties(*self).liquid()
     }
 
     fn atlas_index(&self) -> u8 {
-        match self {
-            VoxelType::Air => 0,
-            VoxelType::TopSoil => 0,
-            VoxelType::SubSoil => 1,
-            VoxelType::Rock => 2,
-            VoxelType::Bedrock => 3,
-            VoxelType::Sand => 4,
-            VoxelType::Clay => 5,
-            VoxelType::Water => 6,
-            VoxelType::Wood => 8,
-            VoxelType::Leaves => 9,
-            VoxelType::DungeonWall => 10,
-            VoxelType::DungeonFloor => 11,
-        }
+        registry::properties(*self).atlas_index()
+    }
+
+    fn splat_material(&self) -> Option<u8> {
+        registry::properties(*self).splat_material()
     }
-}
 
+    fn splat_overlay(&self) -> bool {
+        registry::properties(*self).splat_overlay()
+    }
+
+    fn light_emission(&self) -> u8 {
+        registry::properties(*self).light_emission()
+    }
+}
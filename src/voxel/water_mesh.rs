@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use crate::constants::VOXEL_SIZE;
+use crate::environment::SEA_LEVEL;
+use crate::voxel::lighting;
+use crate::voxel::meshing::{ChunkSnapshot, MeshData};
+use crate::voxel::types::Voxel;
+
+/// Top of the voxel layer generated sea water fills. The bevy_water ocean plane stands in for
+/// the sea, so water faces below it in columns open to the sea are not meshed.
+const OCEAN_TOP: f32 = SEA_LEVEL + 1.0;
+
+/// Slack for faces that end exactly at the ocean top
+const OCEAN_EPSILON: f32 = 1e-3;
+
+/// Corners of a cell's top around the four cells sharing them, as (x, z) offsets
+const CORNER_CELLS: [(i32, i32); 4] = [(-1, -1), (0, -1), (-1, 0), (0, 0)];
+
+/// Mesh the water of a chunk, for both mesh modes. Each connected body gets one top surface
+/// whose corner heights are shared with the cells around them, so partially filled cells
+/// slope into their neighbors and meet the next chunk at the same heights. Sides and bottoms
+/// are only drawn against air. UV_1 carries the direction the water flows in for the shader,
+/// and the vertex colors the light of each cell.
+pub fn generate_water_mesh(chunk: &ChunkSnapshot) -> MeshData {
+    let mut mesh = MeshData::new();
+    let cell_size = VOXEL_SIZE * chunk.scale() as f32;
+    let origin = chunk.origin();
+
+    let size = chunk.size();
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                let local = IVec3::new(x, y, z);
+                let cell = origin + local;
+                if level(chunk, cell).is_none() {
+                    continue;
+                }
+
+                let has_top = !is_liquid(chunk, cell + IVec3::Y);
+                let heights = if has_top {
+                    TOP_CORNERS.map(|corner| corner_height(chunk, cell + corner))
+                } else {
+                    [1.0; 4]
+                };
+                let open_above = !chunk.get_voxel(cell + IVec3::Y).is_some_and(|voxel| voxel.is_solid());
+                let bottom_y = cell.y as f32 * cell_size;
+                let covered = |top: f32| {
+                    bottom_y + top * cell_size <= OCEAN_TOP + OCEAN_EPSILON && under_ocean(chunk, cell)
+                };
+
+                let light = chunk.average_light([cell]).unwrap_or(Vec2::ZERO);
+                let [r, g, b] = lighting::light_color(light.x, light.y);
+                let color = [r, g, b, 1.0];
+
+                // Water right under a block only shows if it does not reach the block
+                if has_top && (open_above || heights.iter().any(|&h| h < 1.0)) && !covered(max4(heights)) {
+                    add_top(&mut mesh, chunk, local, heights, cell_size, color);
+                }
+                if is_open(chunk, cell - IVec3::Y) && !covered(0.0) {
+                    add_bottom(&mut mesh, chunk, local, cell_size, color);
+                }
+                for side in SIDES {
+                    let (a, b) = (side.corners[0], side.corners[1]);
+                    let side_heights = [heights[a], heights[b]];
+                    if is_open(chunk, cell + side.normal) && !covered(side_heights[0].max(side_heights[1])) {
+                        add_side(&mut mesh, chunk, local, side, side_heights, cell_size, color);
+                    }
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+/// A vertical side of a water cell
+#[derive(Clone, Copy)]
+struct Side {
+    normal: IVec3,
+    /// Indices into the top corner heights, in the order that winds the quad outward
+    corners: [usize; 2],
+}
+
+const SIDES: [Side; 4] = [
+    Side { normal: IVec3::X, corners: [2, 1] },
+    Side { normal: IVec3::NEG_X, corners: [0, 3] },
+    Side { normal: IVec3::Z, corners: [3, 2] },
+    Side { normal: IVec3::NEG_Z, corners: [1, 0] },
+];
+
+/// Top corners of a cell in (x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1) order
+const TOP_CORNERS: [IVec3; 4] = [IVec3::ZERO, IVec3::X, IVec3::new(1, 0, 1), IVec3::Z];
+
+fn is_liquid(chunk: &ChunkSnapshot, cell: IVec3) -> bool {
+    chunk.get_voxel(cell).is_some_and(|voxel| voxel.is_liquid())
+}
+
+/// Water faces are drawn against air and other see-through cells that are neither solid
+/// nor water. Nothing is drawn toward the outside of the world.
+fn is_open(chunk: &ChunkSnapshot, cell: IVec3) -> bool {
+    chunk.get_voxel(cell).is_some_and(|voxel| !voxel.is_solid() && !voxel.is_liquid())
+}
+
+/// Water level of a cell as a fraction of the cell, None if it holds no liquid
+fn level(chunk: &ChunkSnapshot, cell: IVec3) -> Option<f32> {
+    if !is_liquid(chunk, cell) {
+        return None;
+    }
+    let mut level = chunk.get_fill(cell) as f32 / 255.0;
+
+    // Downsampled cells do not know how full they are; ones reaching past the ocean top are
+    // treated as filled up to it
+    if chunk.scale() > 1 {
+        let cell_size = VOXEL_SIZE * chunk.scale() as f32;
+        level = level.min((OCEAN_TOP - cell.y as f32 * cell_size) / cell_size).max(0.0);
+    }
+    Some(level)
+}
+
+/// Height of the water surface in a cell, 1 when more water sits on top of it
+fn surface(chunk: &ChunkSnapshot, cell: IVec3) -> Option<f32> {
+    let level = level(chunk, cell)?;
+    Some(if is_liquid(chunk, cell + IVec3::Y) { 1.0 } else { level })
+}
+
+/// Surface height at a top corner: the average of the water cells around it, or full if any
+/// of them has water on top
+fn corner_height(chunk: &ChunkSnapshot, corner: IVec3) -> f32 {
+    let mut sum = 0.0;
+    let mut count = 0;
+    for (dx, dz) in CORNER_CELLS {
+        let cell = corner + IVec3::new(dx, 0, dz);
+        if is_liquid(chunk, cell + IVec3::Y) && is_liquid(chunk, cell) {
+            return 1.0;
+        }
+        if let Some(height) = surface(chunk, cell) {
+            sum += height;
+            count += 1;
+        }
+    }
+    if count == 0 { 0.0 } else { sum / count as f32 }
+}
+
+/// Downhill direction of the surface at a top corner, in cells of height per cell. Cells
+/// without water take the corner height, so they do not tilt the surface.
+fn corner_slope(chunk: &ChunkSnapshot, corner: IVec3) -> Vec2 {
+    let height = corner_height(chunk, corner);
+    let [a, b, c, d] = CORNER_CELLS
+        .map(|(dx, dz)| surface(chunk, corner + IVec3::new(dx, 0, dz)).unwrap_or(height));
+    Vec2::new((a + c) - (b + d), (a + b) - (c + d)) * 0.5
+}
+
+/// Whether a water cell lies in a column of water that reaches up to the ocean top. Columns
+/// leaving the snapshot while still water are assumed to reach it.
+fn under_ocean(chunk: &ChunkSnapshot, mut cell: IVec3) -> bool {
+    let cell_size = VOXEL_SIZE * chunk.scale() as f32;
+    loop {
+        if (cell.y + 1) as f32 * cell_size >= OCEAN_TOP {
+            return true;
+        }
+        cell += IVec3::Y;
+        match chunk.get_voxel(cell) {
+            Some(voxel) if voxel.is_liquid() => continue,
+            Some(_) => return false,
+            None => return true,
+        }
+    }
+}
+
+/// Horizontal world position of a cell corner, used as the water's UVs
+fn world_xz(chunk: &ChunkSnapshot, local: IVec3, cell_size: f32) -> Vec2 {
+    let corner = (chunk.origin() + local).as_vec3() * cell_size;
+    Vec2::new(corner.x, corner.z)
+}
+
+fn push_quad(mesh: &mut MeshData, positions: [Vec3; 4], normals: [Vec3; 4], uvs: [Vec2; 4], flows: [Vec2; 4], color: [f32; 4]) {
+    let base = mesh.positions.len() as u32;
+    mesh.positions.extend(positions.map(|p| p.to_array()));
+    mesh.normals.extend(normals.map(|n| n.to_array()));
+    mesh.uvs.extend(uvs.map(|uv| uv.to_array()));
+    mesh.flows.extend(flows.map(|flow| flow.to_array()));
+    mesh.colors.extend([color; 4]);
+    mesh.indices.extend([base, base + 3, base + 2, base, base + 2, base + 1]);
+}
+
+fn add_top(mesh: &mut MeshData, chunk: &ChunkSnapshot, local: IVec3, heights: [f32; 4], cell_size: f32, color: [f32; 4]) {
+    let cell = chunk.origin() + local;
+    let positions = std::array::from_fn(|i| {
+        let corner = TOP_CORNERS[i];
+        Vec3::new((local.x + corner.x) as f32, local.y as f32 + heights[i], (local.z + corner.z) as f32) * cell_size
+    });
+    let slopes = TOP_CORNERS.map(|corner| corner_slope(chunk, cell + corner));
+    let normals = slopes.map(|slope| Vec3::new(slope.x, 1.0, slope.y).normalize());
+    let uvs = TOP_CORNERS.map(|corner| world_xz(chunk, local + corner, cell_size));
+    // Still water has no slope and no flow
+    let flows = slopes.map(|slope| slope.clamp_length_max(1.0));
+    push_quad(mesh, positions, normals, uvs, flows, color);
+}
+
+fn add_bottom(mesh: &mut MeshData, chunk: &ChunkSnapshot, local: IVec3, cell_size: f32, color: [f32; 4]) {
+    // Corners in reverse order so the quad faces down
+    let corners = [TOP_CORNERS[0], TOP_CORNERS[3], TOP_CORNERS[2], TOP_CORNERS[1]];
+    let positions = corners.map(|corner| (local + corner).as_vec3() * cell_size);
+    let uvs = corners.map(|corner| world_xz(chunk, local + corner, cell_size));
+    push_quad(mesh, positions, [Vec3::NEG_Y; 4], uvs, [Vec2::ZERO; 4], color);
+}
+
+fn add_side(
+    mesh: &mut MeshData,
+    chunk: &ChunkSnapshot,
+    local: IVec3,
+    side: Side,
+    heights: [f32; 2],
+    cell_size: f32,
+    color: [f32; 4],
+) {
+    let [a, b] = side.corners.map(|i| TOP_CORNERS[i]);
+    let bottom = |corner: IVec3| (local + corner).as_vec3() * cell_size;
+    let top = |corner: IVec3, height: f32| bottom(corner) + Vec3::Y * height * cell_size;
+    let positions = [bottom(a), top(a, heights[0]), top(b, heights[1]), bottom(b)];
+
+    // U runs along the side, V is the world height, and the water runs down the side
+    let chunk_origin = chunk.origin().as_vec3() * cell_size;
+    let uvs = positions.map(|p| {
+        let world = chunk_origin + p;
+        Vec2::new(if side.normal.x != 0 { world.z } else { world.x }, world.y)
+    });
+    push_quad(mesh, positions, [side.normal.as_vec3(); 4], uvs, [Vec2::NEG_Y; 4], color);
+}
+
+fn max4(values: [f32; 4]) -> f32 {
+    values.into_iter().fold(f32::MIN, f32::max)
+}
@@ -1,16 +1,80 @@
 use crate::constants::CHUNK_SIZE_I32;
-use crate::voxel::chunk::Chunk;
+use crate::voxel::chunk::{Chunk, ChunkData};
+use crate::voxel::lighting;
 use crate::voxel::types::VoxelType;
 use crate::voxel::persistence::WorldData;
 use bevy::prelude::*;
 use std::collections::HashMap;
 
+/// Seed for terrain generation, stored in the save header
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    /// Pseudo-random value in 0..=1 for a 2D lattice point, different for every seed.
+    /// Seed 0 gives the same values as the unseeded hash older worlds used.
+    pub fn hash(self, x: i32, z: i32) -> f32 {
+        let n = x.wrapping_mul(374761393).wrapping_add(z.wrapping_mul(668265263)) ^ self.salt();
+        let n = (n ^ (n >> 13)).wrapping_mul(1274126177);
+        ((n ^ (n >> 16)) as u32 as f32) / u32::MAX as f32
+    }
+
+    /// Pseudo-random value in 0..=1 for a 3D lattice point, different for every seed
+    pub fn hash3(self, x: i32, y: i32, z: i32) -> f32 {
+        self.hash(x, z.wrapping_add(y.wrapping_mul(1_103_515_245)))
+    }
+
+    /// Seed bits mixed so that nearby seeds give unrelated worlds (0 stays 0)
+    fn salt(self) -> i32 {
+        let mut x = self.0;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+        (x ^ (x >> 32)) as i32
+    }
+
+    /// Seed for a new world when none is configured
+    pub fn random() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        // Spread the clock bits out so seeds picked close together differ everywhere
+        Self(nanos.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(17))
+    }
+}
+
+/// Voxel storage totals across all loaded chunks
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WorldMemoryStats {
+    pub chunks: usize,
+    /// Chunks stored as a single value
+    pub uniform_chunks: usize,
+    pub bytes: usize,
+}
+
+/// Which chunk positions belong to the world
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoundsPolicy {
+    /// The box of `world_size_chunks` starting at the origin, generated up front
+    #[default]
+    Fixed,
+    /// Unlimited horizontally, `world_size_chunks.y` layers from y = 0. Chunks are
+    /// streamed in and out around the player.
+    Columns,
+}
+
 #[derive(Resource)]
 pub struct VoxelWorld {
     chunks: HashMap<IVec3, Chunk>,
     world_size_chunks: IVec3,
+    bounds: BoundsPolicy,
     #[allow(dead_code)]
     chunk_size: i32,
+    /// Chunks inserted since they were last lit
+    unlit: Vec<IVec3>,
+    /// Voxels whose light changed with an edit, relit by `lighting::update_lighting`
+    light_edits: Vec<IVec3>,
 }
 
 impl VoxelWorld {
@@ -18,7 +82,10 @@ impl VoxelWorld {
         Self {
             chunks: HashMap::new(),
             world_size_chunks: size_chunks,
+            bounds: BoundsPolicy::Fixed,
             chunk_size: CHUNK_SIZE_I32,
+            unlit: Vec::new(),
+            light_edits: Vec::new(),
         }
     }
 
@@ -35,10 +102,21 @@ impl VoxelWorld {
         self.chunks.contains_key(&chunk_pos)
     }
 
-    pub fn insert_chunk(&mut self, chunk: Chunk) {
+    pub fn insert_chunk(&mut self, mut chunk: Chunk) {
+        chunk.clear_light();
+        self.unlit.push(chunk.position());
         self.chunks.insert(chunk.position(), chunk);
     }
 
+    /// Take a chunk out of the world. Its mesh entities are left for the caller to despawn.
+    pub fn remove_chunk(&mut self, chunk_pos: IVec3) -> Option<Chunk> {
+        self.chunks.remove(&chunk_pos)
+    }
+
+    pub fn loaded_chunk_positions(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.chunks.keys().copied()
+    }
+
     // Voxel access (world coordinates)
     pub fn get_voxel(&self, world_pos: IVec3) -> Option<VoxelType> {
         let chunk_pos = Self::world_to_chunk(world_pos);
@@ -56,13 +134,111 @@ impl VoxelWorld {
         let local_pos = Self::world_to_local(world_pos);
 
         if let Some(chunk) = self.get_chunk_mut(chunk_pos) {
+            let old = chunk.get(local_pos);
             chunk.set(local_pos, voxel);
+            self.queue_light_edit(world_pos, old, voxel);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fill level at a world position, 0 empty to 255 full
+    pub fn get_density(&self, world_pos: IVec3) -> Option<u8> {
+        let chunk = self.get_chunk(Self::world_to_chunk(world_pos))?;
+        Some(chunk.density(Self::world_to_local(world_pos)))
+    }
+
+    /// Set the fill level at a world position, see `Chunk::set_density`
+    pub fn set_density(&mut self, world_pos: IVec3, density: u8, material: VoxelType) -> bool {
+        let chunk_pos = Self::world_to_chunk(world_pos);
+        let local_pos = Self::world_to_local(world_pos);
+
+        if let Some(chunk) = self.get_chunk_mut(chunk_pos) {
+            let old = chunk.get(local_pos);
+            chunk.set_density(local_pos, density, material);
+            let new = chunk.get(local_pos);
+            self.queue_light_edit(world_pos, old, new);
             true
         } else {
             false
         }
     }
 
+    /// Set a liquid and its fill at a world position, see `Chunk::set_liquid`
+    pub fn set_liquid(&mut self, world_pos: IVec3, liquid: VoxelType, fill: u8) -> bool {
+        let chunk_pos = Self::world_to_chunk(world_pos);
+        let local_pos = Self::world_to_local(world_pos);
+
+        if let Some(chunk) = self.get_chunk_mut(chunk_pos) {
+            let old = chunk.get(local_pos);
+            chunk.set_liquid(local_pos, liquid, fill);
+            self.queue_light_edit(world_pos, old, liquid);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn queue_light_edit(&mut self, world_pos: IVec3, old: VoxelType, new: VoxelType) {
+        if lighting::changes_light(old, new) {
+            self.light_edits.push(world_pos);
+        }
+    }
+
+    /// Packed sky and block light at a world position, None if the chunk is not loaded or
+    /// not lit yet
+    pub fn get_light(&self, world_pos: IVec3) -> Option<u8> {
+        let chunk = self.get_chunk(Self::world_to_chunk(world_pos))?;
+        chunk.light(Self::world_to_local(world_pos))
+    }
+
+    /// Set the packed light at a world position in a lit chunk
+    pub fn set_light(&mut self, world_pos: IVec3, light: u8) {
+        if let Some(chunk) = self.get_chunk_mut(Self::world_to_chunk(world_pos)) {
+            chunk.set_light(Self::world_to_local(world_pos), light);
+        }
+    }
+
+    /// Take up to `max` chunks waiting to be lit, highest first so light reaches the chunks
+    /// below from above before they are lit themselves
+    pub fn take_unlit_chunks(&mut self, max: usize) -> Vec<IVec3> {
+        self.unlit.sort_unstable_by_key(|pos| pos.y);
+        let start = self.unlit.len().saturating_sub(max);
+        self.unlit.drain(start..).rev().collect()
+    }
+
+    /// Take the voxels edited since the last call whose light has to be updated
+    pub fn take_light_edits(&mut self) -> Vec<IVec3> {
+        std::mem::take(&mut self.light_edits)
+    }
+
+    /// Light every loaded chunk again from scratch (e.g. after light emission or opacity of a
+    /// voxel type changed). Chunks keep their meshes until they are relit.
+    pub fn relight_all(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.clear_light();
+        }
+        self.unlit = self.chunks.keys().copied().collect();
+        self.light_edits.clear();
+    }
+
+    /// Mark every chunk within `reach` voxels of a world position dirty, diagonal
+    /// neighbors included
+    pub fn mark_dirty_around(&mut self, world_pos: IVec3, reach: i32) {
+        let min = Self::world_to_chunk(world_pos - IVec3::splat(reach));
+        let max = Self::world_to_chunk(world_pos + IVec3::splat(reach));
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    if let Some(chunk) = self.get_chunk_mut(IVec3::new(x, y, z)) {
+                        chunk.mark_dirty();
+                    }
+                }
+            }
+        }
+    }
+
     // Coordinate conversion
     pub fn world_to_chunk(world_pos: IVec3) -> IVec3 {
         IVec3::new(
@@ -92,12 +268,56 @@ impl VoxelWorld {
             .map(|(pos, _)| *pos)
     }
 
+    /// Queue every loaded chunk for remeshing (e.g. after the atlas layout changed)
+    pub fn mark_all_dirty(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.mark_dirty();
+        }
+    }
+
+    /// Snapshot chunks edited since their last save and clear their save flags.
+    /// Queue them with `WorldStorage::queue_chunks` so they are kept until written.
+    pub fn take_unsaved_chunks(&mut self) -> Vec<ChunkData> {
+        self.chunks
+            .values_mut()
+            .filter(|chunk| chunk.needs_save())
+            .map(|chunk| {
+                chunk.clear_needs_save();
+                chunk.to_data()
+            })
+            .collect()
+    }
+
+    pub fn mark_needs_save(&mut self, positions: &[IVec3]) {
+        for pos in positions {
+            if let Some(chunk) = self.chunks.get_mut(pos) {
+                chunk.mark_needs_save();
+            }
+        }
+    }
+
+    pub fn memory_stats(&self) -> WorldMemoryStats {
+        let mut stats = WorldMemoryStats::default();
+        for chunk in self.chunks.values() {
+            stats.chunks += 1;
+            stats.bytes += chunk.memory_stats().bytes;
+            if chunk.single_value().is_some() {
+                stats.uniform_chunks += 1;
+            }
+        }
+        stats
+    }
+
     pub fn all_chunk_positions(&self) -> impl Iterator<Item = IVec3> + '_ {
         // Generate all positions within world bounds
         // This is a naive implementation, might want to just iterate loaded chunks
         // But for Phase 1 we want to generate the whole world
         let start = IVec3::ZERO;
-        let end = self.world_size_chunks;
+        // A streamed world has no fixed set of chunks
+        let end = match self.bounds {
+            BoundsPolicy::Fixed => self.world_size_chunks,
+            BoundsPolicy::Columns => IVec3::ZERO,
+        };
         
         (start.x..end.x).flat_map(move |x| {
             (start.y..end.y).flat_map(move |y| {
@@ -113,26 +333,59 @@ impl VoxelWorld {
     }
 
     pub fn chunk_in_bounds(&self, chunk_pos: IVec3) -> bool {
-        chunk_pos.x >= 0 && chunk_pos.x < self.world_size_chunks.x &&
-        chunk_pos.y >= 0 && chunk_pos.y < self.world_size_chunks.y &&
-        chunk_pos.z >= 0 && chunk_pos.z < self.world_size_chunks.z
+        let in_height = chunk_pos.y >= 0 && chunk_pos.y < self.world_size_chunks.y;
+        match self.bounds {
+            BoundsPolicy::Fixed => {
+                in_height &&
+                chunk_pos.x >= 0 && chunk_pos.x < self.world_size_chunks.x &&
+                chunk_pos.z >= 0 && chunk_pos.z < self.world_size_chunks.z
+            }
+            BoundsPolicy::Columns => in_height,
+        }
+    }
+
+    pub fn bounds(&self) -> BoundsPolicy {
+        self.bounds
     }
 
-    /// Convert world to serializable data
+    pub fn set_bounds(&mut self, bounds: BoundsPolicy) {
+        self.bounds = bounds;
+    }
+
+    /// True once every in-bounds neighbor, diagonals included, is loaded.
+    /// Meshing waits for this so chunk borders are built against final terrain.
+    pub fn neighbors_loaded(&self, chunk_pos: IVec3) -> bool {
+        (-1..=1).all(|dx| {
+            (-1..=1).all(|dy| {
+                (-1..=1).all(|dz| {
+                    let neighbor = chunk_pos + IVec3::new(dx, dy, dz);
+                    !self.chunk_in_bounds(neighbor) || self.chunk_exists(neighbor)
+                })
+            })
+        })
+    }
+
+    /// True once the chunk and every loaded neighbor, diagonals included, have been lit, so
+    /// a mesh built now reads final light on both sides of its borders
+    pub fn neighbors_lit(&self, chunk_pos: IVec3) -> bool {
+        (-1..=1).all(|dx| {
+            (-1..=1).all(|dy| {
+                (-1..=1).all(|dz| {
+                    self.get_chunk(chunk_pos + IVec3::new(dx, dy, dz)).is_none_or(Chunk::is_lit)
+                })
+            })
+        }) && self.get_chunk(chunk_pos).is_some_and(Chunk::is_lit)
+    }
+
+    /// Convert world-level settings to serializable data (chunks are saved separately)
     pub fn to_data(&self) -> WorldData {
         WorldData {
             world_size_chunks: self.world_size_chunks,
-            chunks: self.chunks.values().map(|c| c.to_data()).collect(),
         }
     }
 
-    /// Create world from serializable data
+    /// Create an empty world from serializable data
     pub fn from_data(data: WorldData) -> Self {
-        let mut world = Self::new(data.world_size_chunks);
-        for chunk_data in data.chunks {
-            let chunk = Chunk::from_data(chunk_data);
-            world.insert_chunk(chunk);
-        }
-        world
+        Self::new(data.world_size_chunks)
     }
 }
@@ -0,0 +1,31 @@
+use voxel_builder::voxel::generation::BiomeMap;
+use voxel_builder::voxel::world::WorldSeed;
+
+#[test]
+fn biome_borders_blend_smoothly() {
+    let map = BiomeMap::builtin(WorldSeed(0x5ea_f1e1d));
+    let biomes = map.biomes();
+    let mut borders = 0;
+    for z in (0..1024).step_by(16) {
+        let mut previous: Option<(usize, f32, [f32; 3])> = None;
+        for x in 0..1024 {
+            let blend = map.blend(x, z);
+            let total: f32 = blend.weights.iter().map(|(_, weight)| weight).sum();
+            assert!((total - 1.0).abs() < 1e-4, "weights sum to {total} at {x}, {z}");
+
+            let offset = blend.mix(biomes, |biome| biome.height_offset);
+            let color = map.grass_color(x, z);
+            if let Some((previous_dominant, previous_offset, previous_color)) = previous {
+                if previous_dominant != blend.dominant() {
+                    borders += 1;
+                }
+                assert!((offset - previous_offset).abs() < 1.5, "height steps at {x}, {z}");
+                for (a, b) in color.iter().zip(previous_color) {
+                    assert!((a - b).abs() < 0.25, "grass color steps at {x}, {z}");
+                }
+            }
+            previous = Some((blend.dominant(), offset, color));
+        }
+    }
+    assert!(borders > 0, "no biome borders crossed");
+}
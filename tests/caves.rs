@@ -0,0 +1,100 @@
+use bevy::math::{IVec3, Vec3};
+use voxel_builder::voxel::generation::noise::WATER_LEVEL;
+use voxel_builder::voxel::generation::perlin::{fbm3, perlin3};
+use voxel_builder::voxel::generation::{NoiseGenerator, WorldGenerator, DUNGEON_CELL};
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::{VoxelWorld, WorldSeed};
+
+const SEED: WorldSeed = WorldSeed(0x5ea_f1e1d);
+
+const NEIGHBORS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+fn generated_world(size: IVec3) -> VoxelWorld {
+    let generator = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let mut world = VoxelWorld::new(size);
+    for x in 0..size.x {
+        for y in 0..size.y {
+            for z in 0..size.z {
+                world.insert_chunk(generator.generate(IVec3::new(x, y, z)));
+            }
+        }
+    }
+    world
+}
+
+#[test]
+fn perlin_noise_is_smooth_and_seeded() {
+    let mut differs = false;
+    for i in 0..500 {
+        let p = Vec3::new(i as f32 * 0.37, i as f32 * 0.11 - 20.0, i as f32 * -0.23);
+        let value = perlin3(SEED, p);
+        assert!(value.abs() <= 1.0, "{value} out of range at {p}");
+        assert_eq!(value, perlin3(SEED, p));
+        assert!((perlin3(SEED, p + Vec3::splat(0.001)) - value).abs() < 0.01, "jump at {p}");
+        assert!(fbm3(SEED, p, 3).abs() <= 1.0);
+        differs |= (perlin3(WorldSeed(1), p) - value).abs() > 0.01;
+    }
+    assert!(differs, "seeds give the same noise");
+    // Gradient noise is zero on the lattice
+    assert_eq!(perlin3(SEED, Vec3::new(3.0, -2.0, 7.0)), 0.0);
+}
+
+#[test]
+fn caves_are_dry_and_never_touch_water() {
+    let size = IVec3::new(6, 3, 6);
+    let world = generated_world(size);
+    let generator = NoiseGenerator::new(SEED);
+
+    let mut caves_below_sea = 0;
+    for x in 0..size.x * 16 {
+        for z in 0..size.z * 16 {
+            for y in 0..=WATER_LEVEL {
+                let pos = IVec3::new(x, y, z);
+                let Some(voxel) = world.get_voxel(pos) else { continue };
+                if voxel == VoxelType::Air && generator.is_cave(pos) {
+                    caves_below_sea += 1;
+                }
+                if voxel != VoxelType::Water {
+                    continue;
+                }
+                for neighbor in NEIGHBORS.map(|n| pos + n) {
+                    let air = world.get_voxel(neighbor) == Some(VoxelType::Air);
+                    assert!(
+                        !(air && neighbor.y <= WATER_LEVEL && generator.is_cave(neighbor)),
+                        "water at {} next to cave air at {}",
+                        pos,
+                        neighbor,
+                    );
+                }
+            }
+        }
+    }
+    assert!(caves_below_sea > 0, "no caves below sea level to check");
+}
+
+#[test]
+fn caves_leave_bedrock_and_dungeons_alone() {
+    let generator = NoiseGenerator::new(SEED);
+    for x in 0..200 {
+        for z in 0..200 {
+            for y in 0..=3 {
+                assert!(!generator.is_cave(IVec3::new(x, y, z)), "cave in bedrock at {x}, {y}, {z}");
+            }
+        }
+    }
+    // A dungeon with its walls and entrance shafts
+    let dungeon = (0..4)
+        .find_map(|cell| generator.dungeon_at(cell * DUNGEON_CELL, 0))
+        .expect("one of the first cells has a dungeon");
+    let (min, max) = (dungeon.bounds.min, dungeon.bounds.max);
+    for x in min.x..=max.x {
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                let pos = IVec3::new(x, y, z);
+                if dungeon.voxel_at(pos).is_some() {
+                    assert!(!generator.is_cave(pos), "cave cuts the dungeon at {x}, {y}, {z}");
+                }
+            }
+        }
+    }
+}
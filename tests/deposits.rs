@@ -0,0 +1,75 @@
+use bevy::math::{IVec3, UVec3};
+use voxel_builder::config::biomes::BiomesConfigFile;
+use voxel_builder::config::ores::OresConfigFile;
+use voxel_builder::config::structures::StructuresConfigFile;
+use voxel_builder::config::world::GeneratorSection;
+use voxel_builder::voxel::generation::{
+    BiomeMap, DepositGenerator, GeneratorContext, GeneratorError, NoiseGenerator, TerrainGenerators, WorldGenerator,
+};
+use voxel_builder::voxel::registry::VoxelRegistry;
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::WorldSeed;
+
+const SEED: WorldSeed = WorldSeed(0x0de_5eed);
+
+#[test]
+fn ores_only_replace_rock_within_their_depth_range() {
+    let registry = VoxelRegistry::builtin();
+    let ctx = GeneratorContext {
+        seed: SEED,
+        settings: &GeneratorSection::default(),
+        registry: &registry,
+        biomes: &BiomesConfigFile::default(),
+        ores: &OresConfigFile::default(),
+        structures: &StructuresConfigFile::default(),
+    };
+    let with_ores = TerrainGenerators::default().create(&ctx).unwrap();
+    let terrain = WorldGenerator::new(NoiseGenerator::new(SEED));
+
+    let mut counts = [0; 3];
+    for cz in -2..2 {
+        for cy in 0..3 {
+            for cx in -2..2 {
+                let pos = IVec3::new(cx, cy, cz);
+                let (ores, plain) = (with_ores.generate(pos), terrain.generate(pos));
+                for z in 0..16 {
+                    for y in 0..16 {
+                        for x in 0..16 {
+                            let local = UVec3::new(x, y, z);
+                            let (voxel, before) = (ores.get(local), plain.get(local));
+                            if voxel == before {
+                                continue;
+                            }
+                            let world_y = cy * 16 + y as i32;
+                            assert_eq!(before, VoxelType::Rock, "ore replaced {before:?} at y {world_y}");
+                            let (index, max_y) = match voxel {
+                                VoxelType::CoalOre => (0, 48),
+                                VoxelType::IronOre => (1, 30),
+                                VoxelType::GoldOre => (2, 40),
+                                other => panic!("deposit pass placed {other:?}"),
+                            };
+                            assert!((4..=max_y).contains(&world_y), "{voxel:?} at y {world_y}");
+                            counts[index] += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    assert!(counts.iter().all(|&count| count > 0), "ore counts {counts:?}");
+    assert!(counts[0] > counts[2], "coal is more common than gold: {counts:?}");
+}
+
+#[test]
+fn deposits_reject_unknown_names() {
+    let registry = VoxelRegistry::builtin();
+    let mut config = OresConfigFile::default();
+    config.deposits[0].biomes = vec!["tundra".to_string()];
+    let result = DepositGenerator::new(SEED, &config, BiomeMap::builtin(SEED), &registry);
+    assert!(matches!(result, Err(GeneratorError::UnknownBiome(id)) if id == "tundra"));
+
+    let mut config = OresConfigFile::default();
+    config.deposits[0].hosts = vec!["marble".to_string()];
+    let result = DepositGenerator::new(SEED, &config, BiomeMap::builtin(SEED), &registry);
+    assert!(matches!(result, Err(GeneratorError::UnknownVoxelType(id)) if id == "marble"));
+}
@@ -0,0 +1,150 @@
+use bevy::math::{IVec2, IVec3};
+use std::collections::{HashMap, HashSet, VecDeque};
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::generation::noise::WATER_LEVEL;
+use voxel_builder::voxel::generation::{Dungeon, NoiseGenerator, WorldGenerator, DUNGEON_CELL};
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::WorldSeed;
+
+const SEED: WorldSeed = WorldSeed(0xd_0e5);
+
+const NEIGHBORS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+fn dungeons(generator: &WorldGenerator) -> Vec<std::sync::Arc<Dungeon>> {
+    generator.dungeons_in(IVec2::splat(-3 * DUNGEON_CELL), IVec2::splat(3 * DUNGEON_CELL - 1))
+}
+
+#[test]
+fn layouts_are_seeded_and_stay_in_their_cell() {
+    let generator = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let again = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let other = WorldGenerator::new(NoiseGenerator::new(WorldSeed(SEED.0 + 1)));
+
+    let found = dungeons(&generator);
+    assert!(found.len() > 18 && found.len() < 36, "{} of 36 cells have a dungeon", found.len());
+    let rooms = |dungeons: &[std::sync::Arc<Dungeon>]| {
+        dungeons.iter().flat_map(|d| d.rooms.iter().map(|room| room.interior)).collect::<Vec<_>>()
+    };
+    assert_eq!(rooms(&found), rooms(&dungeons(&again)));
+    assert_ne!(rooms(&found), rooms(&dungeons(&other)));
+
+    for dungeon in &found {
+        let cell_min = dungeon.cell * DUNGEON_CELL;
+        let cell_max = cell_min + DUNGEON_CELL - 1;
+        assert!(dungeon.bounds.min.x >= cell_min.x && dungeon.bounds.max.x <= cell_max.x, "{:?}", dungeon.bounds);
+        assert!(dungeon.bounds.min.z >= cell_min.y && dungeon.bounds.max.z <= cell_max.y, "{:?}", dungeon.bounds);
+        assert!(dungeon.rooms.len() >= 2);
+        assert!(dungeon.corridors.len() >= dungeon.rooms.len() - 1);
+        for room in &dungeon.rooms {
+            assert!(room.floor_y() >= 3, "room floor below the bedrock top: {:?}", room.interior);
+            assert_eq!(generator.dungeon_at(room.spawn_point().x, room.spawn_point().z).unwrap().cell, dungeon.cell);
+        }
+    }
+    let floors: HashSet<i32> = found.iter().flat_map(|d| d.rooms.iter().map(|room| room.floor_y())).collect();
+    assert!(floors.len() > 2, "room floors vary in height: {floors:?}");
+}
+
+#[test]
+fn rooms_are_reachable_from_the_entrances_and_closed_in() {
+    let generator = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let mut entrances = 0;
+    for dungeon in dungeons(&generator) {
+        for entrance in &dungeon.entrances {
+            entrances += 1;
+            let start = entrance.opening();
+            let mut reached = HashSet::from([start]);
+            let mut queue = VecDeque::from([start]);
+            while let Some(pos) = queue.pop_front() {
+                for offset in NEIGHBORS {
+                    let next = pos + offset;
+                    match dungeon.voxel_at(next) {
+                        Some(VoxelType::Air) => {
+                            if reached.insert(next) {
+                                queue.push_back(next);
+                            }
+                        }
+                        Some(_) => {}
+                        None => {
+                            // The only way out is up through the top of a shaft
+                            let top = dungeon
+                                .entrances
+                                .iter()
+                                .any(|entrance| entrance.interior.contains(pos) && pos.y == entrance.interior.max.y);
+                            assert!(top && offset == IVec3::Y, "dungeon open at {pos} towards {offset}");
+                        }
+                    }
+                }
+            }
+            for room in &dungeon.rooms {
+                assert!(reached.contains(&room.spawn_point()), "room {:?} not reachable", room.interior);
+            }
+        }
+    }
+    assert!(entrances > 0);
+}
+
+#[test]
+fn entrances_open_on_dry_ground_and_no_water_reaches_inside() {
+    let noise = NoiseGenerator::new(SEED);
+    let generator = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let dungeon = (0..4)
+        .find_map(|cell| generator.dungeon_at(cell * DUNGEON_CELL, 0).filter(|d| !d.entrances.is_empty()))
+        .expect("one of the first cells has a dungeon with an entrance");
+
+    let mut chunks: HashMap<IVec3, Chunk> = HashMap::new();
+    let mut voxel = |pos: IVec3| {
+        let chunk_pos = pos.div_euclid(IVec3::splat(16));
+        let chunk = chunks.entry(chunk_pos).or_insert_with(|| generator.generate(chunk_pos));
+        chunk.get(pos.rem_euclid(IVec3::splat(16)).as_uvec3())
+    };
+
+    for entrance in &dungeon.entrances {
+        let opening = entrance.opening();
+        let ground = noise.terrain_height(opening.x, opening.z);
+        assert!(ground > WATER_LEVEL && ground < opening.y, "shaft top {} over ground {ground}", opening.y);
+        assert_eq!(voxel(opening), VoxelType::Air);
+        assert_eq!(voxel(opening + IVec3::Y), VoxelType::Air, "shaft at {opening} is covered");
+    }
+
+    let (min, max) = (dungeon.bounds.min, dungeon.bounds.max);
+    for x in min.x..=max.x {
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                let pos = IVec3::new(x, y, z);
+                let Some(expected) = dungeon.voxel_at(pos) else {
+                    continue;
+                };
+                assert_eq!(voxel(pos), expected, "dungeon changed at {pos}");
+                if expected != VoxelType::Air {
+                    continue;
+                }
+                for offset in NEIGHBORS {
+                    assert_ne!(voxel(pos + offset), VoxelType::Water, "water next to the dungeon at {pos}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn entrance_shafts_miss_the_corridors() {
+    let generator = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let found = generator.dungeons_in(IVec2::splat(-8 * DUNGEON_CELL), IVec2::splat(8 * DUNGEON_CELL - 1));
+    let mut entrances = 0;
+    for dungeon in &found {
+        for entrance in &dungeon.entrances {
+            entrances += 1;
+            let shaft = entrance.interior;
+            for z in shaft.min.z..=shaft.max.z {
+                for x in shaft.min.x..=shaft.max.x {
+                    let column = IVec2::new(x, z);
+                    assert!(
+                        !dungeon.corridors.iter().any(|corridor| corridor.covers(column)),
+                        "corridor through the entrance shaft at {column}"
+                    );
+                }
+            }
+        }
+    }
+    assert!(entrances > 50, "only {entrances} entrances");
+}
@@ -0,0 +1,82 @@
+use bevy::math::IVec3;
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::fluid::{water_level, FluidSimulation, SOURCE_LEVEL};
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::VoxelWorld;
+
+/// Height of the open ground water runs over
+const GROUND: i32 = 3;
+const SOURCE: IVec3 = IVec3::new(8, GROUND + 1, 8);
+
+/// A single chunk of rock up to `GROUND` with air above
+fn flat_world() -> VoxelWorld {
+    let mut world = VoxelWorld::new(IVec3::ONE);
+    world.insert_chunk(Chunk::new(IVec3::ZERO));
+    for z in 0..16 {
+        for y in 0..=GROUND {
+            for x in 0..16 {
+                world.set_voxel(IVec3::new(x, y, z), VoxelType::Rock);
+            }
+        }
+    }
+    world
+}
+
+/// Queue a voxel and its neighbors like an edit there does, then step until the water settles
+fn settle(world: &mut VoxelWorld, simulation: &mut FluidSimulation, edited: IVec3) {
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                simulation.schedule(edited + IVec3::new(x, y, z));
+            }
+        }
+    }
+    for _ in 0..100 {
+        if simulation.pending() == 0 {
+            return;
+        }
+        simulation.step(world, usize::MAX);
+    }
+    panic!("water still moving after 100 steps, {} updates pending", simulation.pending());
+}
+
+fn surface() -> impl Iterator<Item = IVec3> {
+    (0..16).flat_map(|z| (0..16).map(move |x| IVec3::new(x, GROUND + 1, z)))
+}
+
+#[test]
+fn water_spreads_over_open_ground() {
+    let mut world = flat_world();
+    let mut simulation = FluidSimulation::default();
+    world.set_voxel(SOURCE, VoxelType::Water);
+    settle(&mut world, &mut simulation, SOURCE);
+
+    // One level less per voxel away from the source, and it stays on the ground
+    for pos in surface() {
+        let distance = (pos - SOURCE).abs().element_sum() as u8;
+        let expected = SOURCE_LEVEL.saturating_sub(distance);
+        assert_eq!(water_level(&world, pos), expected, "water level at {pos}");
+        let voxel = world.get_voxel(pos).unwrap();
+        match distance {
+            0 => assert_eq!(voxel, VoxelType::Water),
+            d if d < SOURCE_LEVEL => assert_eq!(voxel, VoxelType::FlowingWater, "voxel at {pos}"),
+            _ => assert_eq!(voxel, VoxelType::Air, "voxel at {pos}"),
+        }
+        assert_eq!(world.get_voxel(pos + IVec3::Y), Some(VoxelType::Air));
+    }
+}
+
+#[test]
+fn water_drains_after_its_source_is_removed() {
+    let mut world = flat_world();
+    let mut simulation = FluidSimulation::default();
+    world.set_voxel(SOURCE, VoxelType::Water);
+    settle(&mut world, &mut simulation, SOURCE);
+    assert_eq!(water_level(&world, SOURCE + IVec3::new(3, 0, 2)), SOURCE_LEVEL - 5);
+
+    world.set_voxel(SOURCE, VoxelType::Air);
+    settle(&mut world, &mut simulation, SOURCE);
+    for pos in surface() {
+        assert_eq!(world.get_voxel(pos), Some(VoxelType::Air), "water left at {pos}");
+    }
+}
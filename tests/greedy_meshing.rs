@@ -0,0 +1,118 @@
+use bevy::math::{IVec3, UVec3, Vec3};
+use std::collections::{HashMap, HashSet};
+use voxel_builder::voxel::generation::{NoiseGenerator, WorldGenerator};
+use voxel_builder::voxel::lighting::light_chunk;
+use voxel_builder::voxel::meshing::{generate_chunk_mesh, generate_chunk_mesh_greedy, ChunkSnapshot, MeshData};
+use voxel_builder::voxel::types::{Voxel, VoxelType};
+use voxel_builder::voxel::world::{VoxelWorld, WorldSeed};
+
+/// A quad of a blocky mesh traced back to the cells it covers
+struct Quad {
+    normal: IVec3,
+    /// Atlas tile corner, as bits so it can be a map key
+    tile: [u32; 2],
+    colors: [[f32; 4]; 4],
+    cells: Vec<UVec3>,
+}
+
+fn quads(mesh: &MeshData) -> Vec<Quad> {
+    (0..mesh.positions.len() / 4)
+        .map(|quad| {
+            let vertices = quad * 4..quad * 4 + 4;
+            let normal = Vec3::from(mesh.normals[quad * 4]).round().as_ivec3();
+            let corners = mesh.positions[vertices.clone()].iter().map(|p| Vec3::from(*p).round().as_ivec3());
+            let min = corners.clone().fold(IVec3::MAX, IVec3::min);
+            let max = corners.fold(IVec3::MIN, IVec3::max);
+
+            // Faces pointing along an axis sit on the far side of their cells
+            let axis = normal.abs().max_position();
+            let (mut min_cell, mut max_cell) = (min, max - IVec3::ONE);
+            let layer = if normal[axis] > 0 { min[axis] - 1 } else { min[axis] };
+            min_cell[axis] = layer;
+            max_cell[axis] = layer;
+
+            let mut cells = Vec::new();
+            for z in min_cell.z..=max_cell.z {
+                for y in min_cell.y..=max_cell.y {
+                    for x in min_cell.x..=max_cell.x {
+                        cells.push(IVec3::new(x, y, z).as_uvec3());
+                    }
+                }
+            }
+
+            let uv = mesh.uvs[quad * 4 + 1];
+            assert_eq!((uv[0] * uv[1]) as usize, cells.len(), "UVs repeat the tile once per cell");
+            Quad {
+                normal,
+                tile: mesh.tile_origins[quad * 4].map(f32::to_bits),
+                colors: mesh.colors[vertices].try_into().unwrap(),
+                cells,
+            }
+        })
+        .collect()
+}
+
+/// Lit snapshots of a column of generated chunks with all their neighbors loaded
+fn terrain_snapshots() -> Vec<ChunkSnapshot> {
+    let size = IVec3::new(3, 4, 3);
+    let generator = WorldGenerator::new(NoiseGenerator::new(WorldSeed(42)));
+    let mut world = VoxelWorld::new(size);
+    let positions: Vec<IVec3> = world.all_chunk_positions().collect();
+    for &pos in &positions {
+        world.insert_chunk(generator.generate(pos));
+    }
+    // A few hand-placed voxels on the surface for type changes inside otherwise flat faces
+    for (x, z, voxel) in [(20, 20, VoxelType::Clay), (21, 20, VoxelType::Sand), (24, 27, VoxelType::Torch)] {
+        let top = (0..size.y * 16).rev().find(|&y| world.get_voxel(IVec3::new(x, y, z)).is_some_and(|v| v.is_solid()));
+        world.set_voxel(IVec3::new(x, top.unwrap(), z), voxel);
+    }
+    for &pos in &positions {
+        light_chunk(&mut world, pos, &mut HashSet::new());
+    }
+
+    (0..size.y)
+        .filter_map(|y| ChunkSnapshot::capture(&world, IVec3::new(1, y, 1)))
+        .collect()
+}
+
+#[test]
+fn greedy_meshes_cover_the_same_faces() {
+    let snapshots = terrain_snapshots();
+    assert_eq!(snapshots.len(), 4);
+
+    let mut merged = 0;
+    for snapshot in &snapshots {
+        let per_face = quads(&generate_chunk_mesh(snapshot).solid);
+        let greedy = quads(&generate_chunk_mesh_greedy(snapshot).solid);
+
+        // Same visible area for every face direction and atlas tile
+        let area = |quads: &[Quad]| {
+            let mut area: HashMap<(IVec3, [u32; 2]), usize> = HashMap::new();
+            for quad in quads {
+                *area.entry((quad.normal, quad.tile)).or_default() += quad.cells.len();
+            }
+            area
+        };
+        assert_eq!(area(&greedy), area(&per_face));
+
+        // Every merged quad covers cells of one voxel type whose own faces are shaded exactly
+        // like it, so no AO or light change is smeared across a merged quad
+        let faces: HashMap<(UVec3, IVec3), [[f32; 4]; 4]> =
+            per_face.iter().map(|quad| ((quad.cells[0], quad.normal), quad.colors)).collect();
+        for quad in &greedy {
+            let voxel = snapshot.get(quad.cells[0]);
+            for &cell in &quad.cells {
+                assert_eq!(snapshot.get(cell), voxel, "quad at {} crosses a voxel type change", quad.cells[0]);
+                assert_eq!(
+                    faces.get(&(cell, quad.normal)),
+                    Some(&quad.colors),
+                    "quad at {} crosses a shading change at {cell}",
+                    quad.cells[0]
+                );
+            }
+        }
+
+        merged += per_face.len() - greedy.len();
+    }
+    assert!(merged > 0, "greedy meshing merged no faces");
+}
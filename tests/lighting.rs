@@ -0,0 +1,122 @@
+use bevy::math::IVec3;
+use std::collections::HashSet;
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::lighting::{light_chunk, relight_voxel, LightChannel, MAX_LIGHT};
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::VoxelWorld;
+
+/// Topmost rock layer; open sky above it
+const GROUND: i32 = 11;
+/// Room carved out of the rock
+const CAVITY_MIN: IVec3 = IVec3::new(2, 3, 2);
+const CAVITY_MAX: IVec3 = IVec3::new(9, 6, 9);
+/// Column of the shaft from the cavity up to the ground
+const SHAFT: (i32, i32) = (8, 8);
+
+/// A single chunk of rock up to `GROUND` with a sealed cavity, and a shaft above the cavity
+/// up to `shaft_top`
+fn cave_world(shaft_top: Option<i32>) -> VoxelWorld {
+    let mut world = VoxelWorld::new(IVec3::ONE);
+    world.insert_chunk(Chunk::new(IVec3::ZERO));
+    for z in 0..16 {
+        for y in 0..=GROUND {
+            for x in 0..16 {
+                let pos = IVec3::new(x, y, z);
+                let in_cavity = pos.cmpge(CAVITY_MIN).all() && pos.cmple(CAVITY_MAX).all();
+                let in_shaft = (x, z) == SHAFT && y > CAVITY_MAX.y && shaft_top.is_some_and(|top| y <= top);
+                if !in_cavity && !in_shaft {
+                    world.set_voxel(pos, VoxelType::Rock);
+                }
+            }
+        }
+    }
+    world
+}
+
+fn lit(world: &mut VoxelWorld) {
+    light_chunk(world, IVec3::ZERO, &mut HashSet::new());
+}
+
+fn edit(world: &mut VoxelWorld, pos: IVec3, voxel: VoxelType) {
+    world.set_voxel(pos, voxel);
+    relight_voxel(world, pos, &mut HashSet::new());
+}
+
+fn level(world: &VoxelWorld, channel: LightChannel, pos: IVec3) -> u8 {
+    channel.level(world.get_light(pos).unwrap())
+}
+
+fn cavity() -> impl Iterator<Item = IVec3> {
+    (CAVITY_MIN.z..=CAVITY_MAX.z).flat_map(|z| {
+        (CAVITY_MIN.y..=CAVITY_MAX.y)
+            .flat_map(move |y| (CAVITY_MIN.x..=CAVITY_MAX.x).map(move |x| IVec3::new(x, y, z)))
+    })
+}
+
+#[test]
+fn light_spreads_into_a_cavity() {
+    let mut world = cave_world(Some(GROUND));
+    let torch = IVec3::new(3, 3, 3);
+    world.set_voxel(torch, VoxelType::Torch);
+    lit(&mut world);
+
+    // Full sky light falls down the shaft and on to the cavity floor, then fades sideways
+    let (x, z) = SHAFT;
+    for y in CAVITY_MIN.y..=GROUND {
+        assert_eq!(level(&world, LightChannel::Sky, IVec3::new(x, y, z)), MAX_LIGHT, "shaft at y {y}");
+    }
+    assert_eq!(level(&world, LightChannel::Sky, IVec3::new(2, 6, 2)), MAX_LIGHT - 12);
+    assert_eq!(level(&world, LightChannel::Sky, IVec3::new(8, 4, 5)), MAX_LIGHT - 3);
+
+    // Torch light fades one level per voxel
+    assert_eq!(level(&world, LightChannel::Block, torch), MAX_LIGHT);
+    assert_eq!(level(&world, LightChannel::Block, torch + IVec3::X), MAX_LIGHT - 1);
+    assert_eq!(level(&world, LightChannel::Block, torch + IVec3::new(2, 1, 3)), MAX_LIGHT - 6);
+
+    // Rock stays dark
+    assert_eq!(world.get_light(IVec3::new(0, 5, 0)), Some(0));
+    assert_eq!(world.get_light(IVec3::new(5, 2, 5)), Some(0));
+}
+
+#[test]
+fn removing_a_light_source_darkens_the_cavity() {
+    let mut world = cave_world(None);
+    let torch = IVec3::new(5, 4, 5);
+    world.set_voxel(torch, VoxelType::Torch);
+    lit(&mut world);
+    assert_eq!(level(&world, LightChannel::Block, IVec3::new(9, 6, 9)), MAX_LIGHT - 10);
+    assert!(cavity().all(|pos| level(&world, LightChannel::Sky, pos) == 0));
+
+    edit(&mut world, torch, VoxelType::Air);
+    for pos in cavity() {
+        assert_eq!(world.get_light(pos), Some(0), "light left at {pos}");
+    }
+}
+
+#[test]
+fn breaking_and_placing_blocks_relights() {
+    // The shaft ends one voxel below the ground, capped with rock
+    let mut world = cave_world(Some(GROUND - 1));
+    lit(&mut world);
+    assert!(cavity().all(|pos| world.get_light(pos) == Some(0)));
+
+    let (x, z) = SHAFT;
+    let cap = IVec3::new(x, GROUND, z);
+    edit(&mut world, cap, VoxelType::Air);
+    assert_eq!(level(&world, LightChannel::Sky, IVec3::new(x, CAVITY_MIN.y, z)), MAX_LIGHT);
+    assert_eq!(level(&world, LightChannel::Sky, IVec3::new(2, 6, 2)), MAX_LIGHT - 12);
+
+    // A block placed in the shaft shades everything below it again
+    edit(&mut world, IVec3::new(x, 8, z), VoxelType::Rock);
+    assert_eq!(level(&world, LightChannel::Sky, IVec3::new(x, 9, z)), MAX_LIGHT);
+    for pos in cavity() {
+        assert_eq!(level(&world, LightChannel::Sky, pos), 0, "sky light left at {pos}");
+    }
+
+    // A torch placed in the dark lights it up, and breaking it darkens it again
+    let torch = IVec3::new(4, 3, 4);
+    edit(&mut world, torch, VoxelType::Torch);
+    assert_eq!(level(&world, LightChannel::Block, torch + IVec3::Z), MAX_LIGHT - 1);
+    edit(&mut world, torch, VoxelType::Air);
+    assert!(cavity().all(|pos| world.get_light(pos) == Some(0)));
+}
@@ -0,0 +1,81 @@
+use bevy::math::{IVec3, UVec3};
+use voxel_builder::config::world::LodSection;
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::meshing::ChunkSnapshot;
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::VoxelWorld;
+
+#[test]
+fn lod_changes_only_past_the_hysteresis() {
+    let lod = LodSection { enabled: true, distances: [6.0, 12.0, 20.0], hysteresis: 0.5 };
+
+    // Moving away: a chunk keeps its level until it is the hysteresis past a threshold
+    assert_eq!(lod.next_level(0, 6.2), 0);
+    assert_eq!(lod.next_level(0, 6.6), 1);
+    assert_eq!(lod.next_level(1, 12.4), 1);
+    assert_eq!(lod.next_level(0, 25.0), 3);
+
+    // Moving closer: the level drops back only the hysteresis before the threshold
+    assert_eq!(lod.next_level(1, 5.8), 1);
+    assert_eq!(lod.next_level(1, 5.4), 0);
+    assert_eq!(lod.next_level(3, 19.6), 3);
+    assert_eq!(lod.next_level(3, 2.0), 0);
+
+    // Standing on a threshold never flips the level
+    for current in [0, 1] {
+        assert_eq!(lod.next_level(current, 6.0), current);
+    }
+
+    let disabled = LodSection { enabled: false, ..lod };
+    assert_eq!(disabled.next_level(2, 15.0), 0);
+}
+
+#[test]
+fn downsampled_cells_take_the_dominant_voxel() {
+    let mut world = VoxelWorld::new(IVec3::new(2, 1, 1));
+    world.insert_chunk(Chunk::new(IVec3::ZERO));
+    world.insert_chunk(Chunk::new(IVec3::X));
+
+    // Cell 0: half rock, half air
+    for x in 0..2 {
+        for z in 0..2 {
+            world.set_voxel(IVec3::new(x, 0, z), VoxelType::Rock);
+        }
+    }
+    // Cell 1: three rock, two sand, one water
+    let cell = IVec3::new(2, 0, 0);
+    for (offset, voxel) in [
+        (IVec3::new(0, 0, 0), VoxelType::Rock),
+        (IVec3::new(1, 0, 0), VoxelType::Rock),
+        (IVec3::new(0, 0, 1), VoxelType::Rock),
+        (IVec3::new(1, 0, 1), VoxelType::Sand),
+        (IVec3::new(0, 1, 0), VoxelType::Sand),
+        (IVec3::new(1, 1, 0), VoxelType::Water),
+    ] {
+        world.set_voxel(cell + offset, voxel);
+    }
+    // Cell 2: three rock and one water, too little solid but enough with the liquid
+    let cell = IVec3::new(4, 0, 0);
+    for offset in [IVec3::new(0, 0, 0), IVec3::new(1, 0, 0), IVec3::new(0, 0, 1)] {
+        world.set_voxel(cell + offset, VoxelType::Rock);
+    }
+    world.set_voxel(cell + IVec3::new(1, 0, 1), VoxelType::Water);
+    // Cell 3: a single solid voxel
+    world.set_voxel(IVec3::new(6, 0, 0), VoxelType::Rock);
+
+    let snapshot = ChunkSnapshot::capture_lod(&world, IVec3::ZERO, 1).unwrap().into_snapshot();
+    assert_eq!((snapshot.scale(), snapshot.size()), (2, 8));
+    assert_eq!(snapshot.get(UVec3::new(0, 0, 0)), VoxelType::Rock);
+    assert_eq!(snapshot.get(UVec3::new(1, 0, 0)), VoxelType::Rock);
+    assert_eq!(snapshot.get(UVec3::new(2, 0, 0)), VoxelType::Water);
+    assert_eq!(snapshot.get(UVec3::new(3, 0, 0)), VoxelType::Air);
+
+    // Padding reads the neighbor chunk, and is None where no chunk is loaded
+    world.set_voxel(IVec3::new(16, 0, 0), VoxelType::Rock);
+    for offset in [IVec3::new(1, 0, 0), IVec3::new(0, 0, 1), IVec3::new(1, 0, 1)] {
+        world.set_voxel(IVec3::new(16, 0, 0) + offset, VoxelType::Clay);
+    }
+    let snapshot = ChunkSnapshot::capture_lod(&world, IVec3::ZERO, 1).unwrap().into_snapshot();
+    assert_eq!(snapshot.get_voxel(IVec3::new(8, 0, 0)), Some(VoxelType::Clay));
+    assert_eq!(snapshot.get_voxel(IVec3::new(-1, 0, 0)), None);
+}
@@ -0,0 +1,116 @@
+use serde::Serialize;
+use voxel_builder::constants::CHUNK_VOLUME;
+use voxel_builder::voxel::palette::PalettedStorage;
+use voxel_builder::voxel::types::VoxelType;
+
+/// Set every voxel to one of `types` values and check the whole chunk reads back
+fn fill_with(storage: &mut PalettedStorage, expected: &mut [VoxelType], types: usize) {
+    for (index, voxel) in expected.iter_mut().enumerate() {
+        // Spread the types unevenly so every word holds a mix of indices
+        *voxel = VoxelType(((index * 7 + index / 64) % types) as u8);
+        storage.set(index, *voxel);
+    }
+    assert_voxels(storage, expected);
+}
+
+fn assert_voxels(storage: &PalettedStorage, expected: &[VoxelType]) {
+    for (index, voxel) in expected.iter().enumerate() {
+        assert_eq!(storage.get(index), *voxel, "voxel {index}");
+    }
+    assert!(storage.iter().eq(expected.iter().copied()));
+}
+
+#[test]
+fn indices_widen_and_collapse_with_the_palette() {
+    let mut storage = PalettedStorage::filled(VoxelType::Air);
+    let mut expected = vec![VoxelType::Air; CHUNK_VOLUME];
+    assert_eq!(storage.single_value(), Some(VoxelType::Air));
+    assert_eq!(storage.bits_per_voxel(), 0);
+
+    for (types, bits) in [(2, 1), (3, 2), (5, 4), (17, 8), (256, 8)] {
+        fill_with(&mut storage, &mut expected, types);
+        assert_eq!(storage.bits_per_voxel(), bits, "{types} types");
+        assert_eq!(storage.palette_len(), types);
+        assert_eq!(storage.single_value(), None);
+    }
+
+    // Going back down keeps the wide indices until one value fills the chunk again
+    for (types, compact_bits) in [(17, 8), (5, 4), (3, 2), (2, 1)] {
+        fill_with(&mut storage, &mut expected, types);
+        assert_eq!(storage.bits_per_voxel(), 8, "{types} types");
+        assert_eq!(storage.palette_len(), types);
+        assert_eq!(storage.compacted().bits_per_voxel(), compact_bits);
+    }
+    fill_with(&mut storage, &mut expected, 1);
+    assert_eq!(storage.single_value(), Some(VoxelType::Air));
+    assert_eq!(storage.bits_per_voxel(), 0);
+
+    // Collapses to whichever value filled it
+    storage.set(9, VoxelType::Rock);
+    assert_eq!(storage.bits_per_voxel(), 1);
+    for index in 0..CHUNK_VOLUME {
+        storage.set(index, VoxelType::Rock);
+    }
+    assert_eq!(storage.single_value(), Some(VoxelType::Rock));
+}
+
+#[test]
+fn unused_palette_entries_are_reused() {
+    let types = [VoxelType::Air, VoxelType::Rock, VoxelType::Sand, VoxelType::Clay];
+    let mut expected: Vec<VoxelType> = (0..CHUNK_VOLUME).map(|index| types[index % 4]).collect();
+    let mut storage = PalettedStorage::from_voxels(expected.iter().copied());
+    assert_eq!(storage.bits_per_voxel(), 2);
+
+    // Clearing the clay frees its entry, so water fits without widening the indices
+    for index in (3..CHUNK_VOLUME).step_by(4) {
+        storage.set(index, VoxelType::Air);
+        expected[index] = VoxelType::Air;
+    }
+    assert_eq!(storage.palette_len(), 3);
+    storage.set(0, VoxelType::Water);
+    expected[0] = VoxelType::Water;
+    assert_eq!(storage.bits_per_voxel(), 2);
+    assert_eq!(storage.palette_len(), 4);
+    assert_voxels(&storage, &expected);
+
+    // A fifth value in use needs wider indices
+    storage.set(1, VoxelType::Wood);
+    expected[1] = VoxelType::Wood;
+    assert_eq!(storage.bits_per_voxel(), 4);
+    assert_voxels(&storage, &expected);
+}
+
+/// `PalettedStorage` as it is serialized, to build inconsistent ones
+#[derive(Serialize)]
+struct RawPalette {
+    palette: Vec<VoxelType>,
+    bits: u8,
+    words: Vec<u64>,
+}
+
+fn deserialized(raw: &RawPalette) -> PalettedStorage {
+    bincode::deserialize(&bincode::serialize(raw).unwrap()).unwrap()
+}
+
+#[test]
+fn rebuild_counts_rejects_inconsistent_data() {
+    let storage = PalettedStorage::from_voxels((0..CHUNK_VOLUME).map(|index| VoxelType((index % 3) as u8)));
+    let mut round_trip: PalettedStorage = bincode::deserialize(&bincode::serialize(&storage).unwrap()).unwrap();
+    assert!(round_trip.rebuild_counts());
+    assert_eq!(round_trip.palette_len(), 3);
+    assert!(round_trip.iter().eq(storage.iter()));
+
+    let two = vec![VoxelType::Air, VoxelType::Rock];
+    assert!(deserialized(&RawPalette { palette: two.clone(), bits: 1, words: vec![u64::MAX; 64] }).rebuild_counts());
+    assert!(deserialized(&RawPalette { palette: vec![VoxelType::Air], bits: 0, words: Vec::new() }).rebuild_counts());
+
+    for (case, raw) in [
+        ("empty palette", RawPalette { palette: Vec::new(), bits: 0, words: Vec::new() }),
+        ("too few words", RawPalette { palette: two.clone(), bits: 1, words: vec![0; 63] }),
+        ("too many words", RawPalette { palette: two.clone(), bits: 1, words: vec![0; 65] }),
+        ("unsupported width", RawPalette { palette: two.clone(), bits: 3, words: vec![0; 192] }),
+        ("index past the palette", RawPalette { palette: two, bits: 2, words: vec![0b10; 128] }),
+    ] {
+        assert!(!deserialized(&raw).rebuild_counts(), "{case}");
+    }
+}
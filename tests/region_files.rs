@@ -0,0 +1,159 @@
+use bevy::math::{IVec3, UVec3};
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::persistence::region::{region_of, RegionStore, REGION_HEIGHT, REGION_SIZE};
+use voxel_builder::voxel::types::VoxelType;
+
+/// Magic and version before the two offset tables
+const HEADER_BYTES: u64 = 8;
+/// Generation and checksum, then offset and length per chunk
+const TABLE_BYTES: u64 = 16 + 12 * (REGION_SIZE * REGION_SIZE * REGION_HEIGHT) as u64;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("voxel_builder_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn region_path(dir: &Path, chunk_pos: IVec3) -> PathBuf {
+    let region = region_of(chunk_pos);
+    dir.join(format!("r.{}.{}.{}.region", region.x, region.y, region.z))
+}
+
+/// Chunk whose first voxel tells the saves apart
+fn chunk(pos: IVec3, voxel: VoxelType) -> Chunk {
+    let mut chunk = Chunk::new(pos);
+    chunk.set(UVec3::ZERO, voxel);
+    chunk.set(UVec3::new(5, 6, 7), VoxelType::Clay);
+    chunk
+}
+
+fn save(store: &mut RegionStore, chunks: &[(IVec3, VoxelType)]) {
+    let saved = store.save_chunks(chunks.iter().map(|&(pos, voxel)| chunk(pos, voxel).to_data())).unwrap();
+    assert_eq!(saved, chunks.len());
+}
+
+/// First voxel of a chunk read back by a freshly opened store
+fn reload(dir: &Path, pos: IVec3) -> Option<VoxelType> {
+    let chunk = RegionStore::new(dir).load_chunk(pos).unwrap()?;
+    assert_eq!(chunk.position(), pos);
+    assert_eq!(chunk.get(UVec3::new(5, 6, 7)), VoxelType::Clay);
+    Some(chunk.get(UVec3::ZERO))
+}
+
+#[test]
+fn chunks_round_trip_and_rewrite_in_place() {
+    let dir = temp_dir("region_round_trip");
+    let (a, b, c) = (IVec3::new(0, 0, 0), IVec3::new(3, 1, 4), IVec3::new(31, 7, 31));
+    let mut store = RegionStore::new(&dir);
+    save(&mut store, &[(a, VoxelType::Rock), (b, VoxelType::Sand), (c, VoxelType::Water)]);
+    drop(store);
+
+    assert_eq!(reload(&dir, a), Some(VoxelType::Rock));
+    assert_eq!(reload(&dir, b), Some(VoxelType::Sand));
+    assert_eq!(reload(&dir, c), Some(VoxelType::Water));
+    assert_eq!(reload(&dir, IVec3::new(1, 0, 0)), None);
+
+    // Rewriting one chunk leaves its neighbors in the same region as they were
+    let mut store = RegionStore::new(&dir);
+    save(&mut store, &[(b, VoxelType::Wood)]);
+    save(&mut store, &[(b, VoxelType::Leaves), (a, VoxelType::Bedrock)]);
+    drop(store);
+
+    assert_eq!(reload(&dir, a), Some(VoxelType::Bedrock));
+    assert_eq!(reload(&dir, b), Some(VoxelType::Leaves));
+    assert_eq!(reload(&dir, c), Some(VoxelType::Water));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_damaged_table_falls_back_to_the_other_one() {
+    let dir = temp_dir("region_torn_table");
+    let pos = IVec3::new(2, 2, 2);
+    let mut store = RegionStore::new(&dir);
+    // Creating the region commits an empty table to the first slot, so the first save
+    // commits to the second slot and the next one back to the first
+    save(&mut store, &[(pos, VoxelType::Rock)]);
+    save(&mut store, &[(pos, VoxelType::Sand)]);
+    drop(store);
+    assert_eq!(reload(&dir, pos), Some(VoxelType::Sand));
+
+    let path = region_path(&dir, pos);
+    let file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    let first_table = HEADER_BYTES;
+
+    // A flipped bit in the newest table fails its checksum
+    let mut byte = [0u8; 1];
+    file.read_exact_at(&mut byte, first_table + 100).unwrap();
+    file.write_all_at(&[byte[0] ^ 0x10], first_table + 100).unwrap();
+    assert_eq!(reload(&dir, pos), Some(VoxelType::Rock));
+
+    // A table torn halfway through its write, leaving other bytes in its second half
+    file.write_all_at(&[byte[0]], first_table + 100).unwrap();
+    assert_eq!(reload(&dir, pos), Some(VoxelType::Sand));
+    let half = TABLE_BYTES / 2;
+    file.write_all_at(&vec![0xA5; half as usize], first_table + half).unwrap();
+    assert_eq!(reload(&dir, pos), Some(VoxelType::Rock));
+
+    // With neither table intact the region cannot be read
+    file.write_all_at(&[0xFF; 8], first_table + TABLE_BYTES + 20).unwrap();
+    drop(file);
+    assert!(RegionStore::new(&dir).load_chunk(pos).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saving_leaves_other_regions_untouched() {
+    let dir = temp_dir("region_untouched");
+    let here = IVec3::new(1, 0, 1);
+    let elsewhere = IVec3::new(-5, 9, 40);
+    assert_ne!(region_of(here), region_of(elsewhere));
+
+    let mut store = RegionStore::new(&dir);
+    save(&mut store, &[(here, VoxelType::Rock), (elsewhere, VoxelType::Sand)]);
+    let before = fs::read(region_path(&dir, elsewhere)).unwrap();
+
+    save(&mut store, &[(here, VoxelType::Clay)]);
+    assert_eq!(fs::read(region_path(&dir, elsewhere)).unwrap(), before);
+    drop(store);
+
+    assert_eq!(reload(&dir, here), Some(VoxelType::Clay));
+    assert_eq!(reload(&dir, elsewhere), Some(VoxelType::Sand));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saved_chunk_positions_lists_every_region() {
+    let dir = temp_dir("region_positions");
+    assert!(RegionStore::new(&dir).saved_chunk_positions().unwrap().is_empty());
+
+    let mut positions = vec![
+        IVec3::new(0, 0, 0),
+        IVec3::new(31, 7, 31),
+        IVec3::new(32, 0, 0),
+        IVec3::new(-1, -1, -1),
+        IVec3::new(-33, 8, 70),
+        IVec3::new(5, 3, -12),
+    ];
+    let mut store = RegionStore::new(&dir);
+    let chunks: Vec<_> = positions.iter().map(|&pos| (pos, VoxelType::Rock)).collect();
+    save(&mut store, &chunks);
+    // Saving a chunk again does not list it twice
+    save(&mut store, &[(positions[1], VoxelType::Sand)]);
+    drop(store);
+
+    // Files that are not regions are ignored
+    fs::write(dir.join("metadata.yaml"), "name: test\n").unwrap();
+
+    let mut listed = RegionStore::new(&dir).saved_chunk_positions().unwrap();
+    listed.sort_by_key(|pos| pos.to_array());
+    positions.sort_by_key(|pos| pos.to_array());
+    assert_eq!(listed, positions);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,65 @@
+use bevy::math::{IVec3, UVec3};
+use std::fs;
+use std::path::PathBuf;
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::persistence::WorldStorage;
+use voxel_builder::voxel::types::VoxelType;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("voxel_builder_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn chunk_with(pos: IVec3, voxel: VoxelType) -> Chunk {
+    let mut chunk = Chunk::new(pos);
+    chunk.set(UVec3::ZERO, voxel);
+    chunk
+}
+
+#[test]
+fn queued_chunks_are_read_before_the_disk() {
+    let dir = temp_dir("save_queue_read");
+    let storage = WorldStorage::new(&dir);
+    let pos = IVec3::new(2, 0, -3);
+
+    storage.queue_chunks([chunk_with(pos, VoxelType::Rock).to_data()]);
+    storage.write_pending(|store, chunks| store.save_chunks(chunks)).unwrap();
+    assert!(!storage.has_pending());
+
+    // A newer snapshot wins over the disk until it is written, even when that write fails
+    storage.queue_chunks([chunk_with(pos, VoxelType::Sand).to_data()]);
+    let failed: Result<(), &str> = storage.write_pending(|_, _| Err("disk full"));
+    assert!(failed.is_err());
+    assert_eq!(storage.load_chunk(pos).unwrap().unwrap().get(UVec3::ZERO), VoxelType::Sand);
+    assert_eq!(storage.lock().load_chunk(pos).unwrap().unwrap().get(UVec3::ZERO), VoxelType::Rock);
+
+    storage.write_pending(|store, chunks| store.save_chunks(chunks)).unwrap();
+    assert!(storage.pending_chunk(pos).is_none());
+    assert_eq!(storage.lock().load_chunk(pos).unwrap().unwrap().get(UVec3::ZERO), VoxelType::Sand);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_write_keeps_snapshots_queued_while_it_ran() {
+    let dir = temp_dir("save_queue_order");
+    let storage = WorldStorage::new(&dir);
+    let pos = IVec3::new(0, 1, 0);
+
+    storage.queue_chunks([chunk_with(pos, VoxelType::Rock).to_data()]);
+    let other = storage.clone();
+    storage
+        .write_pending(|store, chunks| {
+            // The chunk is edited and queued again while the older snapshot is being written
+            other.queue_chunks([chunk_with(pos, VoxelType::Clay).to_data()]);
+            store.save_chunks(chunks)
+        })
+        .unwrap();
+
+    assert_eq!(storage.pending_chunk(pos).unwrap().voxels.get(0), VoxelType::Clay);
+    storage.write_pending(|store, chunks| store.save_chunks(chunks)).unwrap();
+    assert_eq!(storage.lock().load_chunk(pos).unwrap().unwrap().get(UVec3::ZERO), VoxelType::Clay);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
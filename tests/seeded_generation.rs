@@ -0,0 +1,35 @@
+use bevy::math::IVec3;
+use voxel_builder::voxel::generation::{NoiseGenerator, WorldGenerator};
+use voxel_builder::voxel::world::WorldSeed;
+
+fn chunk_bytes(seed: WorldSeed, pos: IVec3) -> Vec<u8> {
+    let generator = WorldGenerator::new(NoiseGenerator::new(seed));
+    bincode::serialize(&generator.generate(pos).to_data()).unwrap()
+}
+
+fn sample_positions() -> Vec<IVec3> {
+    let mut positions = Vec::new();
+    for x in [0, 3, 7] {
+        for y in 0..4 {
+            for z in [0, 5, 6] {
+                positions.push(IVec3::new(x, y, z));
+            }
+        }
+    }
+    positions
+}
+
+#[test]
+fn same_seed_gives_identical_chunks() {
+    let seed = WorldSeed(0x5eed_1234_abcd);
+    for pos in sample_positions() {
+        assert_eq!(chunk_bytes(seed, pos), chunk_bytes(seed, pos), "chunk {pos:?} differs");
+    }
+}
+
+#[test]
+fn different_seeds_give_different_terrain() {
+    let a: Vec<Vec<u8>> = sample_positions().into_iter().map(|pos| chunk_bytes(WorldSeed(1), pos)).collect();
+    let b: Vec<Vec<u8>> = sample_positions().into_iter().map(|pos| chunk_bytes(WorldSeed(2), pos)).collect();
+    assert_ne!(a, b);
+}
@@ -0,0 +1,125 @@
+use bevy::math::{IVec2, IVec3, Vec3Swizzles};
+use std::collections::{HashMap, HashSet};
+use voxel_builder::config::loader::ConfigError;
+use voxel_builder::config::structures::{ShapeDef, ShapeForm, StructuresConfigFile};
+use voxel_builder::voxel::chunk::Chunk;
+use voxel_builder::voxel::generation::noise::WATER_LEVEL;
+use voxel_builder::voxel::generation::{BiomeMap, GeneratorError, NoiseGenerator, StructureGenerator, WorldGenerator};
+use voxel_builder::voxel::registry::VoxelRegistry;
+use voxel_builder::voxel::types::VoxelType;
+use voxel_builder::voxel::world::WorldSeed;
+
+const SEED: WorldSeed = WorldSeed(0x0de_5eed);
+
+const TREES: [&str; 4] = ["oak", "tall_oak", "birch", "pine"];
+
+#[test]
+fn structures_stand_on_dry_ground() {
+    let noise = NoiseGenerator::new(SEED);
+    let mut templates = HashSet::new();
+    for z in -8..8 {
+        for x in -8..8 {
+            for structure in noise.structures_in(IVec2::new(x, z)).iter() {
+                let ground = noise.terrain_height(structure.anchor.x, structure.anchor.z);
+                assert_eq!(structure.anchor.y, ground + 1);
+                assert!(ground > WATER_LEVEL, "structure in the water at {}", structure.anchor);
+                assert_eq!(structure.anchor.x.div_euclid(16), x);
+                assert_eq!(structure.anchor.z.div_euclid(16), z);
+                templates.insert(noise.structures().template_id(structure).to_string());
+            }
+        }
+    }
+    for tree in TREES {
+        assert!(templates.contains(tree), "no {tree} in {templates:?}");
+    }
+}
+
+#[test]
+fn structures_spill_across_chunk_borders() {
+    let noise = NoiseGenerator::new(SEED);
+    let generator = WorldGenerator::new(NoiseGenerator::new(SEED));
+    let mut chunks: HashMap<IVec3, Chunk> = HashMap::new();
+    let mut voxel = |pos: IVec3| {
+        let chunk_pos = pos.div_euclid(IVec3::splat(16));
+        let chunk = chunks.entry(chunk_pos).or_insert_with(|| generator.generate(chunk_pos));
+        chunk.get(pos.rem_euclid(IVec3::splat(16)).as_uvec3())
+    };
+
+    // Structures may overlap, so only look at trees standing alone
+    let structures: Vec<_> = (-5..5)
+        .flat_map(|z| (-5..5).map(move |x| IVec2::new(x, z)))
+        .flat_map(|column| noise.structures_in(column).to_vec())
+        .collect();
+    let mut spilled = 0;
+    for z in -4..4 {
+        for x in -4..4 {
+            for structure in noise.structures_in(IVec2::new(x, z)).iter() {
+                let overlapped = structures
+                    .iter()
+                    .filter(|other| other.bounds.intersects(&structure.bounds))
+                    .count()
+                    > 1;
+                if overlapped || !TREES.contains(&noise.structures().template_id(structure)) {
+                    continue;
+                }
+                assert_eq!(voxel(structure.anchor), VoxelType::Wood, "no trunk at {}", structure.anchor);
+                assert!(!matches!(voxel(structure.anchor - IVec3::Y), VoxelType::Air | VoxelType::Water));
+
+                // Leaves of trees on a chunk border are in every chunk column the tree covers
+                let (min, max) = (structure.bounds.min, structure.bounds.max);
+                let corners = [min.xz(), max.xz(), IVec2::new(min.x, max.z), IVec2::new(max.x, min.z)];
+                let columns: HashSet<IVec2> = corners
+                    .into_iter()
+                    .map(|column| column.div_euclid(IVec2::splat(16)))
+                    .collect();
+                if columns.len() == 1 {
+                    continue;
+                }
+                let mut with_leaves = HashSet::new();
+                for lz in min.z..=max.z {
+                    for ly in min.y..=max.y {
+                        for lx in min.x..=max.x {
+                            if voxel(IVec3::new(lx, ly, lz)) == VoxelType::Leaves {
+                                with_leaves.insert(IVec2::new(lx, lz).div_euclid(IVec2::splat(16)));
+                            }
+                        }
+                    }
+                }
+                assert!(with_leaves.len() > 1, "tree at {} stays in one chunk", structure.anchor);
+                spilled += 1;
+            }
+        }
+    }
+    assert!(spilled > 0, "no tree crosses a chunk border");
+}
+
+#[test]
+fn structures_reject_bad_templates() {
+    let registry = VoxelRegistry::builtin();
+    let build = |config: &StructuresConfigFile| {
+        StructureGenerator::new(SEED, config, BiomeMap::builtin(SEED), &registry).map(|_| ())
+    };
+
+    let mut config = StructuresConfigFile::default();
+    config.placements[0].template = "willow".to_string();
+    assert!(matches!(config.validate(), Err(ConfigError::UnknownTemplate(id)) if id == "willow"));
+    assert!(matches!(build(&config), Err(GeneratorError::UnknownTemplate(id)) if id == "willow"));
+
+    let mut config = StructuresConfigFile::default();
+    config.placements[0].biomes = vec!["tundra".to_string()];
+    assert!(matches!(build(&config), Err(GeneratorError::UnknownBiome(id)) if id == "tundra"));
+
+    let mut config = StructuresConfigFile::default();
+    config.templates[0].shapes[0].voxel = "marble".to_string();
+    assert!(matches!(build(&config), Err(GeneratorError::UnknownVoxelType(id)) if id == "marble"));
+
+    let mut config = StructuresConfigFile::default();
+    config.templates[0].shapes.push(ShapeDef {
+        voxel: "leaves".to_string(),
+        form: ShapeForm::Box { min: [0, 0, 0], max: [20, 0, 0] },
+        replace: false,
+        chance: 1.0,
+    });
+    assert!(matches!(config.validate(), Err(ConfigError::TemplateTooWide { reach: 20, .. })));
+    assert!(matches!(build(&config), Err(GeneratorError::InvalidOptions(_))));
+}
@@ -0,0 +1,78 @@
+use bevy::math::{IVec3, Vec3};
+use voxel_builder::voxel::generation::{NoiseGenerator, WorldGenerator};
+use voxel_builder::voxel::meshing::{generate_chunk_mesh_surface_nets, ChunkSnapshot};
+use voxel_builder::voxel::world::{VoxelWorld, WorldSeed};
+
+const EPSILON: f32 = 1e-4;
+
+fn generated_world(size: IVec3) -> VoxelWorld {
+    let generator = WorldGenerator::new(NoiseGenerator::new(WorldSeed(0x5ea_f1e1d)));
+    let mut world = VoxelWorld::new(size);
+    for x in 0..size.x {
+        for y in 0..size.y {
+            for z in 0..size.z {
+                world.insert_chunk(generator.generate(IVec3::new(x, y, z)));
+            }
+        }
+    }
+    world
+}
+
+/// Triangles of a chunk's smooth mesh in world space
+fn world_triangles(world: &VoxelWorld, chunk_pos: IVec3) -> Vec<[Vec3; 3]> {
+    let snapshot = ChunkSnapshot::capture(world, chunk_pos).unwrap();
+    let mesh = generate_chunk_mesh_surface_nets(&snapshot).solid;
+    let offset = VoxelWorld::chunk_to_world(chunk_pos).as_vec3();
+    mesh.indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]].map(|i| Vec3::from_array(mesh.positions[i as usize]) + offset))
+        .collect()
+}
+
+fn edges(triangles: &[[Vec3; 3]]) -> Vec<(Vec3, Vec3)> {
+    triangles
+        .iter()
+        .flat_map(|[a, b, c]| [(*a, *b), (*b, *c), (*c, *a)])
+        .collect()
+}
+
+fn same_edge((a0, a1): (Vec3, Vec3), (b0, b1): (Vec3, Vec3)) -> bool {
+    (a0.abs_diff_eq(b0, EPSILON) && a1.abs_diff_eq(b1, EPSILON))
+        || (a0.abs_diff_eq(b1, EPSILON) && a1.abs_diff_eq(b0, EPSILON))
+}
+
+#[test]
+fn adjacent_chunks_share_boundary_vertices() {
+    let world = generated_world(IVec3::new(4, 4, 3));
+    let mut checked = 0;
+
+    for y in 0..4 {
+        let (a, b) = (IVec3::new(1, y, 1), IVec3::new(2, y, 1));
+        let a_edges = edges(&world_triangles(&world, a));
+        let b_edges = edges(&world_triangles(&world, b));
+
+        // Edges used by a single triangle of chunk A are where its mesh stops. On the side
+        // facing B, away from A's other borders, B's mesh has to continue from those exact edges.
+        let border = VoxelWorld::chunk_to_world(b).as_vec3();
+        let origin = VoxelWorld::chunk_to_world(a).as_vec3();
+        let seam_edges = a_edges.iter().filter(|&&edge| {
+            let open = a_edges.iter().filter(|&&other| same_edge(edge, other)).count() == 1;
+            let near_seam = |p: Vec3| {
+                p.x >= border.x - 1.0 - EPSILON
+                    && p.y > origin.y && p.y < origin.y + 15.0
+                    && p.z > origin.z && p.z < origin.z + 15.0
+            };
+            open && near_seam(edge.0) && near_seam(edge.1)
+        });
+
+        for &edge in seam_edges {
+            assert!(
+                b_edges.iter().any(|&other| same_edge(edge, other)),
+                "edge {edge:?} of chunk {a:?} has no matching edge in chunk {b:?}"
+            );
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "no terrain surface crosses the seam");
+}
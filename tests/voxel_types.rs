@@ -0,0 +1,69 @@
+use voxel_builder::config::hot_reload::VoxelTypesDiff;
+use voxel_builder::config::loader::ConfigError;
+use voxel_builder::voxel::registry::{VoxelRegistry, VoxelTypesFile};
+
+fn registry(yaml: &str) -> VoxelRegistry {
+    let file: VoxelTypesFile = serde_yaml::from_str(yaml).unwrap();
+    VoxelRegistry::from_file(&file).unwrap()
+}
+
+const CUSTOM: &str = "
+voxel_types:
+  - id: marble
+    solid: true
+    atlas_index: 15
+  - id: basalt
+    solid: true
+";
+
+#[test]
+fn reloads_may_edit_in_place_and_append() {
+    let old = registry(CUSTOM);
+    let new = registry(
+        "
+voxel_types:
+  - id: marble
+    solid: true
+    atlas_index: 16
+  - id: basalt
+    solid: true
+    hardness: 3.0
+  - id: slate
+    solid: true
+",
+    );
+
+    let diff = VoxelTypesDiff::between(&old, &new).unwrap();
+    assert_eq!(diff.added, vec![new.id("slate").unwrap()]);
+    assert_eq!(diff.visual_changed, vec![new.id("marble").unwrap()]);
+    assert_eq!(diff.gameplay_changed, vec![new.id("basalt").unwrap()]);
+}
+
+#[test]
+fn reloads_may_not_remove_or_renumber_types() {
+    let old = registry(CUSTOM);
+
+    // A type inserted before basalt moves it to the next id
+    let inserted = registry(
+        "
+voxel_types:
+  - id: marble
+    solid: true
+  - id: slate
+    solid: true
+  - id: basalt
+    solid: true
+",
+    );
+    let basalt = old.id("basalt").unwrap().0;
+    assert!(matches!(
+        VoxelTypesDiff::between(&old, &inserted),
+        Err(ConfigError::VoxelTypeRenumbered { id, from, to }) if id == "basalt" && from == basalt && to == basalt + 1
+    ));
+
+    let removed = registry("voxel_types:\n  - id: marble\n    solid: true\n");
+    assert!(matches!(
+        VoxelTypesDiff::between(&old, &removed),
+        Err(ConfigError::VoxelTypeRemoved(id)) if id == "basalt"
+    ));
+}